@@ -0,0 +1,253 @@
+//! Compares the graph's storage against the layout it replaced: vertices and edges in hash
+//! maps, with adjacency held as a pair of edge id lists on every vertex.
+//!
+//! Run with `cargo bench --bench adjacency`. The graph size can be set with BENCH_VERTICES
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use rust_graph_db::{graph::adjacency::Direction, DataResult, Database, Property, PropertyValue};
+
+const DEFAULT_VERTICES: usize = 100_000;
+const EDGES_PER_VERTEX: usize = 10;
+const TRAVERSAL_ROUNDS: usize = 5;
+
+/// Tracks bytes currently allocated, so memory use can be measured exactly
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Deterministic, well spread edge targets
+fn edge_target(from: usize, i: usize, vertex_count: usize) -> usize {
+    (from * 7919 + i * 104_729 + 1) % vertex_count
+}
+
+struct LegacyVertex {
+    label: String,
+    properties: Vec<Property>,
+    out_edge_ids: Vec<usize>,
+    in_edge_ids: Vec<usize>,
+}
+
+struct LegacyEdge {
+    from_vertex_id: usize,
+    to_vertex_id: usize,
+    edge_vertex: LegacyVertex,
+}
+
+fn legacy_vertex(label: &str, properties: Vec<Property>) -> LegacyVertex {
+    LegacyVertex {
+        label: label.to_string(),
+        properties,
+        out_edge_ids: Vec::new(),
+        in_edge_ids: Vec::new(),
+    }
+}
+
+fn age(value: i32) -> Vec<Property> {
+    vec![Property {
+        name: "age".to_string(),
+        value: PropertyValue::Int32(value),
+        flagged_for_removal: false,
+    }]
+}
+
+struct Measurement {
+    bytes_per_vertex: f64,
+    bytes_per_edge: f64,
+    edges_per_second: f64,
+}
+
+fn bench_legacy(vertex_count: usize) -> Measurement {
+    let start = allocated();
+    let mut vertices: HashMap<usize, LegacyVertex> = HashMap::new();
+    for id in 0..vertex_count {
+        vertices.insert(id, legacy_vertex("person", age(id as i32)));
+    }
+    let after_vertices = allocated();
+
+    let mut edges: HashMap<usize, LegacyEdge> = HashMap::new();
+    for from in 0..vertex_count {
+        for i in 0..EDGES_PER_VERTEX {
+            let to = edge_target(from, i, vertex_count);
+            let id = edges.len();
+            edges.insert(
+                id,
+                LegacyEdge {
+                    from_vertex_id: from,
+                    to_vertex_id: to,
+                    edge_vertex: legacy_vertex("knows", Vec::new()),
+                },
+            );
+            vertices.get_mut(&from).unwrap().out_edge_ids.push(id);
+            vertices.get_mut(&to).unwrap().in_edge_ids.push(id);
+        }
+    }
+    let after_edges = allocated();
+
+    let timer = Instant::now();
+    let mut checksum = 0;
+    for _ in 0..TRAVERSAL_ROUNDS {
+        for id in 0..vertex_count {
+            for edge_id in &vertices[&id].out_edge_ids {
+                checksum += edges[edge_id].to_vertex_id;
+            }
+        }
+    }
+    let elapsed = timer.elapsed();
+    black_box(checksum);
+
+    // Keep the unread fields from being optimised away
+    black_box(
+        vertices
+            .values()
+            .map(|v| v.label.len() + v.properties.len() + v.in_edge_ids.len())
+            .sum::<usize>(),
+    );
+    black_box(
+        edges
+            .values()
+            .map(|e| e.from_vertex_id + e.edge_vertex.label.len())
+            .sum::<usize>(),
+    );
+
+    let edge_count = vertex_count * EDGES_PER_VERTEX;
+    Measurement {
+        bytes_per_vertex: (after_vertices - start) as f64 / vertex_count as f64,
+        bytes_per_edge: (after_edges - after_vertices) as f64 / edge_count as f64,
+        edges_per_second: (edge_count * TRAVERSAL_ROUNDS) as f64 / elapsed.as_secs_f64(),
+    }
+}
+
+fn bench_current(vertex_count: usize) -> (Measurement, f64) {
+    let database = Database::new();
+    let handle = database.create_graph("bench").unwrap();
+
+    let start = allocated();
+    let mut ids = Vec::with_capacity(vertex_count);
+    for id in 0..vertex_count {
+        ids.push(
+            handle
+                .add_vertex("person")
+                .property("age", id as i32)
+                .insert()
+                .unwrap(),
+        );
+    }
+    let after_vertices = allocated() - ids.capacity() * std::mem::size_of::<usize>();
+
+    for from in 0..vertex_count {
+        for i in 0..EDGES_PER_VERTEX {
+            let to = edge_target(from, i, vertex_count);
+            handle
+                .add_edge("knows", ids[from], ids[to])
+                .insert()
+                .unwrap();
+        }
+    }
+    let after_edges = allocated() - ids.capacity() * std::mem::size_of::<usize>();
+
+    let edge_count = vertex_count * EDGES_PER_VERTEX;
+    let graph = handle.read().unwrap();
+
+    let timer = Instant::now();
+    let mut checksum = 0;
+    for _ in 0..TRAVERSAL_ROUNDS {
+        for id in &ids {
+            for edge in graph.adjacent_edges(id, Direction::Out).unwrap() {
+                checksum += edge.vertex_id;
+            }
+        }
+    }
+    let elapsed = timer.elapsed();
+    black_box(checksum);
+    drop(graph);
+
+    // The same traversal through the query language, which also copies out the vertices
+    let timer = Instant::now();
+    for _ in 0..TRAVERSAL_ROUNDS {
+        match database.query("bench.V().out()").unwrap() {
+            DataResult::VertexVector(vertices) => assert_eq!(vertices.len(), edge_count),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+    let query_elapsed = timer.elapsed();
+
+    let measurement = Measurement {
+        bytes_per_vertex: (after_vertices - start) as f64 / vertex_count as f64,
+        bytes_per_edge: (after_edges - after_vertices) as f64 / edge_count as f64,
+        edges_per_second: (edge_count * TRAVERSAL_ROUNDS) as f64 / elapsed.as_secs_f64(),
+    };
+    let query_edges_per_second =
+        (edge_count * TRAVERSAL_ROUNDS) as f64 / query_elapsed.as_secs_f64();
+
+    (measurement, query_edges_per_second)
+}
+
+fn report(name: &str, measurement: &Measurement) {
+    println!(
+        "{:<10} {:>12.1} {:>12.1} {:>18.0}",
+        name,
+        measurement.bytes_per_vertex,
+        measurement.bytes_per_edge,
+        measurement.edges_per_second
+    );
+}
+
+fn main() {
+    let vertex_count = std::env::var("BENCH_VERTICES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_VERTICES);
+
+    println!(
+        "{} vertices, {} edges, {} traversal rounds\n",
+        vertex_count,
+        vertex_count * EDGES_PER_VERTEX,
+        TRAVERSAL_ROUNDS
+    );
+    println!(
+        "{:<10} {:>12} {:>12} {:>18}",
+        "layout", "bytes/vertex", "bytes/edge", "out() edges/sec"
+    );
+
+    report("legacy", &bench_legacy(vertex_count));
+
+    let (current, query_edges_per_second) = bench_current(vertex_count);
+    report("current", &current);
+
+    println!(
+        "\nV().out() query, including copying out results: {:.0} edges/sec",
+        query_edges_per_second
+    );
+}
@@ -0,0 +1,207 @@
+mod journal;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    error::GraphDbError,
+    executor::{
+        parameters::{Parameters, PreparedQuery},
+        Command, CommandType, Executor,
+    },
+    graph::{
+        builder::{EdgeBuilder, VertexBuilder},
+        changes::{ChangeFeed, SessionId},
+        edge::Edge,
+        options::GraphOptions,
+        read_lock,
+        vertex::Vertex,
+        write_lock, DataResult, Graph, GraphFactory, SharedGraph,
+    },
+    parser::parse,
+};
+
+pub use self::journal::Journal;
+
+/// Entry point for embedding the database. Holds every graph, and runs queries against them.
+/// A `Database` can be shared between threads, e.g. in an `Arc`
+pub struct Database {
+    executor: Executor,
+    prepared: Mutex<HashMap<String, Arc<PreparedQuery>>>, // query text, parsed query
+}
+
+/// Prepared queries kept, so queries run again and again are only parsed once
+const PREPARED_QUERY_CAPACITY: usize = 1024;
+
+impl Database {
+    pub fn new() -> Self {
+        Database {
+            executor: Executor::new(GraphFactory::new()),
+            prepared: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Parses and runs a command, using the same syntax as the REPL. E.g. `graph.V().hasLabel(person)`
+    pub fn query(&self, query: &str) -> Result<DataResult, GraphDbError> {
+        self.query_with(query, &Parameters::new())
+    }
+
+    /// Runs a command with values for its placeholders, e.g. `graph.V().hasPropertyValue(name, $name)`.
+    /// Values are never written into the command's text, so may hold dots, commas and brackets
+    pub fn query_with(
+        &self,
+        query: &str,
+        parameters: &Parameters,
+    ) -> Result<DataResult, GraphDbError> {
+        let prepared = self.prepare(query)?;
+        self.execute_prepared(&prepared, parameters)
+    }
+
+    /// Parses a command once, to be run with `execute_prepared`. Recently prepared queries are
+    /// cached, so preparing the same text again is cheap
+    pub fn prepare(&self, query: &str) -> Result<Arc<PreparedQuery>, GraphDbError> {
+        let query = query.trim();
+        if let Some(prepared) = self.prepared_queries().get(query) {
+            return Ok(Arc::clone(prepared));
+        }
+
+        let prepared = Arc::new(PreparedQuery::new(parse(query.to_string())?)?);
+        let mut prepared_queries = self.prepared_queries();
+        if prepared_queries.len() >= PREPARED_QUERY_CAPACITY {
+            prepared_queries.clear();
+        }
+        prepared_queries.insert(query.to_string(), Arc::clone(&prepared));
+
+        Ok(prepared)
+    }
+
+    pub fn execute_prepared(
+        &self,
+        prepared: &PreparedQuery,
+        parameters: &Parameters,
+    ) -> Result<DataResult, GraphDbError> {
+        self.executor.execute(prepared.bind(parameters)?, None)
+    }
+
+    /// Runs a command for a session, which the changes it makes are attributed to, so a capture
+    /// for the session collects them. See `ChangeFeed::capture`
+    pub fn query_in_session(
+        &self,
+        query: &str,
+        session: SessionId,
+    ) -> Result<DataResult, GraphDbError> {
+        let prepared = self.prepare(query)?;
+        self.executor
+            .execute(prepared.bind(&Parameters::new())?, Some(session))
+    }
+
+    pub fn execute(&self, command: Command) -> Result<DataResult, GraphDbError> {
+        self.executor.execute(command, None)
+    }
+
+    pub fn create_graph(&self, graph_name: &str) -> Result<GraphHandle, GraphDbError> {
+        self.create_graph_with_options(graph_name, GraphOptions::default())
+    }
+
+    /// Creates a graph set up with the given options, e.g. an undirected simple graph, or one kept
+    /// in a file
+    pub fn create_graph_with_options(
+        &self,
+        graph_name: &str,
+        options: GraphOptions,
+    ) -> Result<GraphHandle, GraphDbError> {
+        self.executor.execute(
+            Command {
+                command_type: CommandType::CreateGraph(graph_name.to_string(), options),
+                command_json: None,
+            },
+            None,
+        )?;
+
+        self.graph(graph_name)
+    }
+
+    pub fn graph(&self, graph_name: &str) -> Result<GraphHandle, GraphDbError> {
+        let graph = self.executor.graph_factory().get_graph(graph_name)?;
+        Ok(GraphHandle { graph })
+    }
+
+    pub fn graph_factory(&self) -> &GraphFactory {
+        self.executor.graph_factory()
+    }
+
+    /// Every change made to the database's graphs, for subscribers and sinks to follow
+    pub fn changes(&self) -> &ChangeFeed {
+        self.executor.graph_factory().changes()
+    }
+
+    fn prepared_queries(&self) -> MutexGuard<'_, HashMap<String, Arc<PreparedQuery>>> {
+        // The cache is only ever inserted into or cleared, so is whole even if a holder panicked
+        match self.prepared.lock() {
+            Ok(prepared) => prepared,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Typed access to a single graph. Handles can be cloned and sent to other threads. Each call
+/// locks the graph for just as long as it needs
+#[derive(Clone)]
+pub struct GraphHandle {
+    graph: SharedGraph,
+}
+
+impl GraphHandle {
+    /// Starts building a vertex. Nothing is added until `insert` is called
+    pub fn add_vertex(&self, label: &str) -> VertexBuilder<'_> {
+        VertexBuilder::new(&self.graph, label)
+    }
+
+    /// Starts building an edge between two existing vertices. Nothing is added until `insert` is called
+    pub fn add_edge(
+        &self,
+        label: &str,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+    ) -> EdgeBuilder<'_> {
+        EdgeBuilder::new(&self.graph, label, from_vertex_id, to_vertex_id)
+    }
+
+    pub fn vertex(&self, id: usize) -> Result<Vertex, GraphDbError> {
+        match self.read()?.get_vertex(&id)? {
+            DataResult::Vertex(vertex) => Ok(vertex),
+            _ => Err(GraphDbError::Internal("Mismatched return type".to_string())),
+        }
+    }
+
+    pub fn edge(&self, id: usize) -> Result<Edge, GraphDbError> {
+        match self.read()?.get_edge(&id)? {
+            DataResult::Edge(edge) => Ok(edge),
+            _ => Err(GraphDbError::Internal("Mismatched return type".to_string())),
+        }
+    }
+
+    /// Removes the vertex and every edge attached to it
+    pub fn remove_vertex(&self, id: usize) -> Result<(), GraphDbError> {
+        self.write()?.remove_vertex(&id)?;
+        Ok(())
+    }
+
+    /// Locks the graph for reading. Writers wait until the guard is dropped
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, Box<dyn Graph>>, GraphDbError> {
+        read_lock(&self.graph)
+    }
+
+    /// Locks the graph for writing. Readers and other writers wait until the guard is dropped
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, Box<dyn Graph>>, GraphDbError> {
+        write_lock(&self.graph)
+    }
+}
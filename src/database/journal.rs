@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use crate::{
+    error::GraphDbError,
+    graph::{
+        changes::{Change, ChangeEvent, SessionId},
+        write_lock_for, DataResult,
+    },
+};
+
+use super::Database;
+
+/// Steps kept for undoing, after which the oldest are forgotten
+const DEFAULT_JOURNAL_CAPACITY: usize = 100;
+
+/// Undo and redo for a single user's session, such as the REPL's. Commands run through the
+/// journal have the changes they make journaled as one step, so the effects of triggers are
+/// undone along with the command. Changes made by anyone else are never journaled, so are never
+/// undone.
+///
+/// Clearing, loading, dropping or renaming a graph can't be undone, and steps before it may no
+/// longer apply, so forgets every step
+pub struct Journal {
+    undo: VecDeque<Vec<ChangeEvent>>, // oldest first
+    redo: Vec<Vec<ChangeEvent>>,      // most recently undone last
+    capacity: usize,
+    session: SessionId, // the session commands run through the journal are run for
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_JOURNAL_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Journal {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            capacity,
+            session: SessionId::new(),
+        }
+    }
+
+    /// Runs a command, journaling the changes it made as one step
+    pub fn query(&mut self, database: &Database, query: &str) -> Result<DataResult, GraphDbError> {
+        let (result, step) = database.changes().capture(self.session, || {
+            database.query_in_session(query, self.session)
+        })?;
+        self.record(step);
+        result
+    }
+
+    /// A new step can't be followed by redoing older ones, so they're forgotten
+    fn record(&mut self, step: Vec<ChangeEvent>) {
+        if step.is_empty() {
+            return;
+        }
+
+        match step.iter().all(|event| is_reversible(&event.change)) {
+            true => {
+                self.push_undo(step);
+                self.redo.clear();
+            }
+            false => {
+                self.undo.clear();
+                self.redo.clear();
+            }
+        }
+    }
+
+    /// Reverses the latest step, restoring removed vertices and edges under the ids they had. If
+    /// a change can't be reversed, it and the changes before it are left to undo later
+    pub fn undo(&mut self, database: &Database) -> Result<DataResult, GraphDbError> {
+        let step = match self.undo.pop_back() {
+            Some(step) => step,
+            None => return Err(GraphDbError::InvalidArgument("Nothing to undo".to_string())),
+        };
+
+        // What reversing the step changed is itself the step that redoes it
+        let ((reversed, result), reversal) = database
+            .changes()
+            .capture(self.session, || reverse(database, self.session, &step))?;
+        if !reversal.is_empty() {
+            self.redo.push(reversal);
+        }
+        if reversed < step.len() {
+            self.undo.push_back(step[..step.len() - reversed].to_vec());
+        }
+
+        result.map(|_| DataResult::Text(format!("Undid {} changes", reversed)))
+    }
+
+    /// Makes the latest undone step again. As with undo, what can't be redone is kept for later
+    pub fn redo(&mut self, database: &Database) -> Result<DataResult, GraphDbError> {
+        let step = match self.redo.pop() {
+            Some(step) => step,
+            None => return Err(GraphDbError::InvalidArgument("Nothing to redo".to_string())),
+        };
+
+        let ((reversed, result), reversal) = database
+            .changes()
+            .capture(self.session, || reverse(database, self.session, &step))?;
+        if !reversal.is_empty() {
+            self.push_undo(reversal);
+        }
+        if reversed < step.len() {
+            self.redo.push(step[..step.len() - reversed].to_vec());
+        }
+
+        result.map(|_| DataResult::Text(format!("Redid {} changes", reversed)))
+    }
+
+    fn push_undo(&mut self, step: Vec<ChangeEvent>) {
+        self.undo.push_back(step);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_reversible(change: &Change) -> bool {
+    !matches!(
+        change,
+        Change::GraphCleared
+            | Change::GraphLoaded
+            | Change::GraphDropped
+            | Change::GraphRenamed { .. }
+    )
+}
+
+/// Reverses each change, latest first, returning how many were reversed. Stops at the first that
+/// fails, e.g. because someone else has since removed the vertex, returning its error alongside
+/// the count. The changes before it are left as they are, and those already reversed can still
+/// be reversed back
+fn reverse(
+    database: &Database,
+    session: SessionId,
+    step: &[ChangeEvent],
+) -> (usize, Result<(), GraphDbError>) {
+    for (reversed, event) in step.iter().rev().enumerate() {
+        if let Err(err) = reverse_change(database, session, event) {
+            return (reversed, Err(err));
+        }
+    }
+
+    (step.len(), Ok(()))
+}
+
+fn reverse_change(
+    database: &Database,
+    session: SessionId,
+    event: &ChangeEvent,
+) -> Result<DataResult, GraphDbError> {
+    let graph = database.graph_factory().get_graph(&event.graph)?;
+    let mut graph = write_lock_for(&graph, Some(session))?;
+
+    match &event.change {
+        Change::VertexAdded { id, .. } => graph.remove_vertex(id),
+        Change::VertexUpdated { id, before, after } => {
+            graph.update_vertex(id, after.properties_changed_to(before))
+        }
+        Change::VertexRemoved { id, before } => graph.restore_vertex(*id, before.clone()),
+        Change::EdgeAdded { id, .. } => graph.remove_edge(id),
+        Change::EdgeUpdated { id, before, after } => graph.update_edge(
+            id,
+            before.from_vertex_id,
+            before.to_vertex_id,
+            after.edge_vertex.properties_changed_to(&before.edge_vertex),
+        ),
+        Change::EdgeRemoved { id, before } => graph.restore_edge(*id, before.clone()),
+        Change::GraphCleared
+        | Change::GraphLoaded
+        | Change::GraphDropped
+        | Change::GraphRenamed { .. } => Err(GraphDbError::Internal(format!(
+            "A {} change can't be undone",
+            event.change.as_str()
+        ))),
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    Graph,
+    Vertex,
+    Edge,
+}
+
+impl EntityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Graph => "Graph",
+            EntityKind::Vertex => "Vertex",
+            EntityKind::Edge => "Edge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphDbError {
+    /// The command could not be parsed. Position is the character offset into the command
+    Parse {
+        position: usize,
+        expected: String,
+        found: String,
+    },
+    /// A value could not be read as the type it was declared as
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    /// A required argument was missing or empty
+    InvalidArgument(String),
+    NotFound {
+        kind: EntityKind,
+        id: String,
+    },
+    GraphExists(String),
+    /// The command was well formed, but breaks a rule of the graph (e.g. duplicate ids)
+    ConstraintViolation(String),
+    /// Something that should not happen, e.g. a graph returned an unexpected result type
+    Internal(String),
+}
+
+impl GraphDbError {
+    /// Codes are stable across releases, so clients can match on them rather than on messages
+    pub fn code(&self) -> u16 {
+        match self {
+            GraphDbError::Parse { .. } => 100,
+            GraphDbError::TypeMismatch { .. } => 101,
+            GraphDbError::InvalidArgument(_) => 102,
+            GraphDbError::NotFound { .. } => 200,
+            GraphDbError::GraphExists(_) => 201,
+            GraphDbError::ConstraintViolation(_) => 202,
+            GraphDbError::Internal(_) => 500,
+        }
+    }
+
+    pub fn parse(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        GraphDbError::Parse {
+            position: 0,
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+
+    pub fn not_found(kind: EntityKind, id: impl ToString) -> Self {
+        GraphDbError::NotFound {
+            kind,
+            id: id.to_string(),
+        }
+    }
+
+    /// Moves a parse error's position along by the given number of characters. Parse errors are
+    /// raised relative to the part of the command being parsed, then offset by the caller
+    pub fn with_offset(self, offset: usize) -> Self {
+        match self {
+            GraphDbError::Parse {
+                position,
+                expected,
+                found,
+            } => GraphDbError::Parse {
+                position: position + offset,
+                expected,
+                found,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for GraphDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphDbError::Parse {
+                position,
+                expected,
+                found,
+            } => match found.is_empty() {
+                true => write!(
+                    f,
+                    "Parse error at position {}: expected {}",
+                    position, expected
+                ),
+                false => write!(
+                    f,
+                    "Parse error at position {}: expected {}, found '{}'",
+                    position, expected, found
+                ),
+            },
+            GraphDbError::TypeMismatch { expected, found } => {
+                write!(f, "Failed to parse value: {} as {}", found, expected)
+            }
+            GraphDbError::InvalidArgument(msg) => write!(f, "{}", msg),
+            GraphDbError::NotFound { kind, id } => match kind {
+                EntityKind::Graph => write!(f, "Unknown graph: {}", id),
+                _ => write!(f, "{} ID: {} does not exist", kind.as_str(), id),
+            },
+            GraphDbError::GraphExists(name) => {
+                write!(f, "Graph with name '{}' already exists", name)
+            }
+            GraphDbError::ConstraintViolation(msg) => write!(f, "{}", msg),
+            GraphDbError::Internal(msg) => write!(f, "Internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GraphDbError {}
@@ -1,51 +1,289 @@
+pub mod parameters;
+pub mod pattern;
+pub mod trigger;
+
+use std::fmt;
+
 use json::JsonValue as Json;
+use regex::Regex;
 
 use crate::{
+    error::GraphDbError,
     graph::{
+        adjacency::Direction,
+        changes::SessionId,
         edge::Edge,
+        options::GraphOptions,
         property::{Property, PropertyValue},
+        read_lock,
+        spatial::{BoundingBox, Point},
+        vector::Metric,
         vertex::Vertex,
-        DataResult, Graph, GraphFactory, GraphType,
+        write_lock_for, DataResult, Graph, GraphFactory, SharedGraph,
     },
-    parser::JsonProperty,
+    parser::{JsonProperty, ValidTypes},
 };
 
-#[derive(Debug)]
+use self::{
+    parameters::unbound,
+    pattern::PatternQuery,
+    trigger::{Trigger, TriggerEvent},
+};
+
+#[derive(Debug, Clone)]
 pub enum VertexMutationCommandType {
     Property(Property),
+    PropertyParameter(String, String, Option<ValidTypes>), // name, placeholder, optional type
     RemoveProperty(String),
+    ExternalId(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum VertexFilterCommandType {
+    HasId(usize),
     HasName(String),
-    HasProperty(String),              // name
-    HasPropertyValue(String, String), // name, value pair
-    HasPropertyLike(String, String),  // name, search term pair
-    Values(String),                   // property name
+    HasExternalId(String),                        // external id
+    HasProperty(String),                          // name
+    HasPropertyValue(String, String),             // name, value pair
+    HasPropertyLike(String, String),              // name, search term pair
+    HasPropertyText(String, TextPredicate, bool), // name, predicate, ignore case
+    HasPropertyRegex(String, Regex),              // name, compiled pattern
+    HasPropertyWithinRadius(String, Point, f64),  // name, center, distance in km
+    HasPropertyWithinBox(String, BoundingBox),    // name, box
+    HasPropertyNearest(String, Point, usize),     // name, center, number of vertices
+    Nearest(String, Vec<f32>, usize, Metric),     // name, vector, number of vertices, metric
+    Out(Option<String>),                          // optional edge label
+    In(Option<String>),                           // optional edge label
+    Values(String),                               // property name
+    Repeat(Repeat),
+    Edges(EdgeStep),
+}
+
+/// Written as the command it was parsed from, e.g. hasLabel(person)
+impl fmt::Display for VertexFilterCommandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VertexFilterCommandType::HasId(id) => write!(f, "V({})", id),
+            VertexFilterCommandType::HasName(label) => write!(f, "hasLabel({})", label),
+            VertexFilterCommandType::HasExternalId(id) => write!(f, "hasExternalId({})", id),
+            VertexFilterCommandType::HasProperty(name) => write!(f, "hasProperty({})", name),
+            VertexFilterCommandType::HasPropertyValue(name, value) => {
+                write!(f, "hasPropertyValue({}, {})", name, value)
+            }
+            VertexFilterCommandType::HasPropertyLike(name, term) => {
+                write!(f, "hasPropertyLike({}, {})", name, term)
+            }
+            VertexFilterCommandType::HasPropertyText(name, predicate, ignore_case) => {
+                let (command, term) = match predicate {
+                    TextPredicate::Equals(term) => ("hasPropertyValue", term),
+                    TextPredicate::Contains(term) => ("hasPropertyLike", term),
+                    TextPredicate::StartsWith(term) => ("hasPropertyStartsWith", term),
+                    TextPredicate::EndsWith(term) => ("hasPropertyEndsWith", term),
+                };
+                let suffix = if *ignore_case { "IgnoreCase" } else { "" };
+                write!(f, "{}{}({}, {})", command, suffix, name, term)
+            }
+            VertexFilterCommandType::HasPropertyRegex(name, regex) => {
+                write!(f, "hasPropertyRegex({}, {})", name, regex.as_str())
+            }
+            VertexFilterCommandType::HasPropertyWithinRadius(name, center, km) => {
+                write!(f, "hasPropertyWithinRadius({}, {}, {})", name, center, km)
+            }
+            VertexFilterCommandType::HasPropertyWithinBox(name, bounding_box) => write!(
+                f,
+                "hasPropertyWithinBox({}, {}, {}, {}, {})",
+                name,
+                bounding_box.min_latitude,
+                bounding_box.min_longitude,
+                bounding_box.max_latitude,
+                bounding_box.max_longitude
+            ),
+            VertexFilterCommandType::HasPropertyNearest(name, center, k) => {
+                write!(f, "hasPropertyNearest({}, {}, {})", name, center, k)
+            }
+            // Vectors are long, so only their length is shown
+            VertexFilterCommandType::Nearest(name, vector, k, metric) => write!(
+                f,
+                "nearest({}, [{} numbers], {}, {})",
+                name,
+                vector.len(),
+                k,
+                metric.as_str()
+            ),
+            VertexFilterCommandType::Out(label) => {
+                write!(f, "out({})", label.as_deref().unwrap_or_default())
+            }
+            VertexFilterCommandType::In(label) => {
+                write!(f, "in({})", label.as_deref().unwrap_or_default())
+            }
+            VertexFilterCommandType::Values(name) => write!(f, "values({})", name),
+            VertexFilterCommandType::Repeat(repeat) => write!(f, "{}", repeat),
+            VertexFilterCommandType::Edges(step) => write!(f, "{}", step),
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Filters written one after another, e.g. out(reportsTo).hasLabel(manager)
+fn write_filters(f: &mut fmt::Formatter<'_>, filters: &[VertexFilterCommandType]) -> fmt::Result {
+    for (i, filter) in filters.iter().enumerate() {
+        if i > 0 {
+            write!(f, ".")?;
+        }
+        write!(f, "{}", filter)?;
+    }
+    Ok(())
+}
+
+/// Steps taken again and again from each vertex, e.g. repeat(out(reportsTo)).until(hasLabel(ceo)).
+/// Each vertex reached is kept once, in the order first reached. Without times or until, walks go
+/// on until they can go no further, keeping the vertices they end at
+#[derive(Debug, Clone)]
+pub struct Repeat {
+    pub steps: Vec<VertexFilterCommandType>,
+    pub times: Option<usize>,                        // most times round
+    pub until: Option<Vec<VertexFilterCommandType>>, // ends walks at vertices these filters keep
+    pub emit: bool,                                  // also keeps every vertex passed through
+    pub paths: PathFilter,
+}
+
+impl fmt::Display for Repeat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "repeat(")?;
+        write_filters(f, &self.steps)?;
+        write!(f, ")")?;
+        if let Some(times) = self.times {
+            write!(f, ".times({})", times)?;
+        }
+        if let Some(until) = &self.until {
+            write!(f, ".until(")?;
+            write_filters(f, until)?;
+            write!(f, ")")?;
+        }
+        if self.emit {
+            write!(f, ".emit()")?;
+        }
+        match self.paths {
+            PathFilter::Any => Ok(()),
+            PathFilter::Simple => write!(f, ".simplePath()"),
+            PathFilter::Cyclic => write!(f, ".cyclicPath()"),
+        }
+    }
+}
+
+/// Steps along edges whose label and properties pass the filters, then to one of their vertices,
+/// e.g. outE(rated).hasPropertyValue(stars, 5).inV()
+#[derive(Debug, Clone)]
+pub struct EdgeStep {
+    pub direction: Direction, // outE or inE
+    pub label: Option<String>,
+    pub filters: Vec<VertexFilterCommandType>, // label and property filters, run on each edge
+    pub end: Option<EdgeStepEnd>,              // only missing while being parsed
+}
+
+impl fmt::Display for EdgeStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let command = match self.direction {
+            Direction::Out => "outE",
+            Direction::In => "inE",
+        };
+        write!(
+            f,
+            "{}({})",
+            command,
+            self.label.as_deref().unwrap_or_default()
+        )?;
+        for filter in &self.filters {
+            write!(f, ".{}", filter)?;
+        }
+        match self.end {
+            Some(end) => write!(f, ".{}()", end.as_str()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The vertex an edge step ends at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeStepEnd {
+    In,    // the edge's to vertex
+    Out,   // the edge's from vertex
+    Other, // the end the step didn't come from
+}
+
+impl EdgeStepEnd {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeStepEnd::In => "inV",
+            EdgeStepEnd::Out => "outV",
+            EdgeStepEnd::Other => "otherV",
+        }
+    }
+}
+
+/// Which of a repeat's walks are kept, by whether they pass through a vertex more than once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathFilter {
+    Any,
+    Simple, // never
+    Cyclic, // at least once
+}
+
+/// A comparison against string property values. Search terms are lower case when ignoring case
+#[derive(Debug, Clone)]
+pub enum TextPredicate {
+    Equals(String),
+    Contains(String),
+    StartsWith(String),
+    EndsWith(String),
+}
+
+#[derive(Debug, Clone)]
 pub enum EdgeMutationCommandType {
     VertexMutationCommandTypes(Vec<VertexMutationCommandType>),
     FromVertex(usize),
     ToVertex(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CommandType {
-    CreateGraph(String),
+    CreateGraph(String, GraphOptions),
+    DropGraph(String),
+    RenameGraph(String, String), // old name, new name
+    ClearGraph(String),
+    CloneGraph(String, String), // source name, destination name
     ListGraphs,
+    DescribeGraph(String),
     ListVertices(Vec<VertexFilterCommandType>),
     GetVertex(usize),
     AddVertex(String, Vec<VertexMutationCommandType>),
     EditVertex(usize, Vec<VertexMutationCommandType>),
     RemoveVertex(usize),
     AddEdge(String, Vec<EdgeMutationCommandType>),
+    EditEdge(usize, Vec<EdgeMutationCommandType>),
+    RemoveEdge(usize),
+    GraphStats,
+    CreateTextIndex(String),           // property name
+    DropTextIndex(String),             // property name
+    TextSearch(String, String),        // property name, query
+    CreateSpatialIndex(String),        // property name
+    DropSpatialIndex(String),          // property name
+    CreateVectorIndex(String, Metric), // property name, metric
+    DropVectorIndex(String),           // property name
+    CreateTrigger(Trigger),
+    DropTrigger(String), // trigger name
+    ListTriggers,
+    Match(PatternQuery),
+    Explain(Vec<VertexFilterCommandType>),
+    Profile(Vec<VertexFilterCommandType>),
+    AsOf(i64, Vec<VertexFilterCommandType>), // milliseconds since January 1, 1970, filters
+    MatchAsOf(i64, PatternQuery),            // milliseconds since January 1, 1970, query
+    History(usize),                          // vertex id
+    EnableVersioning,
+    DisableVersioning,
     Help,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Command {
     pub command_type: CommandType,
     pub command_json: Option<Json>,
@@ -53,84 +291,407 @@ pub struct Command {
 
 pub struct Executor {
     graph_factory: GraphFactory,
-    graph_type: GraphType,
 }
 
 impl Executor {
-    pub fn new(graph_factory: GraphFactory, graph_type: GraphType) -> Self {
-        Executor {
-            graph_factory,
-            graph_type,
-        }
+    pub fn new(graph_factory: GraphFactory) -> Self {
+        Executor { graph_factory }
     }
 
-    pub fn execute(&mut self, command: Command) -> Result<DataResult, String> {
-        println!("Executing: {:?}", command);
-
+    /// Reads share a graph, while mutations hold it exclusively, so writes to a graph are
+    /// serialized and never observed half done. The changes a command makes are attributed to the
+    /// session it's run for, if one is given
+    pub fn execute(
+        &self,
+        command: Command,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
         match &command.command_type {
-            CommandType::CreateGraph(graph_name) => self
+            CommandType::CreateGraph(graph_name, options) => {
+                self.graph_factory
+                    .create_graph(graph_name.to_owned(), options.clone(), session)
+            }
+
+            CommandType::DropGraph(graph_name) => {
+                self.graph_factory.drop_graph(graph_name, session)
+            }
+
+            CommandType::RenameGraph(old_name, new_name) => {
+                self.graph_factory
+                    .rename_graph(old_name, new_name.to_owned(), session)
+            }
+
+            CommandType::ClearGraph(graph_name) => {
+                self.graph_factory.clear_graph(graph_name, session)
+            }
+
+            CommandType::CloneGraph(source_name, destination_name) => self
                 .graph_factory
-                .create_graph(graph_name.to_owned(), &self.graph_type),
+                .clone_graph(source_name, destination_name.to_owned(), session),
 
             CommandType::ListGraphs => self.graph_factory.list_graphs(),
 
+            CommandType::DescribeGraph(graph_name) => {
+                let graph = self.graph_factory.get_graph(graph_name)?;
+                let graph = read_lock(&graph)?;
+                graph.describe()
+            }
+
             CommandType::ListVertices(filter_command) => {
                 let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
                 graph.list_vertices(filter_command)
             }
 
             CommandType::GetVertex(id) => {
                 let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
                 graph.get_vertex(id)
             }
 
             CommandType::AddVertex(label, mutate_command) => {
-                let graph = self.get_mut_graph(&command)?;
-                let vertex = create_vertex(label.to_string(), mutate_command)?;
+                let mut vertex = create_vertex(label.to_string(), mutate_command)?;
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::Insert,
+                    label,
+                    &mut vertex.properties,
+                )?;
+                // After triggers' changes are made along with the insert, as a single change
+                let changes = trigger::run_after(
+                    graph.as_ref(),
+                    TriggerEvent::Insert,
+                    label,
+                    &vertex.properties,
+                )?;
+                vertex.properties = trigger::updated_properties(&vertex, &changes);
                 graph.add_vertex(vertex)
             }
 
             CommandType::EditVertex(id, mutate_command) => {
-                let graph = self.get_mut_graph(&command)?;
-                let get_vertex_result = graph.get_mutable_vertex(id)?;
-                let vertex = match get_vertex_result {
-                    DataResult::MutableVertexRef(vertex) => vertex,
-                    _ => return Err(format!("Mismatched return type")),
-                };
+                if external_id(mutate_command).is_some() {
+                    return Err(GraphDbError::InvalidArgument(
+                        "External IDs can only be set when adding a vertex".to_string(),
+                    ));
+                }
+
+                let mut properties = update_vertex_properties(mutate_command)?;
 
-                let properties = update_vertex_properties(&mutate_command)?;
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                if !trigger::has_triggers(graph.as_ref(), TriggerEvent::Update) {
+                    return graph.update_vertex(id, properties);
+                }
 
-                vertex.update(properties)
+                // Before triggers see the vertex as the update would leave it
+                let vertex = to_vertex(graph.get_vertex(id)?)?;
+                let mut updated = trigger::updated_properties(&vertex, &properties);
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::Update,
+                    &vertex.label,
+                    &mut updated,
+                )?;
+                let changes = trigger::run_after(
+                    graph.as_ref(),
+                    TriggerEvent::Update,
+                    &vertex.label,
+                    &updated,
+                )?;
+
+                // As with inserts, after triggers' changes are part of the one update
+                let mut updated_vertex = vertex.clone();
+                updated_vertex.properties = updated;
+                properties = trigger::changed_properties(
+                    &vertex.properties,
+                    &trigger::updated_properties(&updated_vertex, &changes),
+                );
+                graph.update_vertex(id, properties)
             }
 
             CommandType::RemoveVertex(id) => {
-                let graph = self.get_mut_graph(&command)?;
-                graph.remove_vertex(id)
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                if !trigger::has_triggers(graph.as_ref(), TriggerEvent::Delete)
+                    && !trigger::has_triggers(graph.as_ref(), TriggerEvent::DeleteEdge)
+                {
+                    return graph.remove_vertex(id);
+                }
+
+                let mut vertex = to_vertex(graph.get_vertex(id)?)?;
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::Delete,
+                    &vertex.label,
+                    &mut vertex.properties,
+                )?;
+                let mut edges = attached_edges(graph.as_ref(), *id)?;
+                for edge in &mut edges {
+                    trigger::run_before(
+                        graph.as_ref(),
+                        TriggerEvent::DeleteEdge,
+                        &edge.edge_vertex.label,
+                        &mut edge.edge_vertex.properties,
+                    )?;
+                }
+
+                let updates = trigger::run_after_edges(
+                    graph.as_ref(),
+                    TriggerEvent::DeleteEdge,
+                    &edges,
+                    Some(*id),
+                )?;
+
+                let result = graph.remove_vertex(id)?;
+                for (vertex_id, changes) in updates {
+                    graph.update_vertex(&vertex_id, changes)?;
+                }
+
+                Ok(result)
             }
 
             CommandType::AddEdge(label, mutate_command) => {
-                let graph = self.get_mut_graph(&command)?;
-                let edge = create_edge(graph, mutate_command, label.to_string())?;
-                graph.add_edge(edge)
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                let mut edge = create_edge(graph.as_ref(), mutate_command, label.to_string())?;
+
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::InsertEdge,
+                    label,
+                    &mut edge.edge_vertex.properties,
+                )?;
+                let updates = trigger::run_after_edges(
+                    graph.as_ref(),
+                    TriggerEvent::InsertEdge,
+                    std::slice::from_ref(&edge),
+                    None,
+                )?;
+
+                let result = graph.add_edge(edge)?;
+                for (vertex_id, changes) in updates {
+                    graph.update_vertex(&vertex_id, changes)?;
+                }
+
+                Ok(result)
+            }
+
+            CommandType::EditEdge(id, mutate_command) => {
+                let mutations = edge_mutations(mutate_command)?;
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+
+                // Endpoints that aren't given stay as they are
+                let edge = to_edge(graph.get_edge(id)?)?;
+                let from_vertex_id = mutations.from_vertex.unwrap_or(edge.from_vertex_id);
+                let to_vertex_id = mutations.to_vertex.unwrap_or(edge.to_vertex_id);
+                if !trigger::has_triggers(graph.as_ref(), TriggerEvent::UpdateEdge) {
+                    return graph.update_edge(
+                        id,
+                        from_vertex_id,
+                        to_vertex_id,
+                        mutations.properties,
+                    );
+                }
+
+                // Before triggers see the edge as the update would leave it, and after triggers
+                // change the vertices it then joins
+                let mut updated = Edge {
+                    from_vertex_id,
+                    to_vertex_id,
+                    edge_vertex: edge.edge_vertex.clone(),
+                };
+                updated.edge_vertex.properties =
+                    trigger::updated_properties(&edge.edge_vertex, &mutations.properties);
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::UpdateEdge,
+                    &edge.edge_vertex.label,
+                    &mut updated.edge_vertex.properties,
+                )?;
+                let updates = trigger::run_after_edges(
+                    graph.as_ref(),
+                    TriggerEvent::UpdateEdge,
+                    std::slice::from_ref(&updated),
+                    None,
+                )?;
+
+                let properties = trigger::changed_properties(
+                    &edge.edge_vertex.properties,
+                    &updated.edge_vertex.properties,
+                );
+                let result = graph.update_edge(id, from_vertex_id, to_vertex_id, properties)?;
+                for (vertex_id, changes) in updates {
+                    graph.update_vertex(&vertex_id, changes)?;
+                }
+
+                Ok(result)
+            }
+
+            CommandType::RemoveEdge(id) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                if !trigger::has_triggers(graph.as_ref(), TriggerEvent::DeleteEdge) {
+                    return graph.remove_edge(id);
+                }
+
+                let mut edge = to_edge(graph.get_edge(id)?)?;
+                trigger::run_before(
+                    graph.as_ref(),
+                    TriggerEvent::DeleteEdge,
+                    &edge.edge_vertex.label,
+                    &mut edge.edge_vertex.properties,
+                )?;
+                let updates = trigger::run_after_edges(
+                    graph.as_ref(),
+                    TriggerEvent::DeleteEdge,
+                    std::slice::from_ref(&edge),
+                    None,
+                )?;
+
+                let result = graph.remove_edge(id)?;
+                for (vertex_id, changes) in updates {
+                    graph.update_vertex(&vertex_id, changes)?;
+                }
+
+                Ok(result)
+            }
+
+            CommandType::GraphStats => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.stats()
+            }
+
+            CommandType::CreateTextIndex(property_name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.create_text_index(property_name)
+            }
+
+            CommandType::DropTextIndex(property_name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.drop_text_index(property_name)
+            }
+
+            CommandType::TextSearch(property_name, query) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.text_search(property_name, query)
+            }
+
+            CommandType::CreateSpatialIndex(property_name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.create_spatial_index(property_name)
+            }
+
+            CommandType::DropSpatialIndex(property_name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.drop_spatial_index(property_name)
+            }
+
+            CommandType::CreateVectorIndex(property_name, metric) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.create_vector_index(property_name, *metric)
+            }
+
+            CommandType::DropVectorIndex(property_name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.drop_vector_index(property_name)
+            }
+
+            CommandType::CreateTrigger(trigger) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.create_trigger(trigger.clone())
+            }
+
+            CommandType::DropTrigger(name) => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.drop_trigger(name)
+            }
+
+            CommandType::ListTriggers => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                Ok(DataResult::StringVector(
+                    graph.triggers().iter().map(|t| t.to_string()).collect(),
+                ))
+            }
+
+            CommandType::Match(query) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                pattern::execute(graph.as_ref(), query)
+            }
+
+            CommandType::Explain(filters) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.explain(filters)
             }
 
-            CommandType::Help => Err(help()),
+            CommandType::Profile(filters) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.profile(filters)
+            }
+
+            CommandType::AsOf(timestamp, filters) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.as_of(*timestamp)?.list_vertices(filters)
+            }
+
+            CommandType::MatchAsOf(timestamp, query) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                pattern::execute(graph.as_of(*timestamp)?.as_ref(), query)
+            }
+
+            CommandType::History(id) => {
+                let graph = self.get_graph(&command)?;
+                let graph = read_lock(&graph)?;
+                graph.vertex_history(id)
+            }
+
+            CommandType::EnableVersioning => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.enable_versioning()
+            }
+
+            CommandType::DisableVersioning => {
+                let graph = self.get_graph(&command)?;
+                let mut graph = write_lock_for(&graph, session)?;
+                graph.disable_versioning()
+            }
+
+            CommandType::Help => Ok(DataResult::Text(help())),
         }
     }
 
-    fn get_mut_graph(&mut self, command: &Command) -> Result<&mut Box<dyn Graph>, String> {
+    pub fn graph_factory(&self) -> &GraphFactory {
+        &self.graph_factory
+    }
+
+    fn get_graph(&self, command: &Command) -> Result<SharedGraph, GraphDbError> {
         let graph_name = self.get_graph_name(command)?;
 
         self.graph_factory.get_graph(&graph_name)
     }
 
-    fn get_graph(&mut self, command: &Command) -> Result<&Box<dyn Graph>, String> {
-        let graph = self.get_mut_graph(command)?;
-        Ok(&*graph)
-    }
-
-    fn get_graph_name(&self, command: &Command) -> Result<String, String> {
-        let msg = "Graph not specified".to_string();
+    fn get_graph_name(&self, command: &Command) -> Result<String, GraphDbError> {
+        let msg = GraphDbError::InvalidArgument("Graph not specified".to_string());
 
         match &command.command_json {
             Some(json) => {
@@ -140,7 +701,7 @@ impl Executor {
                     false => Ok(name.to_string()),
                 }
             }
-            None => return Err(msg),
+            None => Err(msg),
         }
     }
 }
@@ -152,15 +713,45 @@ pub fn help() -> String {
 
         help: prints this page
 
-        createGraph(<graph name>): creates a graph with the given name
+        createGraph(<graph name>): creates a graph with the given name. May be followed by options, which are kept for the
+                graph's lifetime, e.g. createGraph(roads).edgePolicy(simple).directed(false)
+
+            .edgePolicy(<multigraph|simple|uniquePerLabel>): which edges may join the same two vertices. A multigraph allows
+                    any number, a simple graph one and no self loops, and uniquePerLabel one of each label. Defaults to multigraph
+
+            .directed(<true|false>): an undirected graph follows its edges either way, so out(), in(), outE() and inE() each
+                    give every edge of a vertex, and inV() ends a step at the vertex it leads to. Defaults to true
+
+            .type(<inMemory|persistent>): where the graph keeps its data. A persistent graph also appends each change to the
+                    file given by option(path, <file>), and is read back from it when created again with the same path.
+                    It must be created again with the same directed, edgePolicy and schema. Only vertices and edges are
+                    kept; indexes given as options are built again. Defaults to inMemory
+
+            .option(<key>, <value>): sets one of:
+                path: the file a persistent graph is kept in
+                directed, edgePolicy: as above
+                schema: flexible, or strict to keep each property to the type of the first value it was given
+                textIndex, spatialIndex: a property to index from the start. May be given more than once
+                vectorIndex: a property and metric to index from the start, e.g. option(vectorIndex, embedding, cosine)
+
+        describeGraph(<graph name>): shows the options the graph was created with, and the indexes it has now
 
         listGraphs(): lists all graphs
 
+        dropGraph(<graph name>): deletes the graph with the given name
+
+        renameGraph(<graph name>, <new graph name>): renames the graph with the given name
+
+        clearGraph(<graph name>): removes every vertex and edge from the graph with the given name
+
+        cloneGraph(<graph name>, <new graph name>): copies the graph with the given name into a new graph. A copy of a
+                persistent graph is kept in memory
+
     Graph Commands (preceded with a graph name. E.g. graph.V()):
 
         .V(): lists vertices in the given graph
         
-        .V(<id>): gets a vertex in the given graph
+        .V(<id>): gets a vertex in the given graph. Can be followed by vertex filter commands, e.g. graph.V(0).out()
 
         .addV(<label>): adds a vertex to the given graph. This command can be used with no mutation commands to create an empty vertex
 
@@ -170,24 +761,162 @@ pub fn help() -> String {
 
         .addE(<label>, <from_id>, <to_id>): adds an edge to the given graph between the given vertex ids
 
-    Vertex mutation commands (preceded with either addV(<label>), editV(<id>), or addE(<label>, <from_id>, <to_id>)))
+        .editE(<id>): selects the edge with the given id for editing. Followed by .from(<id>) and .to(<id>) to move it to other
+                vertices, which must exist, and by vertex mutation commands to change its properties.
+                E.g. graph.editE(0).to(2).property(since, 2021, int32)
+
+        .deleteE(<id>): deletes the edge with the given id
+
+        .stats(): shows vertex and edge counts, label counts, property usage, vertex degrees and approximate memory use
+
+        .createTextIndex(<name>): indexes the words in the given string property, so it can be searched with search()
+
+        .dropTextIndex(<name>): removes the text index on the given property
+
+        .search(<name>, <query>): finds vertices whose indexed property matches the query, most relevant first.
+                Words match on their own, and words in double quotes match as a phrase. E.g. graph.search(bio, rust "graph database")
+
+        .createSpatialIndex(<name>): indexes the given point property, so V() followed by a geo filter doesn't scan every vertex
+
+        .dropSpatialIndex(<name>): removes the spatial index on the given property
+
+        .createVectorIndex(<name>, <cosine|l2|dot>): builds an approximate nearest neighbour (HNSW) index over the given vector property,
+                used by V().nearest(...) with the same metric
+
+        .dropVectorIndex(<name>): removes the vector index on the given property
+
+        .createTrigger(<name>, <before|after>, <insert|update|delete|insertEdge|updateEdge|deleteEdge>, <label>): runs the actions that follow
+                whenever a vertex (or for edge events, an edge) with the given label is mutated. Before triggers can reject the mutation,
+                or change the vertex or edge being inserted or updated. After triggers change the inserted or updated vertex, or the
+                vertex at one end of an edge. E.g. graph.createTrigger(stamp, before, update, person).now(updated_at)
+
+        .dropTrigger(<name>): removes the trigger with the given name
+
+        .triggers(): lists the graph's triggers
+
+        .explain(<query>): shows how a V() query would run, without running it: where its first vertices come from, then each
+                step with the rows it's expected to leave. Filters between traversals run most selective first, and the most selective
+                one with an index (vertex id, label, external id, or a spatial index) finds the first vertices, rather than a scan.
+                E.g. graph.explain(V().hasPropertyValue(name, alice).hasLabel(person).out(knows))
+
+        .profile(<query>): runs a V() query as explain(...) describes, adding the rows each step actually left and its time in ms
+
+        .enableVersioning(): keeps every version of each vertex and edge from now on, with when it was valid
+
+        .disableVersioning(): stops versioning, dropping the history kept so far
+
+        .asOf(<timestamp>): runs the V() or MATCH query that follows against the graph as it was at the given time, in milliseconds since
+                January 1, 1970, e.g. graph.asOf(1700000000000).V().hasLabel(person).out(knows). Needs versioning enabled, and
+                the graph was empty before it was
+
+        .history(<id>): lists each version of the vertex with the given id, oldest first: when it was valid from and to, whether
+                it was added, updated or removed, and the vertex as it was. Needs versioning enabled
+
+        .MATCH <pattern> [WHERE <predicate>] ... RETURN <items> [LIMIT <n>]: finds every way a Cypher style pattern fits the graph,
+                e.g. graph.MATCH (a:person)-[:knows]->(b)-[:works_at]->(c) WHERE a.name = 'alice' RETURN b, c.name AS employer
+                Nodes are (<variable>:<label> {<name>: <value>, ...}) and relationships -[<variable>:<label>]->, <-[...]- or -[...]-,
+                where every part is optional. A variable used twice is the same vertex or edge each time. Further MATCH clauses
+                extend each row, and OPTIONAL MATCH keeps rows it can't extend, with its variables null. Predicates compare
+                properties and values with =, <>, <, <=, >, >= and IS [NOT] NULL, combined with AND, OR and NOT. RETURN * returns
+                every named variable. Matching starts from the node with the fewest candidates, judged by label counts
+
+    Trigger actions (preceded with createTrigger(...))
+
+        .property(<name>, <value>, <type>), .removeProperty(<name>): as for vertex mutations
+
+        .now(<name>): sets a datetime property to the current time
+
+        .increment(<name>, <amount>): adds to an integer property, starting from 0. The amount may be negative
+
+        .require(<name>): rejects the mutation unless the vertex or edge has the property. Before triggers only
+
+        .reject(<message>): rejects the mutation with the given message. Before triggers only
+
+        .target(<from|to>): the end of the edge whose vertex an after insertEdge, updateEdge or deleteEdge trigger changes,
+                e.g. graph.createTrigger(followers, after, insertEdge, follows).target(to).increment(follower_count, 1)
+
+    Vertex mutation commands (preceded with either addV(<label>), editV(<id>), addE(<label>, <from_id>, <to_id>), or editE(<id>))
 
         .property(<name>, <value>, <type>): adds a property to the given vertex with the given vertex property type
 
         .removeProperty(<name>): removes the property with the given name
 
+        .externalId(<id>): sets a unique, user supplied id (e.g. a UUID) on a vertex. Only valid with addV(<label>)
+
     Vertex filter commands (preceded with V())
 
         .hasLabel(<label>): selects vertices with the given label
 
+        .hasExternalId(<id>): selects the vertex with the given external id
+
         .hasProperty(<name>): selects vertices with the given property name
 
         .hasPropertyValue(<name>, <value>): selects vertices with the given property name and value.
                 
         .hasPropertyLike(<name>, <search_term>): selects vertices with properties matching the given search term (string property values only)
 
+        .hasPropertyStartsWith(<name>, <prefix>): selects vertices with string properties starting with the given prefix
+
+        .hasPropertyEndsWith(<name>, <suffix>): selects vertices with string properties ending with the given suffix
+
+        .hasPropertyValueIgnoreCase(<name>, <value>), .hasPropertyLikeIgnoreCase(<name>, <search_term>),
+        .hasPropertyStartsWithIgnoreCase(<name>, <prefix>), .hasPropertyEndsWithIgnoreCase(<name>, <suffix>):
+                as above, ignoring upper and lower case (string property values only)
+
+        .hasPropertyRegex(<name>, <pattern>): selects vertices with string properties matching the given regular expression.
+                Prefix the pattern with (?i) to ignore case
+
+        .hasPropertyWithinRadius(<name>, <latitude>, <longitude>, <km>): selects vertices with points within the given distance
+
+        .hasPropertyWithinBox(<name>, <min_latitude>, <min_longitude>, <max_latitude>, <max_longitude>): selects vertices with points
+                inside the box. A minimum longitude greater than the maximum wraps around the antimeridian
+
+        .hasPropertyNearest(<name>, <latitude>, <longitude>, <k>): selects the k vertices with points nearest the given location
+
+        .nearest(<name>, [<numbers>], <k>, <cosine|l2|dot>): selects the k vertices whose vector property is most similar to the given
+                vector. Straight after V(), uses a vector index if there is one, e.g. graph.V().nearest(embedding, [0.1, 0.9], 5, cosine).out()
+
+        .out(<label>): steps from each selected vertex to the vertices its outgoing edges point to. The label is optional, and restricts which edges are followed
+
+        .in(<label>): steps from each selected vertex to the vertices its incoming edges come from. The label is optional
+
         .values(<name>): selects the value of the property with the given name for each selected vertex
 
+        .outE(<label>) and .inE(<label>): step along each selected vertex's outgoing or incoming edges, e.g.
+                graph.V(0).outE(rated).hasPropertyValue(stars, 5).inV(). The label is optional. Filters that follow, up to the
+                end of the step, keep the edges whose label or properties match: hasLabel, hasProperty, hasPropertyValue,
+                hasPropertyLike, the text and regex filters, hasPropertyWithinRadius and hasPropertyWithinBox. The step ends with:
+
+                .inV(): the vertex each edge points to
+                .outV(): the vertex each edge comes from
+                .otherV(): the vertex at the other end of each edge from the one it was reached from
+
+        .repeat(<filters>): takes the filters again and again from each selected vertex, e.g. repeat(out(reportsTo)), keeping each
+                vertex reached once. Walks go on until they can go no further, keeping the vertices they end at, unless followed by:
+
+                .times(<n>): stops after going round n times, keeping where each walk got to
+                .until(<filters>): ends walks at vertices the filters keep, e.g. until(hasLabel(ceo)), and keeps them
+                .emit(): also keeps every vertex passed through on the way
+                .simplePath(): drops walks that pass through a vertex twice
+                .cyclicPath(): only keeps walks that pass through a vertex twice
+
+                A walk never goes on from the same vertex twice unless its path is kept, so cycles and self-loops can't repeat forever
+
+    REPL commands
+
+        :format <table|json|csv>: sets how query results are displayed. Defaults to table
+
+        undo(): reverses the latest addV, editV, deleteV, addE, editE or deleteE, along with what triggers changed because of it.
+                Deleted vertices and edges come back with the ids and properties they had. The last 100 are kept, until a graph
+                is cleared, dropped or renamed
+
+        redo(): makes the latest undone change again
+
+    Line editing
+
+        Tab completes commands, graph names and property names. Ctrl-R searches history, which is kept in ~/.rustgdb_history
+        Commands continue onto the next line whilst brackets are unclosed, or the line ends with '.' or '\'
+
     Vertex property types
         
         boolean
@@ -197,6 +926,14 @@ pub fn help() -> String {
         float64
         string
         datetime (stored as ms since Unix epoch)
+        point (a latitude and longitude in degrees, e.g. property(home, 51.5, -0.12, point))
+        vector (32 bit floats, e.g. property(embedding, [0.1, 0.9], vector). Every vector of a property has the same length)
+
+    Placeholders
+
+        Arguments written as $<name> are placeholders for values passed alongside the query when embedding the database,
+        e.g. graph.V().hasPropertyValue(name, $name), or graph.addV(person).property(age, $age) taking its type from the value.
+        Labels, property names and values, external ids, search terms and regexes can be placeholders
     "#
     .to_string()
 }
@@ -204,58 +941,83 @@ pub fn help() -> String {
 fn create_vertex(
     label: String,
     mutate_command: &Vec<VertexMutationCommandType>,
-) -> Result<Vertex, String> {
+) -> Result<Vertex, GraphDbError> {
     let properties = update_vertex_properties(mutate_command)?;
 
-    Ok(Vertex::new(label, properties))
+    let mut vertex = Vertex::new(label, properties);
+    vertex.external_id = external_id(mutate_command).map(|id| id.to_owned());
+
+    Ok(vertex)
 }
 
-fn create_edge(
-    graph: &Box<dyn Graph>,
-    mutate_command: &Vec<EdgeMutationCommandType>,
-    label: String,
-) -> Result<Edge, String> {
-    let mut properties = None;
-    let mut from_vertex = None;
-    let mut to_vertex = None;
+fn to_vertex(result: DataResult) -> Result<Vertex, GraphDbError> {
+    match result {
+        DataResult::Vertex(vertex) => Ok(vertex),
+        _ => Err(GraphDbError::Internal("Mismatched return type".to_string())),
+    }
+}
 
-    for command in mutate_command {
-        match command {
-            EdgeMutationCommandType::VertexMutationCommandTypes(commands) => {
-                properties = Some(update_vertex_properties(commands)?);
-            }
+fn to_edge(result: DataResult) -> Result<Edge, GraphDbError> {
+    match result {
+        DataResult::Edge(edge) => Ok(edge),
+        _ => Err(GraphDbError::Internal("Mismatched return type".to_string())),
+    }
+}
 
-            EdgeMutationCommandType::FromVertex(id) => {
-                from_vertex = Some(id);
-            }
+/// Every edge leaving or arriving at the vertex, once each
+fn attached_edges(graph: &dyn Graph, id: usize) -> Result<Vec<Edge>, GraphDbError> {
+    let mut edge_ids: Vec<usize> = graph
+        .adjacent_edges(&id, Direction::Out)?
+        .chain(graph.adjacent_edges(&id, Direction::In)?)
+        .map(|edge| edge.edge_id)
+        .collect();
+    // A self loop is both an out and an in edge
+    edge_ids.sort_unstable();
+    edge_ids.dedup();
 
-            EdgeMutationCommandType::ToVertex(id) => {
-                to_vertex = Some(id);
-            }
-        }
-    }
+    edge_ids
+        .iter()
+        .map(|edge_id| to_edge(graph.get_edge(edge_id)?))
+        .collect()
+}
 
-    let from_vertex_id = *match from_vertex {
+fn external_id(mutate_command: &[VertexMutationCommandType]) -> Option<&String> {
+    mutate_command.iter().find_map(|command| match command {
+        VertexMutationCommandType::ExternalId(id) => Some(id),
+        _ => None,
+    })
+}
+
+fn create_edge(
+    graph: &dyn Graph,
+    mutate_command: &[EdgeMutationCommandType],
+    label: String,
+) -> Result<Edge, GraphDbError> {
+    let mutations = edge_mutations(mutate_command)?;
+
+    let from_vertex_id = match mutations.from_vertex {
         Some(i) => i,
-        None => return Err(format!("Must provide a source vertex")),
+        None => {
+            return Err(GraphDbError::InvalidArgument(
+                "Must provide a source vertex".to_string(),
+            ))
+        }
     };
 
-    let to_vertex_id = *match to_vertex {
+    let to_vertex_id = match mutations.to_vertex {
         Some(i) => i,
-        None => return Err(format!("Must provide a destination vertex")),
+        None => {
+            return Err(GraphDbError::InvalidArgument(
+                "Must provide a destination vertex".to_string(),
+            ))
+        }
     };
 
     // Check vertices exist
     _ = graph.get_vertex(&from_vertex_id)?;
     _ = graph.get_vertex(&to_vertex_id)?;
 
-    let edge_vertex = Vertex::new(
-        label,
-        match properties {
-            Some(props) => props,
-            None => Vec::new(),
-        },
-    );
+    let edge_vertex = Vertex::new(label, mutations.properties);
 
     Ok(Edge {
         from_vertex_id,
@@ -266,17 +1028,60 @@ fn create_edge(
     })
 }
 
+/// The source and destination vertices given for an edge, if any, and its properties
+struct EdgeMutations {
+    from_vertex: Option<usize>,
+    to_vertex: Option<usize>,
+    properties: Vec<Property>,
+}
+
+fn edge_mutations(
+    mutate_command: &[EdgeMutationCommandType],
+) -> Result<EdgeMutations, GraphDbError> {
+    let mut properties = Vec::new();
+    let mut from_vertex = None;
+    let mut to_vertex = None;
+
+    for command in mutate_command {
+        match command {
+            EdgeMutationCommandType::VertexMutationCommandTypes(commands) => {
+                if external_id(commands).is_some() {
+                    return Err(GraphDbError::InvalidArgument(
+                        "External IDs can only be set on vertices".to_string(),
+                    ));
+                }
+
+                properties = update_vertex_properties(commands)?;
+            }
+
+            EdgeMutationCommandType::FromVertex(id) => {
+                from_vertex = Some(*id);
+            }
+
+            EdgeMutationCommandType::ToVertex(id) => {
+                to_vertex = Some(*id);
+            }
+        }
+    }
+
+    Ok(EdgeMutations {
+        from_vertex,
+        to_vertex,
+        properties,
+    })
+}
+
 fn update_vertex_properties(
     mutate_command: &Vec<VertexMutationCommandType>,
-) -> Result<Vec<Property>, String> {
+) -> Result<Vec<Property>, GraphDbError> {
     let mut properties = Vec::new();
     let mut added_names = Vec::new();
     for command in mutate_command {
         match command {
             VertexMutationCommandType::Property(property) => {
                 if added_names.contains(&property.name) {
-                    return Err(format!(
-                        "Cannot have multiple properties with the same name"
+                    return Err(GraphDbError::ConstraintViolation(
+                        "Cannot have multiple properties with the same name".to_string(),
                     ));
                 }
 
@@ -295,6 +1100,10 @@ fn update_vertex_properties(
                     flagged_for_removal: true,
                 });
             }
+            VertexMutationCommandType::PropertyParameter(_, parameter, _) => {
+                return Err(unbound(parameter))
+            }
+            VertexMutationCommandType::ExternalId(_) => (),
         }
     }
 
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::{
+    error::GraphDbError,
+    graph::property::{Property, PropertyValue},
+    parser::{parameter_name, ValidTypes},
+};
+
+use super::{
+    pattern::{ElementPattern, Operand, PatternQuery, Predicate},
+    Command, CommandType, EdgeMutationCommandType, EdgeStep, Repeat, TextPredicate,
+    VertexFilterCommandType, VertexMutationCommandType,
+};
+
+/// Values for a query's placeholders, by name. E.g. `Parameters::new().set("name", "alice")` for
+/// `g.V().hasPropertyValue(name, $name)`
+#[derive(Debug, Clone, Default)]
+pub struct Parameters {
+    values: HashMap<String, PropertyValue>,
+}
+
+impl Parameters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name may be given with or without its $
+    pub fn set(mut self, name: &str, value: impl Into<PropertyValue>) -> Self {
+        let name = name.strip_prefix('$').unwrap_or(name);
+        self.values.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.values.get(name)
+    }
+}
+
+/// A parsed command, kept to be run any number of times with different values for its
+/// placeholders. Placeholders can stand for labels, property names, property values, external
+/// ids, search terms, regexes and the values in a MATCH query
+#[derive(Debug)]
+pub struct PreparedQuery {
+    command: Command,
+    parameters: Vec<String>,
+}
+
+impl PreparedQuery {
+    pub fn new(command: Command) -> Result<Self, GraphDbError> {
+        let mut binder = Binder {
+            parameters: None,
+            names: Vec::new(),
+        };
+        binder.command(&command)?;
+
+        Ok(PreparedQuery {
+            command,
+            parameters: binder.names,
+        })
+    }
+
+    /// Names of the placeholders, in the order they first appear
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// The command with each placeholder replaced by its value. Every placeholder needs a value,
+    /// while values without a placeholder are ignored
+    pub fn bind(&self, parameters: &Parameters) -> Result<Command, GraphDbError> {
+        if let Some(name) = self
+            .parameters
+            .iter()
+            .find(|name| parameters.get(name).is_none())
+        {
+            return Err(unbound(name));
+        }
+
+        Binder {
+            parameters: Some(parameters),
+            names: Vec::new(),
+        }
+        .command(&self.command)
+    }
+}
+
+pub(crate) fn unbound(name: &str) -> GraphDbError {
+    GraphDbError::InvalidArgument(format!("No value bound for ${}", name))
+}
+
+/// Copies a command, replacing its placeholders. Without parameters, placeholders are kept and
+/// only their names are collected
+struct Binder<'a> {
+    parameters: Option<&'a Parameters>,
+    names: Vec<String>,
+}
+
+impl<'a> Binder<'a> {
+    fn command(&mut self, command: &Command) -> Result<Command, GraphDbError> {
+        let command_type = match &command.command_type {
+            CommandType::ListVertices(filters) => CommandType::ListVertices(self.filters(filters)?),
+            CommandType::Explain(filters) => CommandType::Explain(self.filters(filters)?),
+            CommandType::Profile(filters) => CommandType::Profile(self.filters(filters)?),
+            CommandType::AsOf(timestamp, filters) => {
+                CommandType::AsOf(*timestamp, self.filters(filters)?)
+            }
+            CommandType::AddVertex(label, mutations) => {
+                CommandType::AddVertex(self.name(label)?, self.mutations(mutations)?)
+            }
+            CommandType::EditVertex(id, mutations) => {
+                CommandType::EditVertex(*id, self.mutations(mutations)?)
+            }
+            CommandType::AddEdge(label, mutations) => {
+                CommandType::AddEdge(self.name(label)?, self.edge_mutations(mutations)?)
+            }
+            CommandType::EditEdge(id, mutations) => {
+                CommandType::EditEdge(*id, self.edge_mutations(mutations)?)
+            }
+            CommandType::TextSearch(name, query) => {
+                CommandType::TextSearch(self.name(name)?, self.text(query)?)
+            }
+            CommandType::Match(query) => CommandType::Match(self.pattern_query(query)?),
+            CommandType::MatchAsOf(timestamp, query) => {
+                CommandType::MatchAsOf(*timestamp, self.pattern_query(query)?)
+            }
+            other => other.clone(),
+        };
+
+        Ok(Command {
+            command_type,
+            command_json: command.command_json.clone(),
+        })
+    }
+
+    fn pattern_query(&mut self, query: &PatternQuery) -> Result<PatternQuery, GraphDbError> {
+        let mut query = query.clone();
+
+        for clause in &mut query.clauses {
+            for path in &mut clause.paths {
+                let relationships = path.relationships.iter_mut().map(|(element, _)| element);
+                for element in path.nodes.iter_mut().chain(relationships) {
+                    self.element(element)?;
+                }
+            }
+            if let Some(predicate) = &mut clause.predicate {
+                self.predicate(predicate)?;
+            }
+        }
+
+        Ok(query)
+    }
+
+    /// Moves a node or relationship's bound placeholders in with its other property values
+    fn element(&mut self, element: &mut ElementPattern) -> Result<(), GraphDbError> {
+        let mut unbound = Vec::new();
+        for (name, parameter) in element.parameters.drain(..) {
+            match self.lookup(&parameter)? {
+                Some(value) => element.properties.push((name, value.clone())),
+                None => unbound.push((name, parameter)),
+            }
+        }
+        element.parameters = unbound;
+
+        Ok(())
+    }
+
+    fn predicate(&mut self, predicate: &mut Predicate) -> Result<(), GraphDbError> {
+        match predicate {
+            Predicate::Compare(left, _, right) => {
+                self.operand(left)?;
+                self.operand(right)
+            }
+            Predicate::IsNull(operand) => self.operand(operand),
+            Predicate::Not(predicate) => self.predicate(predicate),
+            Predicate::And(left, right) | Predicate::Or(left, right) => {
+                self.predicate(left)?;
+                self.predicate(right)
+            }
+        }
+    }
+
+    fn operand(&mut self, operand: &mut Operand) -> Result<(), GraphDbError> {
+        if let Operand::Parameter(parameter) = operand {
+            if let Some(value) = self.lookup(parameter)? {
+                *operand = Operand::Literal(Some(value.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn filters(
+        &mut self,
+        filters: &[VertexFilterCommandType],
+    ) -> Result<Vec<VertexFilterCommandType>, GraphDbError> {
+        filters.iter().map(|filter| self.filter(filter)).collect()
+    }
+
+    fn filter(
+        &mut self,
+        filter: &VertexFilterCommandType,
+    ) -> Result<VertexFilterCommandType, GraphDbError> {
+        Ok(match filter {
+            VertexFilterCommandType::HasName(label) => {
+                VertexFilterCommandType::HasName(self.name(label)?)
+            }
+            VertexFilterCommandType::HasExternalId(id) => {
+                VertexFilterCommandType::HasExternalId(self.text(id)?)
+            }
+            VertexFilterCommandType::HasProperty(name) => {
+                VertexFilterCommandType::HasProperty(self.name(name)?)
+            }
+            VertexFilterCommandType::HasPropertyValue(name, value) => {
+                VertexFilterCommandType::HasPropertyValue(self.name(name)?, self.text(value)?)
+            }
+            VertexFilterCommandType::HasPropertyLike(name, term) => {
+                VertexFilterCommandType::HasPropertyLike(self.name(name)?, self.text(term)?)
+            }
+            VertexFilterCommandType::HasPropertyText(name, predicate, ignore_case) => {
+                // Search terms are lower case when ignoring case, so bound values must be too
+                let mut term = |term: &str| {
+                    self.text(term).map(|term| match ignore_case {
+                        true => term.to_lowercase(),
+                        false => term,
+                    })
+                };
+                let predicate = match predicate {
+                    TextPredicate::Equals(value) => TextPredicate::Equals(term(value)?),
+                    TextPredicate::Contains(value) => TextPredicate::Contains(term(value)?),
+                    TextPredicate::StartsWith(value) => TextPredicate::StartsWith(term(value)?),
+                    TextPredicate::EndsWith(value) => TextPredicate::EndsWith(term(value)?),
+                };
+                VertexFilterCommandType::HasPropertyText(self.name(name)?, predicate, *ignore_case)
+            }
+            VertexFilterCommandType::HasPropertyRegex(name, regex) => {
+                let regex = match parameter_name(regex.as_str()) {
+                    Some(_) => {
+                        let pattern = self.name(regex.as_str())?;
+                        Regex::new(&pattern).map_err(|err| {
+                            GraphDbError::InvalidArgument(format!(
+                                "Invalid regex {}: {}",
+                                regex.as_str(),
+                                err
+                            ))
+                        })?
+                    }
+                    None => regex.clone(),
+                };
+                VertexFilterCommandType::HasPropertyRegex(self.name(name)?, regex)
+            }
+            VertexFilterCommandType::HasPropertyWithinRadius(name, center, km) => {
+                VertexFilterCommandType::HasPropertyWithinRadius(self.name(name)?, *center, *km)
+            }
+            VertexFilterCommandType::HasPropertyWithinBox(name, bounding_box) => {
+                VertexFilterCommandType::HasPropertyWithinBox(self.name(name)?, *bounding_box)
+            }
+            VertexFilterCommandType::HasPropertyNearest(name, center, k) => {
+                VertexFilterCommandType::HasPropertyNearest(self.name(name)?, *center, *k)
+            }
+            VertexFilterCommandType::Nearest(name, vector, k, metric) => {
+                VertexFilterCommandType::Nearest(self.name(name)?, vector.clone(), *k, *metric)
+            }
+            VertexFilterCommandType::Out(label) => {
+                VertexFilterCommandType::Out(self.optional_name(label)?)
+            }
+            VertexFilterCommandType::In(label) => {
+                VertexFilterCommandType::In(self.optional_name(label)?)
+            }
+            VertexFilterCommandType::Values(name) => {
+                VertexFilterCommandType::Values(self.name(name)?)
+            }
+            VertexFilterCommandType::Repeat(repeat) => VertexFilterCommandType::Repeat(Repeat {
+                steps: self.filters(&repeat.steps)?,
+                until: match &repeat.until {
+                    Some(until) => Some(self.filters(until)?),
+                    None => None,
+                },
+                ..repeat.clone()
+            }),
+            VertexFilterCommandType::Edges(step) => VertexFilterCommandType::Edges(EdgeStep {
+                label: self.optional_name(&step.label)?,
+                filters: self.filters(&step.filters)?,
+                ..step.clone()
+            }),
+            VertexFilterCommandType::HasId(id) => VertexFilterCommandType::HasId(*id),
+        })
+    }
+
+    fn mutations(
+        &mut self,
+        mutations: &[VertexMutationCommandType],
+    ) -> Result<Vec<VertexMutationCommandType>, GraphDbError> {
+        mutations
+            .iter()
+            .map(|mutation| self.mutation(mutation))
+            .collect()
+    }
+
+    fn edge_mutations(
+        &mut self,
+        mutations: &[EdgeMutationCommandType],
+    ) -> Result<Vec<EdgeMutationCommandType>, GraphDbError> {
+        mutations
+            .iter()
+            .map(|mutation| match mutation {
+                EdgeMutationCommandType::VertexMutationCommandTypes(mutations) => Ok(
+                    EdgeMutationCommandType::VertexMutationCommandTypes(self.mutations(mutations)?),
+                ),
+                other => Ok(other.clone()),
+            })
+            .collect()
+    }
+
+    fn mutation(
+        &mut self,
+        mutation: &VertexMutationCommandType,
+    ) -> Result<VertexMutationCommandType, GraphDbError> {
+        Ok(match mutation {
+            VertexMutationCommandType::Property(property) => {
+                VertexMutationCommandType::Property(Property {
+                    name: self.name(&property.name)?,
+                    ..property.clone()
+                })
+            }
+            VertexMutationCommandType::PropertyParameter(name, parameter, value_type) => {
+                match self.lookup(parameter)? {
+                    Some(value) => {
+                        check_type(parameter, value, *value_type)?;
+                        VertexMutationCommandType::Property(Property {
+                            name: self.name(name)?,
+                            value: value.clone(),
+                            flagged_for_removal: false,
+                        })
+                    }
+                    None => VertexMutationCommandType::PropertyParameter(
+                        self.name(name)?,
+                        parameter.clone(),
+                        *value_type,
+                    ),
+                }
+            }
+            VertexMutationCommandType::RemoveProperty(name) => {
+                VertexMutationCommandType::RemoveProperty(self.name(name)?)
+            }
+            VertexMutationCommandType::ExternalId(id) => {
+                VertexMutationCommandType::ExternalId(self.text(id)?)
+            }
+        })
+    }
+
+    /// An argument naming a label or property, so any value bound to it must be a string
+    fn name(&mut self, argument: &str) -> Result<String, GraphDbError> {
+        let Some(parameter) = parameter_name(argument) else {
+            return Ok(argument.to_string());
+        };
+
+        match self.lookup(parameter)? {
+            Some(value) => {
+                check_type(parameter, value, Some(ValidTypes::String))?;
+                Ok(value.to_string())
+            }
+            None => Ok(argument.to_string()),
+        }
+    }
+
+    fn optional_name(&mut self, argument: &Option<String>) -> Result<Option<String>, GraphDbError> {
+        argument
+            .as_deref()
+            .map(|argument| self.name(argument))
+            .transpose()
+    }
+
+    /// An argument compared against property values as text, so any value can be bound to it
+    fn text(&mut self, argument: &str) -> Result<String, GraphDbError> {
+        let Some(parameter) = parameter_name(argument) else {
+            return Ok(argument.to_string());
+        };
+
+        match self.lookup(parameter)? {
+            Some(value) => Ok(value.to_string()),
+            None => Ok(argument.to_string()),
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Result<Option<&'a PropertyValue>, GraphDbError> {
+        if !self.names.iter().any(|known| known == name) {
+            self.names.push(name.to_string());
+        }
+
+        match self.parameters {
+            Some(parameters) => parameters.get(name).map(Some).ok_or_else(|| unbound(name)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn check_type(
+    name: &str,
+    value: &PropertyValue,
+    value_type: Option<ValidTypes>,
+) -> Result<(), GraphDbError> {
+    match value_type {
+        Some(value_type) if value_type.as_str() != value.type_name() => {
+            Err(GraphDbError::TypeMismatch {
+                expected: value_type.as_str().to_string(),
+                found: format!("${} ({})", name, value.type_name()),
+            })
+        }
+        _ => Ok(()),
+    }
+}
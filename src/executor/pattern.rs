@@ -0,0 +1,811 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use crate::{
+    error::GraphDbError,
+    graph::{
+        adjacency::{AdjacentEdge, Direction},
+        edge::Edge,
+        property::PropertyValue,
+        vertex::Vertex,
+        DataResult, Graph,
+    },
+};
+
+use super::{parameters::unbound, VertexFilterCommandType};
+
+/// Each equality a node must meet is assumed to leave this fraction of its candidates
+const EQUALITY_SELECTIVITY: usize = 10;
+
+/// A Cypher style query, e.g. MATCH (a:person)-[:knows]->(b) WHERE a.name = 'alice' RETURN b.
+/// Clauses are matched in order, each extending the rows matched so far
+#[derive(Debug, Clone)]
+pub struct PatternQuery {
+    pub clauses: Vec<MatchClause>,
+    pub returns: Vec<ReturnItem>, // empty for RETURN *
+    pub limit: Option<usize>,
+}
+
+/// A MATCH or OPTIONAL MATCH, along with its WHERE predicate
+#[derive(Debug, Clone)]
+pub struct MatchClause {
+    pub optional: bool,
+    pub paths: Vec<PathPattern>,
+    pub predicate: Option<Predicate>,
+}
+
+/// Nodes joined by relationships, where relationships[i] joins nodes[i] and nodes[i + 1]
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    pub nodes: Vec<ElementPattern>,
+    pub relationships: Vec<(ElementPattern, PatternDirection)>,
+}
+
+/// A node, e.g. (a:person {name: 'alice'}), or a relationship, e.g. [r:knows]
+#[derive(Debug, Clone, Default)]
+pub struct ElementPattern {
+    pub variable: Option<String>,
+    pub label: Option<String>,
+    pub properties: Vec<(String, PropertyValue)>,
+    pub parameters: Vec<(String, String)>, // property name, placeholder name, until bound
+}
+
+/// The way a relationship's arrow points, as written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatternDirection {
+    Right,  // -[]->
+    Left,   // <-[]-
+    Either, // -[]-
+}
+
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Compare(Operand, Comparison, Operand),
+    IsNull(Operand),
+    Not(Box<Predicate>),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "<>",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        match name {
+            _ if name == Comparison::Equal.as_str() => Ok(Comparison::Equal),
+            _ if name == Comparison::NotEqual.as_str() || name == "!=" => Ok(Comparison::NotEqual),
+            _ if name == Comparison::Less.as_str() => Ok(Comparison::Less),
+            _ if name == Comparison::LessOrEqual.as_str() => Ok(Comparison::LessOrEqual),
+            _ if name == Comparison::Greater.as_str() => Ok(Comparison::Greater),
+            _ if name == Comparison::GreaterOrEqual.as_str() => Ok(Comparison::GreaterOrEqual),
+            _ => Err(GraphDbError::parse(
+                "a comparison: =, <>, <, <=, > or >=",
+                name,
+            )),
+        }
+    }
+
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Operand {
+    Variable(String),
+    Property(String, String),       // variable, property name
+    Literal(Option<PropertyValue>), // None is null
+    Parameter(String),              // placeholder name, until bound
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Variable(variable) => write!(f, "{}", variable),
+            Operand::Property(variable, name) => write!(f, "{}.{}", variable, name),
+            Operand::Literal(Some(value)) => write!(f, "{}", value),
+            Operand::Literal(None) => write!(f, "null"),
+            Operand::Parameter(name) => write!(f, "${}", name),
+        }
+    }
+}
+
+/// A column of the result, e.g. b, or b.name AS name
+#[derive(Debug, Clone)]
+pub struct ReturnItem {
+    pub operand: Operand,
+    pub alias: Option<String>,
+}
+
+impl ReturnItem {
+    fn column(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.to_string(),
+            None => self.operand.to_string(),
+        }
+    }
+}
+
+/// A value in a row of MATCH results. Variables an optional match didn't find are null
+#[derive(Debug, Clone)]
+pub enum Binding {
+    Vertex(usize, Vertex),
+    Edge(usize, Edge),
+    Value(PropertyValue),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Null,
+    Vertex(usize),
+    Edge(usize),
+}
+
+/// A slot per variable, in the order they first appear. Unset until matched
+type Row = Vec<Option<Bound>>;
+
+/// An evaluated operand
+enum Value {
+    Null,
+    Entity(Bound),
+    Property(PropertyValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ElementKind {
+    Node,
+    Relationship,
+}
+
+/// One hop of a path, from a matched node across a relationship to the next node
+struct Step {
+    from: usize,         // node index within the path
+    relationship: usize, // relationship index within the path
+    to: usize,           // node index within the path
+    directions: &'static [Direction],
+}
+
+struct PlannedPath<'q> {
+    nodes: Vec<(usize, &'q ElementPattern)>, // slot, pattern
+    relationships: Vec<(usize, &'q ElementPattern)>,
+    start: usize,                   // node index the match starts from
+    candidates: Option<Vec<usize>>, // vertices for an unbound start node
+    steps: Vec<Step>,
+}
+
+struct PlannedClause<'q> {
+    clause: &'q MatchClause,
+    paths: Vec<PlannedPath<'q>>,
+    introduced: Vec<usize>, // slots first bound by this clause
+}
+
+/// Runs a MATCH query under a read lock, caching the vertices and edges it looks at
+pub fn execute(graph: &dyn Graph, query: &PatternQuery) -> Result<DataResult, GraphDbError> {
+    let mut matcher = Matcher {
+        graph,
+        variables: Vec::new(),
+        kinds: Vec::new(),
+        vertices: HashMap::new(),
+        edges: HashMap::new(),
+    };
+
+    let clauses = matcher.plan(query)?;
+    let (columns, items) = matcher.columns(query)?;
+
+    let mut rows: Vec<Row> = vec![vec![None; matcher.variables.len()]];
+    for clause in &clauses {
+        rows = matcher.match_clause(clause, rows)?;
+    }
+    if let Some(limit) = query.limit {
+        rows.truncate(limit);
+    }
+
+    let rows = rows
+        .iter()
+        .map(|row| {
+            items
+                .iter()
+                .map(|item| matcher.binding(item, row))
+                .collect::<Result<Vec<Binding>, GraphDbError>>()
+        })
+        .collect::<Result<Vec<Vec<Binding>>, GraphDbError>>()?;
+
+    Ok(DataResult::Table(columns, rows))
+}
+
+struct Matcher<'g> {
+    graph: &'g dyn Graph,
+    variables: Vec<Option<String>>, // slot names. Anonymous nodes and relationships have none
+    kinds: Vec<ElementKind>,
+    vertices: HashMap<usize, Vertex>,
+    edges: HashMap<usize, Edge>,
+}
+
+impl<'g> Matcher<'g> {
+    /// Assigns every node and relationship a slot, and picks where each path starts
+    fn plan<'q>(
+        &mut self,
+        query: &'q PatternQuery,
+    ) -> Result<Vec<PlannedClause<'q>>, GraphDbError> {
+        let mut clauses = Vec::new();
+
+        for clause in &query.clauses {
+            let first_slot = self.variables.len();
+            let mut paths = Vec::new();
+
+            for path in &clause.paths {
+                let bound = self.variables.len();
+                let nodes = path
+                    .nodes
+                    .iter()
+                    .map(|node| Ok((self.slot(node, ElementKind::Node)?, node)))
+                    .collect::<Result<Vec<_>, GraphDbError>>()?;
+                let relationships = path
+                    .relationships
+                    .iter()
+                    .map(|(relationship, _)| {
+                        Ok((
+                            self.slot(relationship, ElementKind::Relationship)?,
+                            relationship,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, GraphDbError>>()?;
+
+                // Slots from before this path are bound by the time it is matched
+                let start = self.choose_start(&nodes, bound, clause.predicate.as_ref());
+                let candidates = match nodes[start].0 < bound {
+                    true => None,
+                    false => Some(self.candidates(nodes[start].1)?),
+                };
+
+                paths.push(PlannedPath {
+                    steps: steps(path, start),
+                    nodes,
+                    relationships,
+                    start,
+                    candidates,
+                });
+            }
+
+            if let Some(predicate) = &clause.predicate {
+                self.check_predicate(predicate)?;
+            }
+
+            clauses.push(PlannedClause {
+                clause,
+                paths,
+                introduced: (first_slot..self.variables.len()).collect(),
+            });
+        }
+
+        Ok(clauses)
+    }
+
+    fn slot(&mut self, element: &ElementPattern, kind: ElementKind) -> Result<usize, GraphDbError> {
+        if let Some((_, parameter)) = element.parameters.first() {
+            return Err(unbound(parameter));
+        }
+
+        if let Some(variable) = &element.variable {
+            if let Some(slot) = self.find(variable) {
+                return match self.kinds[slot] == kind {
+                    true => Ok(slot),
+                    false => Err(GraphDbError::InvalidArgument(format!(
+                        "'{}' is used as both a node and a relationship",
+                        variable
+                    ))),
+                };
+            }
+        }
+
+        self.variables.push(element.variable.clone());
+        self.kinds.push(kind);
+        Ok(self.variables.len() - 1)
+    }
+
+    fn find(&self, variable: &str) -> Option<usize> {
+        self.variables
+            .iter()
+            .position(|name| name.as_deref() == Some(variable))
+    }
+
+    fn lookup(&self, variable: &str) -> Result<usize, GraphDbError> {
+        self.find(variable).ok_or_else(|| {
+            GraphDbError::InvalidArgument(format!("Unknown variable '{}'", variable))
+        })
+    }
+
+    /// Starts from a node bound by an earlier pattern if there is one. Otherwise from the node
+    /// with the fewest vertices carrying its label, less for each equality it has to meet
+    fn choose_start(
+        &self,
+        nodes: &[(usize, &ElementPattern)],
+        bound: usize,
+        predicate: Option<&Predicate>,
+    ) -> usize {
+        let estimate = |(slot, node): &(usize, &ElementPattern)| {
+            if *slot < bound {
+                return 0;
+            }
+
+            let equalities = node.properties.len()
+                + match (&node.variable, predicate) {
+                    (Some(variable), Some(predicate)) => equality_count(predicate, variable),
+                    _ => 0,
+                };
+            let count = self.graph.vertex_count(node.label.as_deref());
+            (count / EQUALITY_SELECTIVITY.saturating_pow(equalities as u32)).max(1)
+        };
+
+        nodes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, node)| estimate(node))
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    /// Vertices an unbound start node could match, from the label index where it has a label
+    fn candidates(&mut self, node: &ElementPattern) -> Result<Vec<usize>, GraphDbError> {
+        let filters: Vec<VertexFilterCommandType> = node
+            .label
+            .iter()
+            .map(|label| VertexFilterCommandType::HasName(label.to_string()))
+            .collect();
+
+        match self.graph.list_vertices(&filters)? {
+            DataResult::VertexVector(vertices) => Ok(vertices
+                .into_iter()
+                .map(|(id, vertex)| {
+                    self.vertices.insert(id, vertex);
+                    id
+                })
+                .collect()),
+            other => Err(GraphDbError::Internal(format!(
+                "Expected vertices, found {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn check_predicate(&self, predicate: &Predicate) -> Result<(), GraphDbError> {
+        match predicate {
+            Predicate::Compare(left, _, right) => {
+                self.check_operand(left)?;
+                self.check_operand(right)
+            }
+            Predicate::IsNull(operand) => self.check_operand(operand),
+            Predicate::Not(predicate) => self.check_predicate(predicate),
+            Predicate::And(left, right) | Predicate::Or(left, right) => {
+                self.check_predicate(left)?;
+                self.check_predicate(right)
+            }
+        }
+    }
+
+    fn check_operand(&self, operand: &Operand) -> Result<(), GraphDbError> {
+        match operand {
+            Operand::Variable(variable) | Operand::Property(variable, _) => {
+                self.lookup(variable).map(|_| ())
+            }
+            Operand::Literal(_) => Ok(()),
+            Operand::Parameter(name) => Err(unbound(name)),
+        }
+    }
+
+    /// Column names, and what each column holds. RETURN * returns every named variable
+    fn columns(&self, query: &PatternQuery) -> Result<(Vec<String>, Vec<Operand>), GraphDbError> {
+        if query.returns.is_empty() {
+            let variables: Vec<String> = self.variables.iter().flatten().cloned().collect();
+            let operands = variables.iter().cloned().map(Operand::Variable).collect();
+            return Ok((variables, operands));
+        }
+
+        for item in &query.returns {
+            self.check_operand(&item.operand)?;
+        }
+        Ok((
+            query.returns.iter().map(|item| item.column()).collect(),
+            query
+                .returns
+                .iter()
+                .map(|item| item.operand.clone())
+                .collect(),
+        ))
+    }
+
+    fn match_clause(
+        &mut self,
+        planned: &PlannedClause,
+        rows: Vec<Row>,
+    ) -> Result<Vec<Row>, GraphDbError> {
+        let mut results = Vec::new();
+
+        for row in rows {
+            let mut matches = vec![row.clone()];
+            for path in &planned.paths {
+                let mut extended = Vec::new();
+                for row in matches {
+                    self.match_path(planned, path, row, &mut extended)?;
+                }
+                matches = extended;
+            }
+
+            if let Some(predicate) = &planned.clause.predicate {
+                let mut kept = Vec::new();
+                for row in matches {
+                    if self.evaluate(predicate, &row)? == Some(true) {
+                        kept.push(row);
+                    }
+                }
+                matches = kept;
+            }
+
+            // An optional match that finds nothing keeps the row, with its variables null
+            if matches.is_empty() && planned.clause.optional {
+                let mut row = row;
+                for slot in &planned.introduced {
+                    row[*slot] = Some(Bound::Null);
+                }
+                matches.push(row);
+            }
+
+            results.extend(matches);
+        }
+
+        Ok(results)
+    }
+
+    fn match_path(
+        &mut self,
+        planned: &PlannedClause,
+        path: &PlannedPath,
+        row: Row,
+        results: &mut Vec<Row>,
+    ) -> Result<(), GraphDbError> {
+        let (slot, node) = path.nodes[path.start];
+        let starts = match (&path.candidates, row[slot]) {
+            (_, Some(Bound::Vertex(id))) => vec![id],
+            (_, Some(_)) => Vec::new(),
+            (Some(candidates), None) => candidates.clone(),
+            (None, None) => Vec::new(),
+        };
+
+        // A relationship is matched at most once per clause
+        let mut used: Vec<usize> = planned
+            .introduced
+            .iter()
+            .filter_map(|slot| match row[*slot] {
+                Some(Bound::Edge(id)) => Some(id),
+                _ => None,
+            })
+            .collect();
+
+        for id in starts {
+            if !self.vertex_matches(id, node)? {
+                continue;
+            }
+
+            let mut row = row.clone();
+            row[slot] = Some(Bound::Vertex(id));
+            self.extend(path, 0, row, &mut used, results)?;
+        }
+
+        Ok(())
+    }
+
+    fn extend(
+        &mut self,
+        path: &PlannedPath,
+        step_index: usize,
+        row: Row,
+        used: &mut Vec<usize>,
+        results: &mut Vec<Row>,
+    ) -> Result<(), GraphDbError> {
+        let step = match path.steps.get(step_index) {
+            Some(step) => step,
+            None => {
+                results.push(row);
+                return Ok(());
+            }
+        };
+
+        let from = match row[path.nodes[step.from].0] {
+            Some(Bound::Vertex(id)) => id,
+            _ => return Ok(()),
+        };
+        let (relationship_slot, relationship) = path.relationships[step.relationship];
+        let (node_slot, node) = path.nodes[step.to];
+
+        // Either way already gives every edge in an undirected graph
+        let directions = match self.graph.options().directed {
+            true => step.directions,
+            false => &step.directions[..1],
+        };
+        for direction in directions {
+            let adjacent: Vec<AdjacentEdge> =
+                self.graph.adjacent_edges(&from, *direction)?.collect();
+
+            for adjacent in adjacent {
+                // Followed both ways, a self loop would otherwise be seen twice
+                if *direction == Direction::In
+                    && step.directions.len() > 1
+                    && adjacent.vertex_id == from
+                {
+                    continue;
+                }
+
+                if used.contains(&adjacent.edge_id)
+                    || !fits(&row, relationship_slot, Bound::Edge(adjacent.edge_id))
+                    || !fits(&row, node_slot, Bound::Vertex(adjacent.vertex_id))
+                    || !self.edge_matches(adjacent.edge_id, relationship)?
+                    || !self.vertex_matches(adjacent.vertex_id, node)?
+                {
+                    continue;
+                }
+
+                let mut row = row.clone();
+                row[relationship_slot] = Some(Bound::Edge(adjacent.edge_id));
+                row[node_slot] = Some(Bound::Vertex(adjacent.vertex_id));
+
+                used.push(adjacent.edge_id);
+                self.extend(path, step_index + 1, row, used, results)?;
+                used.pop();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn vertex(&mut self, id: usize) -> Result<&Vertex, GraphDbError> {
+        if !self.vertices.contains_key(&id) {
+            match self.graph.get_vertex(&id)? {
+                DataResult::Vertex(vertex) => {
+                    self.vertices.insert(id, vertex);
+                }
+                other => {
+                    return Err(GraphDbError::Internal(format!(
+                        "Expected a vertex, found {:?}",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(&self.vertices[&id])
+    }
+
+    fn edge(&mut self, id: usize) -> Result<&Edge, GraphDbError> {
+        if !self.edges.contains_key(&id) {
+            match self.graph.get_edge(&id)? {
+                DataResult::Edge(edge) => {
+                    self.edges.insert(id, edge);
+                }
+                other => {
+                    return Err(GraphDbError::Internal(format!(
+                        "Expected an edge, found {:?}",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(&self.edges[&id])
+    }
+
+    fn vertex_matches(
+        &mut self,
+        id: usize,
+        pattern: &ElementPattern,
+    ) -> Result<bool, GraphDbError> {
+        if pattern.label.is_none() && pattern.properties.is_empty() {
+            return Ok(true);
+        }
+
+        Ok(element_matches(self.vertex(id)?, pattern))
+    }
+
+    fn edge_matches(&mut self, id: usize, pattern: &ElementPattern) -> Result<bool, GraphDbError> {
+        if pattern.label.is_none() && pattern.properties.is_empty() {
+            return Ok(true);
+        }
+
+        Ok(element_matches(&self.edge(id)?.edge_vertex, pattern))
+    }
+
+    /// Three valued: None when the outcome is unknown, e.g. comparing against null
+    fn evaluate(&mut self, predicate: &Predicate, row: &Row) -> Result<Option<bool>, GraphDbError> {
+        Ok(match predicate {
+            Predicate::Compare(left, comparison, right) => {
+                let left = self.value(left, row)?;
+                let right = self.value(right, row)?;
+                compare(&left, *comparison, &right)
+            }
+            Predicate::IsNull(operand) => Some(matches!(self.value(operand, row)?, Value::Null)),
+            Predicate::Not(predicate) => self.evaluate(predicate, row)?.map(|value| !value),
+            Predicate::And(left, right) => {
+                match (self.evaluate(left, row)?, self.evaluate(right, row)?) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Predicate::Or(left, right) => {
+                match (self.evaluate(left, row)?, self.evaluate(right, row)?) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+        })
+    }
+
+    fn value(&mut self, operand: &Operand, row: &Row) -> Result<Value, GraphDbError> {
+        let (variable, name) = match operand {
+            Operand::Literal(Some(value)) => return Ok(Value::Property(value.clone())),
+            Operand::Literal(None) => return Ok(Value::Null),
+            Operand::Parameter(name) => return Err(unbound(name)),
+            Operand::Variable(variable) => (variable, None),
+            Operand::Property(variable, name) => (variable, Some(name)),
+        };
+
+        let bound = match row[self.lookup(variable)?] {
+            Some(Bound::Null) | None => return Ok(Value::Null),
+            Some(bound) => bound,
+        };
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(Value::Entity(bound)),
+        };
+
+        let value = match bound {
+            Bound::Vertex(id) => self.vertex(id)?.get_property_value(name).cloned(),
+            Bound::Edge(id) => self.edge(id)?.edge_vertex.get_property_value(name).cloned(),
+            Bound::Null => None,
+        };
+        Ok(value.map_or(Value::Null, Value::Property))
+    }
+
+    fn binding(&mut self, operand: &Operand, row: &Row) -> Result<Binding, GraphDbError> {
+        Ok(match self.value(operand, row)? {
+            Value::Null | Value::Entity(Bound::Null) => Binding::Null,
+            Value::Entity(Bound::Vertex(id)) => Binding::Vertex(id, self.vertex(id)?.clone()),
+            Value::Entity(Bound::Edge(id)) => Binding::Edge(id, self.edge(id)?.clone()),
+            Value::Property(value) => Binding::Value(value),
+        })
+    }
+}
+
+/// The hops to match from the start node: forwards to the end of the path, then backwards to its
+/// beginning
+fn steps(path: &PathPattern, start: usize) -> Vec<Step> {
+    let forwards = (start..path.relationships.len()).map(|i| Step {
+        from: i,
+        relationship: i,
+        to: i + 1,
+        directions: directions(path.relationships[i].1, true),
+    });
+    let backwards = (0..start).rev().map(|i| Step {
+        from: i + 1,
+        relationship: i,
+        to: i,
+        directions: directions(path.relationships[i].1, false),
+    });
+
+    forwards.chain(backwards).collect()
+}
+
+/// Which adjacency lists to follow a relationship through, walking the path forwards or backwards
+fn directions(direction: PatternDirection, forwards: bool) -> &'static [Direction] {
+    match (direction, forwards) {
+        (PatternDirection::Right, true) | (PatternDirection::Left, false) => &[Direction::Out],
+        (PatternDirection::Right, false) | (PatternDirection::Left, true) => &[Direction::In],
+        (PatternDirection::Either, _) => &[Direction::Out, Direction::In],
+    }
+}
+
+/// Whether the slot is free to take the value, or already holds it
+fn fits(row: &Row, slot: usize, value: Bound) -> bool {
+    match row[slot] {
+        None => true,
+        Some(bound) => bound == value,
+    }
+}
+
+fn element_matches(vertex: &Vertex, pattern: &ElementPattern) -> bool {
+    if let Some(label) = &pattern.label {
+        if &vertex.label != label {
+            return false;
+        }
+    }
+
+    pattern.properties.iter().all(|(name, value)| {
+        vertex
+            .get_property_value(name)
+            .and_then(|property| order_values(property, value))
+            == Some(Ordering::Equal)
+    })
+}
+
+/// How many of the predicate's top level conjuncts compare the variable's properties to a value
+fn equality_count(predicate: &Predicate, variable: &str) -> usize {
+    match predicate {
+        Predicate::And(left, right) => {
+            equality_count(left, variable) + equality_count(right, variable)
+        }
+        Predicate::Compare(Operand::Property(name, _), Comparison::Equal, Operand::Literal(_))
+        | Predicate::Compare(Operand::Literal(_), Comparison::Equal, Operand::Property(name, _))
+            if name == variable =>
+        {
+            1
+        }
+        _ => 0,
+    }
+}
+
+fn compare(left: &Value, comparison: Comparison, right: &Value) -> Option<bool> {
+    let equality = matches!(comparison, Comparison::Equal | Comparison::NotEqual);
+
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        (Value::Entity(left), Value::Entity(right)) if equality => {
+            Some((left == right) == (comparison == Comparison::Equal))
+        }
+        (Value::Property(left), Value::Property(right)) => match order_values(left, right) {
+            Some(ordering) => Some(comparison.holds(ordering)),
+            None if equality => Some(comparison == Comparison::NotEqual),
+            None => None,
+        },
+        _ if equality => Some(comparison == Comparison::NotEqual),
+        _ => None,
+    }
+}
+
+/// Numbers compare across types, e.g. an int32 property against an integer literal
+fn order_values(left: &PropertyValue, right: &PropertyValue) -> Option<Ordering> {
+    match (left, right) {
+        (PropertyValue::String(left), PropertyValue::String(right)) => Some(left.cmp(right)),
+        (PropertyValue::Boolean(left), PropertyValue::Boolean(right)) => Some(left.cmp(right)),
+        _ => match (as_number(left), as_number(right)) {
+            (Some(left), Some(right)) => left.partial_cmp(&right),
+            _ => (left == right).then_some(Ordering::Equal),
+        },
+    }
+}
+
+fn as_number(value: &PropertyValue) -> Option<f64> {
+    match value {
+        PropertyValue::Int32(value) => Some(*value as f64),
+        PropertyValue::Int64(value) => Some(*value as f64),
+        PropertyValue::Float32(value) => Some(*value as f64),
+        PropertyValue::Float64(value) => Some(*value),
+        PropertyValue::DateTime(value) => Some(*value as f64),
+        _ => None,
+    }
+}
@@ -0,0 +1,475 @@
+use std::fmt;
+
+use crate::{
+    error::GraphDbError,
+    graph::{
+        changes::now,
+        edge::Edge,
+        property::{Property, PropertyValue},
+        vertex::Vertex,
+        DataResult, Graph,
+    },
+};
+
+use super::VertexMutationCommandType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerTiming {
+    Before,
+    After,
+}
+
+impl TriggerTiming {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerTiming::Before => "before",
+            TriggerTiming::After => "after",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == TriggerTiming::Before.as_str() => Ok(TriggerTiming::Before),
+            _ if name == TriggerTiming::After.as_str() => Ok(TriggerTiming::After),
+            other => Err(GraphDbError::parse("before or after", other)),
+        }
+    }
+}
+
+/// The mutation a trigger runs on. Vertex events match the vertex's label, and edge events the edge's
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    Delete,
+    InsertEdge,
+    UpdateEdge,
+    DeleteEdge, // by deleteE, or along with either of their vertices
+}
+
+impl TriggerEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerEvent::Insert => "insert",
+            TriggerEvent::Update => "update",
+            TriggerEvent::Delete => "delete",
+            TriggerEvent::InsertEdge => "insertEdge",
+            TriggerEvent::UpdateEdge => "updateEdge",
+            TriggerEvent::DeleteEdge => "deleteEdge",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == TriggerEvent::Insert.as_str() => Ok(TriggerEvent::Insert),
+            _ if name == TriggerEvent::Update.as_str() => Ok(TriggerEvent::Update),
+            _ if name == TriggerEvent::Delete.as_str() => Ok(TriggerEvent::Delete),
+            _ if name == TriggerEvent::InsertEdge.as_str() => Ok(TriggerEvent::InsertEdge),
+            _ if name == TriggerEvent::UpdateEdge.as_str() => Ok(TriggerEvent::UpdateEdge),
+            _ if name == TriggerEvent::DeleteEdge.as_str() => Ok(TriggerEvent::DeleteEdge),
+            other => Err(GraphDbError::parse(
+                "one of insert, update, delete, insertEdge, updateEdge or deleteEdge",
+                other,
+            )),
+        }
+    }
+
+    fn is_edge_event(&self) -> bool {
+        matches!(
+            self,
+            TriggerEvent::InsertEdge | TriggerEvent::UpdateEdge | TriggerEvent::DeleteEdge
+        )
+    }
+}
+
+/// Which vertex of an edge an after edge trigger changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeEnd {
+    From,
+    To,
+}
+
+impl EdgeEnd {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeEnd::From => "from",
+            EdgeEnd::To => "to",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == EdgeEnd::From.as_str() => Ok(EdgeEnd::From),
+            _ if name == EdgeEnd::To.as_str() => Ok(EdgeEnd::To),
+            other => Err(GraphDbError::parse("from or to", other)),
+        }
+    }
+}
+
+/// Run in the order they were written, each seeing the properties as the one before left them
+#[derive(Debug, Clone)]
+pub enum TriggerAction {
+    Mutation(VertexMutationCommandType), // property or removeProperty
+    Now(String),                         // datetime property to set to the current time
+    Increment(String, i64),              // integer property, amount. Missing properties start at 0
+    Require(String),                     // property the entity must have
+    Reject(String),                      // message
+}
+
+impl TriggerAction {
+    fn changes_properties(&self) -> bool {
+        matches!(
+            self,
+            TriggerAction::Mutation(_) | TriggerAction::Now(_) | TriggerAction::Increment(..)
+        )
+    }
+}
+
+/// A rule run by the executor whenever a matching mutation is made. Before triggers run first,
+/// and can reject the mutation or change the properties of the entity being inserted or updated.
+/// After triggers change the inserted or updated vertex as part of the same change, or one end of
+/// an inserted, updated or deleted edge once the mutation is made. Their changes are worked out first, so
+/// one that fails leaves the graph as it was. Changes made by triggers don't run other triggers.
+///
+/// Triggers only run for commands given to the executor, e.g. through `Database::query`. The typed
+/// `GraphHandle` API writes to the graph directly
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub name: String,
+    pub timing: TriggerTiming,
+    pub event: TriggerEvent,
+    pub label: String,
+    pub target: Option<EdgeEnd>, // only for after edge triggers
+    pub actions: Vec<TriggerAction>,
+}
+
+impl Trigger {
+    /// Rejects triggers whose actions can't apply to their event
+    pub fn validate(&self) -> Result<(), GraphDbError> {
+        let invalid = |message: &str| {
+            Err(GraphDbError::InvalidArgument(format!(
+                "Trigger '{}': {}",
+                self.name, message
+            )))
+        };
+
+        let changes_properties = self.actions.iter().any(|a| a.changes_properties());
+        let checks = self.actions.iter().any(|a| !a.changes_properties());
+        let after_edge = self.timing == TriggerTiming::After && self.event.is_edge_event();
+
+        if self.actions.is_empty() {
+            return invalid("needs at least one action");
+        }
+        if checks && self.timing == TriggerTiming::After {
+            return invalid("only before triggers can require properties or reject mutations");
+        }
+        if changes_properties
+            && matches!(self.event, TriggerEvent::Delete | TriggerEvent::DeleteEdge)
+            && !after_edge
+        {
+            return invalid("deleted entities can't have properties set");
+        }
+        if changes_properties && after_edge && self.target.is_none() {
+            return invalid("after edge triggers need a target(from|to) vertex to change");
+        }
+        if self.target.is_some() && !after_edge {
+            return invalid("only after edge triggers have a target vertex");
+        }
+
+        Ok(())
+    }
+
+    fn matches(&self, timing: TriggerTiming, event: TriggerEvent, label: &str) -> bool {
+        self.timing == timing && self.event == event && self.label == label
+    }
+
+    /// Runs the actions against an entity's properties, changing them in place
+    fn run(&self, properties: &mut Vec<Property>) -> Result<(), GraphDbError> {
+        for action in &self.actions {
+            match action {
+                TriggerAction::Mutation(VertexMutationCommandType::Property(property)) => {
+                    set_property(
+                        properties,
+                        property.name.to_owned(),
+                        property.value.to_owned(),
+                    )
+                }
+                TriggerAction::Mutation(VertexMutationCommandType::RemoveProperty(name)) => {
+                    properties.retain(|property| &property.name != name)
+                }
+                TriggerAction::Mutation(
+                    VertexMutationCommandType::ExternalId(_)
+                    | VertexMutationCommandType::PropertyParameter(..),
+                ) => (),
+                TriggerAction::Now(name) => {
+                    set_property(properties, name.to_owned(), PropertyValue::DateTime(now()))
+                }
+                TriggerAction::Increment(name, amount) => {
+                    let current = properties.iter().find(|property| &property.name == name);
+                    let value = match current.map(|property| &property.value) {
+                        None => Some(PropertyValue::Int64(*amount)),
+                        Some(PropertyValue::Int64(val)) => {
+                            val.checked_add(*amount).map(PropertyValue::Int64)
+                        }
+                        Some(PropertyValue::Int32(val)) => i32::try_from(*amount)
+                            .ok()
+                            .and_then(|amount| val.checked_add(amount))
+                            .map(PropertyValue::Int32),
+                        Some(other) => {
+                            return Err(GraphDbError::TypeMismatch {
+                                expected: format!("an int32 or int64 '{}' to increment", name),
+                                found: other.type_name().to_string(),
+                            })
+                        }
+                    };
+
+                    match value {
+                        Some(value) => set_property(properties, name.to_owned(), value),
+                        None => {
+                            return Err(GraphDbError::ConstraintViolation(format!(
+                                "Trigger '{}' would overflow property '{}'",
+                                self.name, name
+                            )))
+                        }
+                    }
+                }
+                TriggerAction::Require(name) => {
+                    if !properties.iter().any(|property| &property.name == name) {
+                        return Err(GraphDbError::ConstraintViolation(format!(
+                            "Trigger '{}' requires {} {} to have property '{}'",
+                            self.name,
+                            self.label,
+                            entity_name(self.event),
+                            name
+                        )));
+                    }
+                }
+                TriggerAction::Reject(message) => {
+                    return Err(GraphDbError::ConstraintViolation(format!(
+                        "Rejected by trigger '{}': {}",
+                        self.name, message
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Written the way it was created, e.g. "count: after insertEdge follows .target(to).increment(followers, 1)"
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} {} ",
+            self.name,
+            self.timing.as_str(),
+            self.event.as_str(),
+            self.label
+        )?;
+        if let Some(target) = self.target {
+            write!(f, ".target({})", target.as_str())?;
+        }
+
+        for action in &self.actions {
+            match action {
+                TriggerAction::Mutation(VertexMutationCommandType::Property(property)) => write!(
+                    f,
+                    ".property({}, {}, {})",
+                    property.name,
+                    property.value,
+                    property.value.type_name()
+                )?,
+                TriggerAction::Mutation(VertexMutationCommandType::PropertyParameter(
+                    name,
+                    parameter,
+                    _,
+                )) => write!(f, ".property({}, ${})", name, parameter)?,
+                TriggerAction::Mutation(VertexMutationCommandType::RemoveProperty(name)) => {
+                    write!(f, ".removeProperty({})", name)?
+                }
+                TriggerAction::Mutation(VertexMutationCommandType::ExternalId(id)) => {
+                    write!(f, ".externalId({})", id)?
+                }
+                TriggerAction::Now(name) => write!(f, ".now({})", name)?,
+                TriggerAction::Increment(name, amount) => {
+                    write!(f, ".increment({}, {})", name, amount)?
+                }
+                TriggerAction::Require(name) => write!(f, ".require({})", name)?,
+                TriggerAction::Reject(message) => write!(f, ".reject({})", message)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn entity_name(event: TriggerEvent) -> &'static str {
+    match event.is_edge_event() {
+        true => "edges",
+        false => "vertices",
+    }
+}
+
+fn set_property(properties: &mut Vec<Property>, name: String, value: PropertyValue) {
+    match properties.iter_mut().find(|property| property.name == name) {
+        Some(property) => property.value = value,
+        None => properties.push(Property {
+            name,
+            value,
+            flagged_for_removal: false,
+        }),
+    }
+}
+
+/// The triggers to run, copied so the graph can be changed while running them
+fn matching(
+    graph: &dyn Graph,
+    timing: TriggerTiming,
+    event: TriggerEvent,
+    label: &str,
+) -> Vec<Trigger> {
+    graph
+        .triggers()
+        .iter()
+        .filter(|trigger| trigger.matches(timing, event, label))
+        .cloned()
+        .collect()
+}
+
+/// Runs before triggers against the properties an entity will have once the mutation is made
+pub fn run_before(
+    graph: &dyn Graph,
+    event: TriggerEvent,
+    label: &str,
+    properties: &mut Vec<Property>,
+) -> Result<(), GraphDbError> {
+    for trigger in matching(graph, TriggerTiming::Before, event, label) {
+        trigger.run(properties)?;
+    }
+
+    Ok(())
+}
+
+/// Runs after triggers against the properties a vertex will have once inserted or updated,
+/// returning the update they make to it. They're run before the mutation is made, so one that
+/// fails leaves the graph as it was
+pub fn run_after(
+    graph: &dyn Graph,
+    event: TriggerEvent,
+    label: &str,
+    properties: &[Property],
+) -> Result<Vec<Property>, GraphDbError> {
+    let triggers = matching(graph, TriggerTiming::After, event, label);
+    if triggers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut updated = properties.to_vec();
+    for trigger in triggers {
+        trigger.run(&mut updated)?;
+    }
+
+    // The vertex is given its own properties first, then the triggers'
+    let mut given = properties.to_vec();
+    given.extend(updated.iter().cloned());
+    graph.check_properties(&given)?;
+
+    Ok(changed_properties(properties, &updated))
+}
+
+/// Runs after edge triggers against the vertices at either end of the edges, returning the
+/// update they make to each. As with run_after, this is done before the edges are inserted or
+/// deleted. A vertex deleted along with its edges isn't changed
+pub fn run_after_edges(
+    graph: &dyn Graph,
+    event: TriggerEvent,
+    edges: &[Edge],
+    deleted_vertex_id: Option<usize>,
+) -> Result<Vec<(usize, Vec<Property>)>, GraphDbError> {
+    let mut vertices: Vec<(usize, Vec<Property>, Vec<Property>)> = Vec::new(); // id, properties before and after
+    for edge in edges {
+        for trigger in matching(graph, TriggerTiming::After, event, &edge.edge_vertex.label) {
+            let id = match trigger.target {
+                Some(EdgeEnd::From) => edge.from_vertex_id,
+                Some(EdgeEnd::To) => edge.to_vertex_id,
+                None => continue,
+            };
+            if Some(id) == deleted_vertex_id {
+                continue;
+            }
+
+            let position = match vertices.iter().position(|(vertex_id, ..)| *vertex_id == id) {
+                Some(position) => position,
+                None => match graph.get_vertex(&id)? {
+                    DataResult::Vertex(vertex) => {
+                        vertices.push((id, vertex.properties.clone(), vertex.properties));
+                        vertices.len() - 1
+                    }
+                    _ => return Err(GraphDbError::Internal("Mismatched return type".to_string())),
+                },
+            };
+            trigger.run(&mut vertices[position].2)?;
+        }
+    }
+
+    let mut updates = Vec::new();
+    for (id, before, after) in vertices {
+        let changes = changed_properties(&before, &after);
+        if !changes.is_empty() {
+            graph.check_properties(&after)?;
+            updates.push((id, changes));
+        }
+    }
+
+    Ok(updates)
+}
+
+/// Whether any triggers run for the event on any label, to skip looking up what they'd need
+pub fn has_triggers(graph: &dyn Graph, event: TriggerEvent) -> bool {
+    graph
+        .triggers()
+        .iter()
+        .any(|trigger| trigger.event == event)
+}
+
+/// The properties a vertex will have once updated
+pub fn updated_properties(vertex: &Vertex, properties: &[Property]) -> Vec<Property> {
+    let mut vertex = vertex.clone();
+    // Can't fail
+    let _ = vertex.update(properties.to_vec());
+    vertex
+        .properties
+        .retain(|property| !property.flagged_for_removal);
+
+    vertex.properties
+}
+
+/// An update that turns the first set of properties into the second
+pub fn changed_properties(before: &[Property], after: &[Property]) -> Vec<Property> {
+    let mut changes: Vec<Property> = after
+        .iter()
+        .filter(|property| {
+            !before
+                .iter()
+                .any(|existing| existing.name == property.name && existing.value == property.value)
+        })
+        .cloned()
+        .collect();
+
+    for property in before {
+        if !after.iter().any(|other| other.name == property.name) {
+            changes.push(Property {
+                name: property.name.to_owned(),
+                value: PropertyValue::Int32(0),
+                flagged_for_removal: true,
+            });
+        }
+    }
+
+    changes
+}
@@ -1,237 +1,2069 @@
+pub mod adjacency;
+pub mod builder;
+pub mod changes;
 pub mod edge;
 mod entity_map;
+mod history;
+pub mod options;
+mod planner;
 pub mod property;
+mod record;
+mod repeat;
+pub mod spatial;
+pub mod stats;
+mod storage;
+mod symbols;
+mod text_index;
+pub mod vector;
 pub mod vertex;
 
-use crate::executor::VertexFilterCommandType;
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    ops::{Deref, DerefMut},
+    path::Path,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
 
-use self::{edge::Edge, entity_map::EntityMap, property::PropertyValue, vertex::Vertex};
+use crate::{
+    error::{EntityKind, GraphDbError},
+    executor::{
+        pattern::Binding, trigger::Trigger, EdgeStep, EdgeStepEnd, VertexFilterCommandType,
+    },
+};
 
-#[derive(Debug)]
+use self::{
+    adjacency::{Adjacency, AdjacentEdge, Direction},
+    changes::{now, Change, ChangeFeed, SessionId},
+    edge::Edge,
+    entity_map::EntityMap,
+    history::History,
+    options::{EdgePolicy, GraphOptions, OptionKey, SchemaMode},
+    planner::{Access, QueryPlan, Statistics},
+    property::{Property, PropertyValue},
+    record::{EdgeRecord, PropertyRecord, VertexRecord},
+    spatial::{nearest_first, SpatialIndex},
+    stats::{increment_label_count, DegreeStats, GraphStats, PropertyStats},
+    storage::Storage,
+    symbols::{Symbol, SymbolTable},
+    text_index::TextIndex,
+    vector::{HnswIndex, Metric},
+    vertex::Vertex,
+};
+
+/// Where a graph keeps its data
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GraphType {
-    InMemory,
+    InMemory,   // lost when the database is
+    Persistent, // also written to a file, and read back when a graph is created from it
+}
+
+impl GraphType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphType::InMemory => "inMemory",
+            GraphType::Persistent => "persistent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == GraphType::InMemory.as_str() => Ok(GraphType::InMemory),
+            _ if name == GraphType::Persistent.as_str() => Ok(GraphType::Persistent),
+            other => Err(GraphDbError::parse("inMemory or persistent", other)),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum DataResult<'a> {
+/// Results own their data, so they can outlive the lock on the graph they were read from
+#[derive(Debug, Clone)]
+pub enum DataResult {
     UnsignedInt(usize),
-    StringVector(Vec<&'a str>),
-    VertexIndexVector(Vec<&'a usize>),
-    VertexRef(&'a Vertex),
-    EdgeRef(&'a Edge),
-    MutableVertexRef(&'a mut Vertex),
-    VertexValueVector(Vec<Option<&'a PropertyValue>>),
+    StringVector(Vec<String>),
+    VertexVector(Vec<(usize, Vertex)>),
+    ScoredVertexVector(Vec<(usize, f64, Vertex)>), // id, relevance, vertex. Most relevant first
+    Vertex(Vertex),
+    Edge(Edge),
+    VertexValueVector(Vec<Option<PropertyValue>>),
+    Table(Vec<String>, Vec<Vec<Binding>>), // column names, rows
+    GraphStats(GraphStats),
+    Text(String),
+}
+
+/// A graph shared between threads. Any number of readers, or a single writer, may hold it at once
+pub type SharedGraph = Arc<RwLock<Box<dyn Graph>>>;
+
+pub fn read_lock<T: ?Sized>(lock: &RwLock<T>) -> Result<RwLockReadGuard<'_, T>, GraphDbError> {
+    lock.read()
+        .map_err(|_| GraphDbError::Internal("A lock was poisoned by a panicked writer".to_string()))
+}
+
+pub fn write_lock<T: ?Sized>(lock: &RwLock<T>) -> Result<RwLockWriteGuard<'_, T>, GraphDbError> {
+    lock.write()
+        .map_err(|_| GraphDbError::Internal("A lock was poisoned by a panicked writer".to_string()))
+}
+
+/// Write access to a graph on behalf of a session, which the changes made through it are
+/// attributed to until it's dropped
+pub struct SessionWriteGuard<'a> {
+    graph: RwLockWriteGuard<'a, Box<dyn Graph>>,
+}
+
+impl Deref for SessionWriteGuard<'_> {
+    type Target = Box<dyn Graph>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl DerefMut for SessionWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.graph
+    }
+}
+
+impl Drop for SessionWriteGuard<'_> {
+    fn drop(&mut self) {
+        self.graph.set_session(None);
+    }
+}
+
+/// Locks the graph for writing by a command run for the session, if one is given
+pub fn write_lock_for(
+    lock: &RwLock<Box<dyn Graph>>,
+    session: Option<SessionId>,
+) -> Result<SessionWriteGuard<'_>, GraphDbError> {
+    let mut graph = write_lock(lock)?;
+    graph.set_session(session);
+    Ok(SessionWriteGuard { graph })
 }
 
-pub trait Graph {
+pub trait Graph: Send + Sync {
     fn name(&self) -> &str;
 
-    fn add_vertex(&mut self, vertex: Vertex) -> Result<DataResult, String>;
+    /// How the graph was set up when it was created
+    fn options(&self) -> &GraphOptions;
+
+    fn set_name(&mut self, name: String);
+
+    /// Sets the session the changes published from now on are attributed to
+    fn set_session(&mut self, session: Option<SessionId>);
+
+    /// Removes every vertex and edge
+    fn clear(&mut self);
+
+    /// Copies the graph's vertices and edges into a new graph with the given name. Their arrival
+    /// is attributed to the session, if one is given
+    fn clone_graph(&self, name: String, session: Option<SessionId>) -> Box<dyn Graph>;
+
+    /// Deletes the file a persistent graph is kept in. Later changes are kept in memory only
+    fn delete_storage(&mut self) -> Result<(), GraphDbError>;
+
+    fn add_vertex(&mut self, vertex: Vertex) -> Result<DataResult, GraphDbError>;
+
+    fn add_edge(&mut self, edge: Edge) -> Result<DataResult, GraphDbError>;
+
+    fn get_vertex(&self, id: &usize) -> Result<DataResult, GraphDbError>;
+
+    fn get_edge(&self, id: &usize) -> Result<DataResult, GraphDbError>;
+
+    /// Removes the vertex along with every edge attached to it
+    fn remove_vertex(&mut self, id: &usize) -> Result<DataResult, GraphDbError>;
+
+    fn remove_edge(&mut self, id: &usize) -> Result<DataResult, GraphDbError>;
+
+    /// Puts back a removed vertex under the id it had. Its edges are put back separately
+    fn restore_vertex(&mut self, id: usize, vertex: Vertex) -> Result<DataResult, GraphDbError>;
+
+    /// Puts back a removed edge under the id it had, between vertices that exist
+    fn restore_edge(&mut self, id: usize, edge: Edge) -> Result<DataResult, GraphDbError>;
+
+    /// Sets and removes the vertex's properties, returning the updated vertex
+    fn update_vertex(
+        &mut self,
+        id: &usize,
+        properties: Vec<Property>,
+    ) -> Result<DataResult, GraphDbError>;
+
+    /// Fails if a vertex couldn't be given the properties, e.g. a value of another type than a
+    /// strict graph keeps the property to. Changes nothing
+    fn check_properties(&self, properties: &[Property]) -> Result<(), GraphDbError>;
+
+    /// Moves the edge between the given vertices, which must exist, and sets and removes its
+    /// properties. Returns the updated edge
+    fn update_edge(
+        &mut self,
+        id: &usize,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+        properties: Vec<Property>,
+    ) -> Result<DataResult, GraphDbError>;
+
+    /// Edges leaving (out) or arriving at (in) the given vertex, in the order they were added.
+    /// Either way gives every edge of the vertex in an undirected graph
+    fn adjacent_edges(
+        &self,
+        id: &usize,
+        direction: Direction,
+    ) -> Result<Box<dyn Iterator<Item = AdjacentEdge> + '_>, GraphDbError>;
+
+    fn list_vertices(
+        &self,
+        filters: &[VertexFilterCommandType],
+    ) -> Result<DataResult, GraphDbError>;
+
+    /// The steps list_vertices would take for the filters, with the rows each is expected to leave
+    fn explain(&self, filters: &[VertexFilterCommandType]) -> Result<DataResult, GraphDbError>;
+
+    /// Runs the filters as list_vertices would, reporting the rows each step left and its time
+    fn profile(&self, filters: &[VertexFilterCommandType]) -> Result<DataResult, GraphDbError>;
+
+    fn property_names(&self) -> Vec<&str>;
+
+    /// Number of vertices with the given label, or of every vertex, without looking at any of them
+    fn vertex_count(&self, label: Option<&str>) -> usize;
+
+    /// Indexes the words in a string property, so it can be searched with text_search
+    fn create_text_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError>;
+
+    fn drop_text_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError>;
+
+    /// Vertices whose indexed property matches the query, ranked by relevance
+    fn text_search(&self, property_name: &str, query: &str) -> Result<DataResult, GraphDbError>;
+
+    /// Indexes a point property, so geo filters at the start of a query don't scan every vertex
+    fn create_spatial_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError>;
+
+    fn drop_spatial_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError>;
+
+    /// Indexes a vector property for approximate nearest neighbour search with the given metric
+    fn create_vector_index(
+        &mut self,
+        property_name: &str,
+        metric: Metric,
+    ) -> Result<DataResult, GraphDbError>;
+
+    fn drop_vector_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError>;
+
+    /// Triggers run by the executor on mutations, in the order they were created
+    fn triggers(&self) -> &[Trigger];
+
+    fn create_trigger(&mut self, trigger: Trigger) -> Result<DataResult, GraphDbError>;
+
+    fn drop_trigger(&mut self, name: &str) -> Result<DataResult, GraphDbError>;
+
+    fn stats(&self) -> Result<DataResult, GraphDbError>;
 
-    fn add_edge(&mut self, edge: Edge) -> Result<DataResult, String>;
+    /// The options the graph was created with, alongside the indexes it has now
+    fn describe(&self) -> Result<DataResult, GraphDbError>;
 
-    fn get_vertex(&self, id: &usize) -> Result<DataResult, String>;
+    /// Keeps every version of each vertex and edge from now on, with when it was valid
+    fn enable_versioning(&mut self) -> Result<DataResult, GraphDbError>;
 
-    fn remove_vertex(&mut self, id: &usize) -> Result<DataResult, String>;
+    /// Stops versioning, dropping the history kept so far
+    fn disable_versioning(&mut self) -> Result<DataResult, GraphDbError>;
 
-    fn get_mutable_vertex(&mut self, id: &usize) -> Result<DataResult, String>;
+    /// The graph as it was at the given time, in milliseconds since January 1, 1970, to query
+    /// like any other. Needs versioning enabled. Each call rebuilds the graph from its history,
+    /// so costs as much as copying the graph
+    fn as_of(&self, timestamp: i64) -> Result<Box<dyn Graph>, GraphDbError>;
 
-    fn list_vertices(&self, filters: &Vec<VertexFilterCommandType>) -> Result<DataResult, String>;
+    /// Each version of a vertex, as a table. Needs versioning enabled
+    fn vertex_history(&self, id: &usize) -> Result<DataResult, GraphDbError>;
 }
 
+#[derive(Clone)]
 struct InMemoryGraph {
     name: String,
-    vertices: EntityMap<Vertex>,
-    edges: EntityMap<Edge>,
+    options: GraphOptions,
+    symbols: SymbolTable,
+    vertices: EntityMap<VertexRecord>,
+    edges: EntityMap<EdgeRecord>,
+    out_edges: Adjacency,
+    in_edges: Adjacency,
+    vertex_labels: HashMap<Symbol, Vec<usize>>, // label, ids of vertices with it in ascending order
+    external_ids: HashMap<String, usize>,       // external id, vertex id
+    text_indexes: HashMap<Symbol, TextIndex>,   // property key, index
+    spatial_indexes: HashMap<Symbol, SpatialIndex>, // property key, index
+    vector_indexes: HashMap<Symbol, HnswIndex>, // property key, index
+    vector_dimensions: HashMap<Symbol, usize>,  // property key, length of its vectors on vertices
+    property_types: HashMap<Symbol, &'static str>, // property key, type of its values in strict graphs
+    changes: Arc<ChangeFeed>,
+    session: Option<SessionId>, // whose command is making the changes, if anyone's
+    triggers: Vec<Trigger>,
+    history: Option<History>,      // only kept while versioning is enabled
+    storage: Option<Arc<Storage>>, // only persistent graphs have a file
+    storage_error: Option<String>, // why the file stopped being written to, if it has
 }
 
-impl Graph for InMemoryGraph {
-    fn add_vertex(&mut self, vertex: Vertex) -> Result<DataResult, String> {
-        let index = self.vertices.push(vertex);
-        Ok(DataResult::UnsignedInt(index))
+impl InMemoryGraph {
+    fn new(name: String, options: GraphOptions, changes: Arc<ChangeFeed>) -> Self {
+        InMemoryGraph {
+            name,
+            options,
+            symbols: SymbolTable::new(),
+            vertices: EntityMap::new(),
+            edges: EntityMap::new(),
+            out_edges: Adjacency::new(),
+            in_edges: Adjacency::new(),
+            vertex_labels: HashMap::new(),
+            external_ids: HashMap::new(),
+            text_indexes: HashMap::new(),
+            spatial_indexes: HashMap::new(),
+            vector_indexes: HashMap::new(),
+            vector_dimensions: HashMap::new(),
+            property_types: HashMap::new(),
+            changes,
+            session: None,
+            triggers: Vec::new(),
+            history: None,
+            storage: None,
+            storage_error: None,
+        }
+    }
+
+    /// A new graph set up as its options say, with the indexes they name. A persistent graph
+    /// starts with whatever its file already holds, read back on behalf of the session
+    fn create(
+        name: String,
+        options: GraphOptions,
+        changes: Arc<ChangeFeed>,
+        session: Option<SessionId>,
+    ) -> Result<Self, GraphDbError> {
+        options.validate()?;
+        let mut graph = InMemoryGraph::new(name, options, changes);
+        if let Some(path) = graph.options.path.clone() {
+            graph.session = session;
+            let opened = graph.open_storage(&path);
+            graph.session = None;
+            opened?;
+        }
+
+        // After reading the file, as it may name indexes of its own
+        let options = graph.options.clone();
+        for property_name in &options.text_indexes {
+            graph.create_text_index(property_name)?;
+        }
+        for property_name in &options.spatial_indexes {
+            graph.create_spatial_index(property_name)?;
+        }
+        for (property_name, metric) in &options.vector_indexes {
+            graph.create_vector_index(property_name, *metric)?;
+        }
+
+        Ok(graph)
+    }
+
+    /// Tells subscribers about the change, and keeps it in the history if versioning is enabled
+    fn publish(&mut self, change: Change) {
+        // The history and the event share a timestamp, so each version matches its event
+        let mut timestamp = now();
+        if let Some(history) = &mut self.history {
+            timestamp = history.record(&change, timestamp);
+        }
+        self.store(&change);
+        self.changes
+            .publish_at(&self.name, change, timestamp, self.session);
+    }
+
+    /// Whether a change would be seen by anyone. Its before and after images are only built if so
+    fn is_observed(&self) -> bool {
+        self.history.is_some() || self.storage.is_some() || self.changes.is_followed()
+    }
+
+    /// Publishes every vertex and edge as though it had just been added, without adding them to
+    /// the history
+    fn announce_contents(&mut self) {
+        let history = self.history.take();
+        for id in self.vertices.get_indices() {
+            self.vertex_added(id);
+        }
+        for id in self.edges.get_indices() {
+            self.edge_added(id);
+        }
+        self.history = history;
+    }
+
+    fn vertex_added(&mut self, id: usize) {
+        if !self.is_observed() {
+            return;
+        }
+        if let Some(vertex) = self.vertices.get(&id) {
+            let after = vertex.to_vertex(&self.symbols);
+            self.publish(Change::VertexAdded { id, after });
+        }
+    }
+
+    fn edge_added(&mut self, id: usize) {
+        if !self.is_observed() {
+            return;
+        }
+        if let Some(edge) = self.edges.get(&id) {
+            let after = edge.to_edge(&self.symbols);
+            self.publish(Change::EdgeAdded { id, after });
+        }
+    }
+
+    /// Adds the edge to the adjacency lists of both its vertices. A self loop is both an out and
+    /// an in edge of its vertex
+    fn link_edge(&mut self, id: usize, from_vertex_id: usize, to_vertex_id: usize) {
+        self.out_edges.insert(
+            from_vertex_id,
+            AdjacentEdge {
+                edge_id: id,
+                vertex_id: to_vertex_id,
+            },
+        );
+        self.in_edges.insert(
+            to_vertex_id,
+            AdjacentEdge {
+                edge_id: id,
+                vertex_id: from_vertex_id,
+            },
+        );
     }
 
-    fn add_edge(&mut self, edge: Edge) -> Result<DataResult, String> {
-        let index = self.edges.push(edge.clone());
+    /// Removes the edge from the adjacency lists of both its vertices
+    fn unlink_edge(&mut self, id: usize, from_vertex_id: usize, to_vertex_id: usize) {
+        self.out_edges.remove(from_vertex_id, id);
+        self.in_edges.remove(to_vertex_id, id);
+    }
 
-        // Find the vertices the edge is attached to and link them together based on the edge index
-        let panic_msg = "Unexpected return type whilst trying to get a mutable vertex";
-        if edge.from_vertex_id == edge.to_vertex_id {
-            match self.get_mutable_vertex(&edge.from_vertex_id)? {
-                DataResult::MutableVertexRef(vertex) => {
-                    vertex.in_edge_ids.push(index);
-                    vertex.out_edge_ids.push(index);
+    /// Removes the edge, publishing the change, if it hasn't been removed already
+    fn remove_edge_record(&mut self, id: usize) -> bool {
+        match self.edges.remove(&id) {
+            Some(edge) => {
+                if self.is_observed() {
+                    let before = edge.to_edge(&self.symbols);
+                    self.publish(Change::EdgeRemoved { id, before });
                 }
-                _ => panic!("{}", panic_msg),
+                true
             }
-        } else {
-            {
-                let from_vertex = self.get_mutable_vertex(&edge.from_vertex_id)?;
+            None => false,
+        }
+    }
 
-                match from_vertex {
-                    DataResult::MutableVertexRef(vertex) => {
-                        vertex.out_edge_ids.push(index);
-                    }
-                    _ => panic!("{}", panic_msg),
+    fn has_indexes(&self) -> bool {
+        !self.text_indexes.is_empty()
+            || !self.spatial_indexes.is_empty()
+            || !self.vector_indexes.is_empty()
+    }
+
+    /// Fails if the properties break the graph's rules: in a strict graph every value of a
+    /// property must have the type of the first one stored, and every vector of a vertex property
+    /// must have the length of the first one. Properties the graph has no rule for yet are held to
+    /// the first value given here. Changes nothing, not even the symbol table
+    fn check_schema(&self, kind: EntityKind, properties: &[Property]) -> Result<(), GraphDbError> {
+        let mut types: HashMap<&str, &'static str> = HashMap::new();
+        let mut dimensions: HashMap<&str, usize> = HashMap::new();
+        for property in properties.iter().filter(|p| !p.flagged_for_removal) {
+            let name = property.name.as_str();
+            let key = self.symbols.get(name);
+
+            if self.options.schema == SchemaMode::Strict {
+                let type_name = key
+                    .and_then(|key| self.property_types.get(&key).copied())
+                    .unwrap_or_else(|| *types.entry(name).or_insert(property.value.type_name()));
+                if type_name != property.value.type_name() {
+                    return Err(self.property_type_error(
+                        name,
+                        type_name,
+                        property.value.type_name(),
+                    ));
+                }
+            }
+
+            if let (EntityKind::Vertex, PropertyValue::Vector(vector)) = (kind, &property.value) {
+                let dimension = key
+                    .and_then(|key| self.vector_dimensions.get(&key).copied())
+                    .unwrap_or_else(|| *dimensions.entry(name).or_insert(vector.len()));
+                if dimension != vector.len() {
+                    return Err(vector_dimension_error(name, dimension, vector.len()));
                 }
             }
-            {
-                let to_vertex = self.get_mutable_vertex(&edge.to_vertex_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the types and vector lengths of properties that passed check_schema, for later
+    /// values to be held to
+    fn record_schema(&mut self, kind: EntityKind, properties: &[PropertyRecord]) {
+        for property in properties {
+            if self.options.schema == SchemaMode::Strict {
+                self.property_types
+                    .entry(property.key)
+                    .or_insert(property.value.type_name());
+            }
 
-                match to_vertex {
-                    DataResult::MutableVertexRef(vertex) => {
-                        vertex.in_edge_ids.push(index);
+            if let (EntityKind::Vertex, PropertyValue::Vector(vector)) = (kind, &property.value) {
+                self.vector_dimensions
+                    .entry(property.key)
+                    .or_insert(vector.len());
+            }
+        }
+    }
+
+    fn property_type_error(&self, name: &str, type_name: &str, found: &str) -> GraphDbError {
+        GraphDbError::ConstraintViolation(format!(
+            "Property '{}' holds {} values in graph '{}', which has a strict schema, not {}",
+            name, type_name, self.name, found
+        ))
+    }
+
+    /// Fails if vectors of the property have a different length to the given vector
+    fn check_query_dimension(&self, name: &str, vector: &[f32]) -> Result<(), GraphDbError> {
+        let dimension = self
+            .symbols
+            .get(name)
+            .and_then(|key| self.vector_dimensions.get(&key));
+
+        match dimension {
+            Some(dimension) if *dimension != vector.len() => {
+                Err(GraphDbError::InvalidArgument(format!(
+                    "Property '{}' holds vectors of length {}, not {}",
+                    name,
+                    dimension,
+                    vector.len()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds or removes a vertex's indexed properties from the text and spatial indexes
+    fn update_indexes(&mut self, id: usize, vertex: &VertexRecord, insert: bool) {
+        for property in &vertex.properties {
+            match &property.value {
+                PropertyValue::String(text) => {
+                    if let Some(index) = self.text_indexes.get_mut(&property.key) {
+                        match insert {
+                            true => index.insert(id, text),
+                            false => index.remove(id, text),
+                        }
+                    }
+                }
+                PropertyValue::Point(point) => {
+                    if let Some(index) = self.spatial_indexes.get_mut(&property.key) {
+                        match insert {
+                            true => index.insert(id, *point),
+                            false => index.remove(id),
+                        }
                     }
-                    _ => panic!("{}", panic_msg),
                 }
+                PropertyValue::Vector(vector) => {
+                    if let Some(index) = self.vector_indexes.get_mut(&property.key) {
+                        match insert {
+                            true => index.insert(id, vector.clone()),
+                            false => index.remove(id),
+                        }
+                    }
+                }
+                _ => (),
             }
         }
+    }
 
-        Ok(DataResult::UnsignedInt(index))
+    /// Vertices from the spatial index on the property, if there is one
+    fn spatial_index_lookup(
+        &self,
+        name: &str,
+        lookup: impl Fn(&SpatialIndex) -> Vec<usize>,
+    ) -> Option<Vec<usize>> {
+        let key = self.symbols.get(name)?;
+
+        self.spatial_indexes.get(&key).map(lookup)
+    }
+
+    /// Edges leaving (out) or arriving at (in) the vertex. An undirected graph follows edges
+    /// either way, so gives every edge of the vertex, with self loops once
+    fn adjacent(&self, id: usize, direction: Direction) -> impl Iterator<Item = AdjacentEdge> + '_ {
+        let (adjacency, reversed) = match (self.options.directed, direction) {
+            (true, Direction::Out) => (&self.out_edges, None),
+            (true, Direction::In) => (&self.in_edges, None),
+            (false, _) => (&self.out_edges, Some(&self.in_edges)),
+        };
+
+        adjacency.edges(id).chain(
+            reversed
+                .into_iter()
+                .flat_map(move |adjacency| adjacency.edges(id))
+                .filter(move |edge| edge.vertex_id != id),
+        )
+    }
+
+    /// Fails if the graph's edge policy doesn't allow another edge with the label between the
+    /// vertices. An edge being moved, given by its id, doesn't count against itself
+    fn check_edge_policy(
+        &self,
+        id: Option<usize>,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+        label: &str,
+    ) -> Result<(), GraphDbError> {
+        let per_label = match self.options.edge_policy {
+            EdgePolicy::Multigraph => return Ok(()),
+            EdgePolicy::Simple if from_vertex_id == to_vertex_id => {
+                return Err(GraphDbError::ConstraintViolation(format!(
+                    "Graph '{}' is a simple graph, so can't have self loops",
+                    self.name
+                )))
+            }
+            EdgePolicy::Simple => false,
+            EdgePolicy::UniquePerLabel => true,
+        };
+
+        // A label the graph hasn't seen can't be on any edge yet
+        let label_key = self.symbols.get(label);
+        let existing = self
+            .adjacent(from_vertex_id, Direction::Out)
+            .filter(|edge| edge.vertex_id == to_vertex_id && Some(edge.edge_id) != id)
+            .find(|edge| {
+                !per_label
+                    || self
+                        .edges
+                        .get(&edge.edge_id)
+                        .is_some_and(|edge| Some(edge.label) == label_key)
+            });
+
+        match (existing, per_label) {
+            (None, _) => Ok(()),
+            (Some(edge), false) => Err(GraphDbError::ConstraintViolation(format!(
+                "Edge {} already joins vertices {} and {}, and graph '{}' is a simple graph",
+                edge.edge_id, from_vertex_id, to_vertex_id, self.name
+            ))),
+            (Some(edge), true) => Err(GraphDbError::ConstraintViolation(format!(
+                "Edge {} already joins vertices {} and {} with label '{}', and graph '{}' allows one of each label",
+                edge.edge_id,
+                from_vertex_id,
+                to_vertex_id,
+                label,
+                self.name
+            ))),
+        }
+    }
+
+    /// Finds a plan's first vertices, from the index it chose if any
+    fn access(
+        &self,
+        filters: &[VertexFilterCommandType],
+        access: Access,
+    ) -> Result<Vec<usize>, GraphDbError> {
+        let filter = match access {
+            Access::Scan => return Ok(self.vertices.get_indices()),
+            Access::Index(index) => &filters[index],
+        };
+
+        Ok(match filter {
+            VertexFilterCommandType::HasId(id) => match self.vertices.get(id) {
+                Some(_) => vec![*id],
+                None => Vec::new(),
+            },
+            VertexFilterCommandType::HasName(label) => self.vertices_with_label(label),
+            VertexFilterCommandType::HasExternalId(external_id) => self
+                .external_ids
+                .get(external_id)
+                .into_iter()
+                .copied()
+                .collect(),
+            // Kept in id order, as a scan would be, since the filter needn't come first
+            VertexFilterCommandType::HasPropertyWithinRadius(name, center, km) => self
+                .spatial_index_lookup(name, |index| sorted(index.within_radius(center, *km)))
+                .unwrap_or_else(|| self.vertices.get_indices()),
+            VertexFilterCommandType::HasPropertyWithinBox(name, bounding_box) => self
+                .spatial_index_lookup(name, |index| sorted(index.within_box(bounding_box)))
+                .unwrap_or_else(|| self.vertices.get_indices()),
+            VertexFilterCommandType::HasPropertyNearest(name, center, k) => self
+                .spatial_index_lookup(name, |index| index.nearest(center, *k))
+                .unwrap_or_else(|| self.vertices.get_indices()),
+            VertexFilterCommandType::Nearest(name, vector, k, metric) => {
+                self.check_query_dimension(name, vector)?;
+                self.symbols
+                    .get(name)
+                    .and_then(|key| self.vector_indexes.get(&key))
+                    .filter(|index| index.metric() == *metric)
+                    .map(|index| {
+                        index
+                            .search(vector, *k)
+                            .into_iter()
+                            .map(|(id, _)| id)
+                            .collect()
+                    })
+                    .unwrap_or_else(|| self.vertices.get_indices())
+            }
+            _ => self.vertices.get_indices(),
+        })
     }
 
-    fn list_vertices(&self, filters: &Vec<VertexFilterCommandType>) -> Result<DataResult, String> {
-        let mut vertex_indices = self.vertices.get_indices();
+    /// Runs a query plan, recording each step's rows and time when profiling
+    fn run_plan(
+        &self,
+        filters: &[VertexFilterCommandType],
+        plan: &QueryPlan,
+        mut profile: Option<&mut Vec<(usize, Duration)>>,
+    ) -> Result<DataResult, GraphDbError> {
+        let started = Instant::now();
+        let mut vertex_indices = self.access(filters, plan.access)?;
+        if let Some(profile) = profile.as_deref_mut() {
+            profile.push((vertex_indices.len(), started.elapsed()));
+        }
 
         if self.vertices.len() < 1 {
-            return Ok(DataResult::VertexIndexVector(vertex_indices));
+            return Ok(DataResult::VertexVector(Vec::new()));
         }
 
         let mut vertex_value_vector = Vec::new();
 
         let mut return_values = false;
-        for filter in filters {
-            match filter {
-                VertexFilterCommandType::HasName(name) => {
-                    vertex_indices.retain(|index| match self.vertices.get(index) {
-                        Some(val) => &val.label == name,
-                        None => false,
-                    });
+        for step in &plan.steps {
+            let started = Instant::now();
+            vertex_indices = self.apply_filter(
+                &filters[*step],
+                vertex_indices,
+                &mut vertex_value_vector,
+                &mut return_values,
+            )?;
+            if let Some(profile) = profile.as_deref_mut() {
+                profile.push((vertex_indices.len(), started.elapsed()));
+            }
+        }
+
+        match return_values {
+            true => Ok(DataResult::VertexValueVector(vertex_value_vector)),
+            false => Ok(DataResult::VertexVector(
+                vertex_indices
+                    .into_iter()
+                    .filter_map(|index| {
+                        self.vertices
+                            .get(&index)
+                            .map(|vertex| (index, vertex.to_vertex(&self.symbols)))
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    fn apply_filter(
+        &self,
+        filter: &VertexFilterCommandType,
+        mut vertex_indices: Vec<usize>,
+        vertex_value_vector: &mut Vec<Option<PropertyValue>>,
+        return_values: &mut bool,
+    ) -> Result<Vec<usize>, GraphDbError> {
+        match filter {
+            VertexFilterCommandType::HasId(id) => {
+                vertex_indices.retain(|index| index == id);
+            }
+            VertexFilterCommandType::HasName(name) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, label| val.label == label);
+            }
+            VertexFilterCommandType::HasExternalId(external_id) => {
+                let id = self.external_ids.get(external_id);
+                vertex_indices.retain(|index| Some(index) == id);
+            }
+            VertexFilterCommandType::HasProperty(name) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| val.has_property(key));
+            }
+            VertexFilterCommandType::HasPropertyValue(name, value) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_value(key, value)
+                });
+            }
+            VertexFilterCommandType::HasPropertyLike(name, search_term) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_like(key, search_term)
+                });
+            }
+            VertexFilterCommandType::HasPropertyText(name, predicate, ignore_case) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_text(key, predicate, *ignore_case)
+                });
+            }
+            VertexFilterCommandType::HasPropertyRegex(name, regex) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_regex(key, regex)
+                });
+            }
+            VertexFilterCommandType::HasPropertyWithinRadius(name, center, km) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_within_radius(key, center, *km)
+                });
+            }
+            VertexFilterCommandType::HasPropertyWithinBox(name, bounding_box) => {
+                self.retain_vertices(&mut vertex_indices, name, |val, key| {
+                    val.has_property_within_box(key, bounding_box)
+                });
+            }
+            VertexFilterCommandType::HasPropertyNearest(name, center, k) => {
+                let mut distances: Vec<(usize, f64)> = match self.symbols.get(name) {
+                    Some(key) => vertex_indices
+                        .iter()
+                        .filter_map(|index| {
+                            let point = self.vertices.get(index)?.get_point(key)?;
+                            Some((*index, point.distance_km(center)))
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+                // A vertex reached more than once takes up one place
+                distances.sort_by_key(|(index, _)| *index);
+                distances.dedup_by_key(|(index, _)| *index);
+                vertex_indices = nearest_first(&mut distances, *k);
+            }
+            VertexFilterCommandType::Nearest(name, vector, k, metric) => {
+                self.check_query_dimension(name, vector)?;
+                let mut distances: Vec<(usize, f64)> = match self.symbols.get(name) {
+                    Some(key) => vertex_indices
+                        .iter()
+                        .filter_map(|index| {
+                            let value = self.vertices.get(index)?.get_property_value(key)?;
+                            match value {
+                                PropertyValue::Vector(val) => {
+                                    Some((*index, metric.distance(vector, val) as f64))
+                                }
+                                _ => None,
+                            }
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+                distances.sort_by_key(|(index, _)| *index);
+                distances.dedup_by_key(|(index, _)| *index);
+                vertex_indices = nearest_first(&mut distances, *k);
+            }
+            VertexFilterCommandType::Out(label) => {
+                vertex_indices = self.traverse(&vertex_indices, Direction::Out, label.as_deref());
+            }
+            VertexFilterCommandType::In(label) => {
+                vertex_indices = self.traverse(&vertex_indices, Direction::In, label.as_deref());
+            }
+            VertexFilterCommandType::Edges(step) => {
+                vertex_indices = self.traverse_edges(&vertex_indices, step);
+            }
+            VertexFilterCommandType::Repeat(repeat) => {
+                vertex_indices = self.repeat(repeat, vertex_indices)?;
+            }
+            VertexFilterCommandType::Values(name) => {
+                let key = self.symbols.get(name);
+                for index in &vertex_indices {
+                    let value = match (self.vertices.get(index), key) {
+                        (Some(val), Some(key)) => val.get_property_value(key).cloned(),
+                        _ => None,
+                    };
+                    vertex_value_vector.push(value);
+                    *return_values = true;
                 }
-                VertexFilterCommandType::HasProperty(name) => {
-                    vertex_indices.retain(|index| match self.vertices.get(index) {
-                        Some(val) => val.has_property(name),
-                        None => false,
-                    });
+            }
+        }
+
+        Ok(vertex_indices)
+    }
+
+    /// Vertices with the given label, from the label index
+    fn vertices_with_label(&self, label: &str) -> Vec<usize> {
+        self.symbols
+            .get(label)
+            .and_then(|label| self.vertex_labels.get(&label))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Keeps the vertices matching the predicate. Filters on a name the graph has never seen
+    /// match nothing, without looking at any vertex
+    fn retain_vertices(
+        &self,
+        vertex_indices: &mut Vec<usize>,
+        name: &str,
+        predicate: impl Fn(&VertexRecord, Symbol) -> bool,
+    ) {
+        match self.symbols.get(name) {
+            Some(symbol) => vertex_indices.retain(|index| match self.vertices.get(index) {
+                Some(val) => predicate(val, symbol),
+                None => false,
+            }),
+            None => vertex_indices.clear(),
+        }
+    }
+
+    /// Steps from each vertex to its neighbours, optionally only along edges with the given label.
+    /// A vertex reached along several edges appears once per edge
+    fn traverse(
+        &self,
+        vertex_ids: &[usize],
+        direction: Direction,
+        label: Option<&str>,
+    ) -> Vec<usize> {
+        let label = match label {
+            Some(label) => match self.symbols.get(label) {
+                Some(symbol) => Some(symbol),
+                None => return Vec::new(),
+            },
+            None => None,
+        };
+
+        let mut neighbours = Vec::new();
+        for vertex_id in vertex_ids {
+            for edge in self.adjacent(*vertex_id, direction) {
+                let label_matches = match label {
+                    Some(label) => self
+                        .edges
+                        .get(&edge.edge_id)
+                        .is_some_and(|e| e.label == label),
+                    None => true,
+                };
+
+                if label_matches {
+                    neighbours.push(edge.vertex_id);
                 }
-                VertexFilterCommandType::HasPropertyValue(name, value) => {
-                    vertex_indices.retain(|index| match self.vertices.get(index) {
-                        Some(val) => val.has_property_value(name, value),
-                        None => false,
-                    });
+            }
+        }
+
+        neighbours
+    }
+
+    /// Steps from each vertex along the edges matching the step's label and filters, to the
+    /// vertex the step ends at. As with `traverse`, a vertex appears once per edge
+    fn traverse_edges(&self, vertex_ids: &[usize], step: &EdgeStep) -> Vec<usize> {
+        let label = match &step.label {
+            Some(label) => match self.symbols.get(label) {
+                Some(symbol) => Some(symbol),
+                None => return Vec::new(),
+            },
+            None => None,
+        };
+
+        // Each filter names a label or a property, which no edge has if the graph has never seen it
+        let mut filters = Vec::new();
+        for filter in &step.filters {
+            let name = match filter {
+                VertexFilterCommandType::HasName(name)
+                | VertexFilterCommandType::HasProperty(name)
+                | VertexFilterCommandType::HasPropertyValue(name, _)
+                | VertexFilterCommandType::HasPropertyLike(name, _)
+                | VertexFilterCommandType::HasPropertyText(name, _, _)
+                | VertexFilterCommandType::HasPropertyRegex(name, _)
+                | VertexFilterCommandType::HasPropertyWithinRadius(name, _, _)
+                | VertexFilterCommandType::HasPropertyWithinBox(name, _) => name,
+                _ => continue,
+            };
+            match self.symbols.get(name) {
+                Some(symbol) => filters.push((filter, symbol)),
+                None => return Vec::new(),
+            }
+        }
+
+        let mut neighbours = Vec::new();
+        for vertex_id in vertex_ids {
+            for edge in self.adjacent(*vertex_id, step.direction) {
+                let Some(record) = self.edges.get(&edge.edge_id) else {
+                    continue;
+                };
+                if label.is_some_and(|label| record.label != label) {
+                    continue;
                 }
-                VertexFilterCommandType::HasPropertyLike(name, search_term) => {
-                    vertex_indices.retain(|index| match self.vertices.get(index) {
-                        Some(val) => val.has_property_like(name, search_term),
-                        None => false,
-                    });
+
+                let matches = filters.iter().all(|(filter, symbol)| match filter {
+                    VertexFilterCommandType::HasName(_) => record.label == *symbol,
+                    _ => record
+                        .get_property_value(*symbol)
+                        .is_some_and(|value| property_matches(filter, value)),
+                });
+                if !matches {
+                    continue;
                 }
-                VertexFilterCommandType::Values(name) => {
-                    for index in &vertex_indices {
-                        let value = match self.vertices.get(index) {
-                            Some(val) => val.get_property_value(name),
-                            None => None,
-                        };
-                        vertex_value_vector.push(value);
-                        return_values = true;
+
+                // Oriented along the step, which in an undirected graph may be against the edge
+                let (from_vertex_id, to_vertex_id) = match step.direction {
+                    Direction::Out => (*vertex_id, edge.vertex_id),
+                    Direction::In => (edge.vertex_id, *vertex_id),
+                };
+                neighbours.push(match step.end {
+                    Some(EdgeStepEnd::In) => to_vertex_id,
+                    Some(EdgeStepEnd::Out) => from_vertex_id,
+                    Some(EdgeStepEnd::Other) | None => edge.vertex_id,
+                });
+            }
+        }
+
+        neighbours
+    }
+}
+
+/// Whether a property value passes a property filter. Filters on other things pass everything
+fn property_matches(filter: &VertexFilterCommandType, value: &PropertyValue) -> bool {
+    match filter {
+        VertexFilterCommandType::HasPropertyValue(_, expected) => value.matches_str(expected),
+        VertexFilterCommandType::HasPropertyLike(_, search_term) => value.contains_str(search_term),
+        VertexFilterCommandType::HasPropertyText(_, predicate, ignore_case) => {
+            value.matches_text(predicate, *ignore_case)
+        }
+        VertexFilterCommandType::HasPropertyRegex(_, regex) => value.matches_regex(regex),
+        VertexFilterCommandType::HasPropertyWithinRadius(_, center, km) => {
+            value.within_radius(center, *km)
+        }
+        VertexFilterCommandType::HasPropertyWithinBox(_, bounding_box) => {
+            value.within_box(bounding_box)
+        }
+        _ => true,
+    }
+}
+
+impl Statistics for InMemoryGraph {
+    fn vertices(&self, label: Option<&str>) -> usize {
+        self.vertex_count(label)
+    }
+
+    fn edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn has_spatial_index(&self, name: &str) -> bool {
+        self.symbols
+            .get(name)
+            .is_some_and(|key| self.spatial_indexes.contains_key(&key))
+    }
+
+    fn has_vector_index(&self, name: &str, metric: Metric) -> bool {
+        self.symbols
+            .get(name)
+            .and_then(|key| self.vector_indexes.get(&key))
+            .is_some_and(|index| index.metric() == metric)
+    }
+}
+
+impl Graph for InMemoryGraph {
+    fn add_vertex(&mut self, vertex: Vertex) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        if let Some(external_id) = &vertex.external_id {
+            if self.external_ids.contains_key(external_id) {
+                return Err(GraphDbError::ConstraintViolation(format!(
+                    "External ID: {} already exists",
+                    external_id
+                )));
+            }
+        }
+
+        self.check_schema(EntityKind::Vertex, &vertex.properties)?;
+
+        let vertex = VertexRecord::from_vertex(vertex, &mut self.symbols);
+        self.record_schema(EntityKind::Vertex, &vertex.properties);
+        let label = vertex.label;
+        let external_id = vertex.external_id.clone();
+
+        let index = self.vertices.push(vertex);
+        if self.has_indexes() {
+            let vertex = self.vertices.get(&index).cloned();
+            if let Some(vertex) = vertex {
+                self.update_indexes(index, &vertex, true);
+            }
+        }
+        // Ids only increase, so pushing keeps the label index sorted
+        self.vertex_labels.entry(label).or_default().push(index);
+        if let Some(external_id) = external_id {
+            self.external_ids.insert(external_id, index);
+        }
+        self.vertex_added(index);
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    fn add_edge(&mut self, edge: Edge) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        // Check both vertices exist before storing the edge, so a failure can't leave it dangling
+        self.get_vertex(&edge.from_vertex_id)?;
+        self.get_vertex(&edge.to_vertex_id)?;
+
+        let from_vertex_id = edge.from_vertex_id;
+        let to_vertex_id = edge.to_vertex_id;
+        self.check_edge_policy(None, from_vertex_id, to_vertex_id, &edge.edge_vertex.label)?;
+        self.check_schema(EntityKind::Edge, &edge.edge_vertex.properties)?;
+
+        let edge = EdgeRecord::from_edge(edge, &mut self.symbols);
+        self.record_schema(EntityKind::Edge, &edge.properties);
+        let index = self.edges.push(edge);
+
+        self.link_edge(index, from_vertex_id, to_vertex_id);
+        self.edge_added(index);
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    fn list_vertices(
+        &self,
+        filters: &[VertexFilterCommandType],
+    ) -> Result<DataResult, GraphDbError> {
+        let plan = planner::plan(filters, self);
+        self.run_plan(filters, &plan, None)
+    }
+
+    fn explain(&self, filters: &[VertexFilterCommandType]) -> Result<DataResult, GraphDbError> {
+        let plan = planner::plan(filters, self);
+        Ok(planner::describe(filters, &plan, None))
+    }
+
+    fn profile(&self, filters: &[VertexFilterCommandType]) -> Result<DataResult, GraphDbError> {
+        let plan = planner::plan(filters, self);
+        let mut profile = Vec::new();
+        self.run_plan(filters, &plan, Some(&mut profile))?;
+        Ok(planner::describe(filters, &plan, Some(&profile)))
+    }
+
+    fn get_vertex(&self, id: &usize) -> Result<DataResult, GraphDbError> {
+        match self.vertices.get(id) {
+            Some(val) => Ok(DataResult::Vertex(val.to_vertex(&self.symbols))),
+            None => Err(GraphDbError::not_found(EntityKind::Vertex, id)),
+        }
+    }
+
+    fn get_edge(&self, id: &usize) -> Result<DataResult, GraphDbError> {
+        match self.edges.get(id) {
+            Some(val) => Ok(DataResult::Edge(val.to_edge(&self.symbols))),
+            None => Err(GraphDbError::not_found(EntityKind::Edge, id)),
+        }
+    }
+
+    fn remove_vertex(&mut self, id: &usize) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        let vertex = match self.vertices.remove(id) {
+            Some(vertex) => vertex,
+            None => return Err(GraphDbError::not_found(EntityKind::Vertex, id)),
+        };
+
+        // Remove attached edges, unlinking them from the vertex at their other end
+        let out_edges = self.out_edges.remove_vertex(*id);
+        let in_edges = self.in_edges.remove_vertex(*id);
+        for edge in out_edges {
+            self.remove_edge_record(edge.edge_id);
+            self.in_edges.remove(edge.vertex_id, edge.edge_id);
+        }
+
+        for edge in in_edges {
+            // Self loops were already removed along with the out edges
+            if self.remove_edge_record(edge.edge_id) {
+                self.out_edges.remove(edge.vertex_id, edge.edge_id);
+            }
+        }
+
+        if let Some(ids) = self.vertex_labels.get_mut(&vertex.label) {
+            if let Ok(position) = ids.binary_search(id) {
+                ids.remove(position);
+            }
+        }
+
+        self.update_indexes(*id, &vertex, false);
+
+        if let Some(external_id) = &vertex.external_id {
+            self.external_ids.remove(external_id);
+        }
+        if self.is_observed() {
+            let before = vertex.to_vertex(&self.symbols);
+            self.publish(Change::VertexRemoved { id: *id, before });
+        }
+
+        Ok(DataResult::UnsignedInt(*id))
+    }
+
+    fn remove_edge(&mut self, id: &usize) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        let (from_vertex_id, to_vertex_id) = match self.edges.get(id) {
+            Some(edge) => (edge.from_vertex_id, edge.to_vertex_id),
+            None => return Err(GraphDbError::not_found(EntityKind::Edge, id)),
+        };
+
+        self.unlink_edge(*id, from_vertex_id, to_vertex_id);
+        self.remove_edge_record(*id);
+
+        Ok(DataResult::UnsignedInt(*id))
+    }
+
+    fn restore_vertex(&mut self, id: usize, vertex: Vertex) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        if !self.vertices.can_insert(id) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "Vertex {} can't be restored, as its id is in use or from before the graph was cleared",
+                id
+            )));
+        }
+        if let Some(external_id) = &vertex.external_id {
+            if self.external_ids.contains_key(external_id) {
+                return Err(GraphDbError::ConstraintViolation(format!(
+                    "External ID: {} already exists",
+                    external_id
+                )));
+            }
+        }
+
+        self.check_schema(EntityKind::Vertex, &vertex.properties)?;
+
+        let vertex = VertexRecord::from_vertex(vertex, &mut self.symbols);
+        self.record_schema(EntityKind::Vertex, &vertex.properties);
+        let label = vertex.label;
+        let external_id = vertex.external_id.clone();
+        self.vertices.insert(id, vertex);
+
+        if self.has_indexes() {
+            let vertex = self.vertices.get(&id).cloned();
+            if let Some(vertex) = vertex {
+                self.update_indexes(id, &vertex, true);
+            }
+        }
+
+        let ids = self.vertex_labels.entry(label).or_default();
+        if let Err(position) = ids.binary_search(&id) {
+            ids.insert(position, id);
+        }
+        if let Some(external_id) = external_id {
+            self.external_ids.insert(external_id, id);
+        }
+        self.vertex_added(id);
+
+        Ok(DataResult::UnsignedInt(id))
+    }
+
+    fn restore_edge(&mut self, id: usize, edge: Edge) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        if !self.edges.can_insert(id) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "Edge {} can't be restored, as its id is in use or from before the graph was cleared",
+                id
+            )));
+        }
+        self.get_vertex(&edge.from_vertex_id)?;
+        self.get_vertex(&edge.to_vertex_id)?;
+
+        let from_vertex_id = edge.from_vertex_id;
+        let to_vertex_id = edge.to_vertex_id;
+        self.check_edge_policy(None, from_vertex_id, to_vertex_id, &edge.edge_vertex.label)?;
+        self.check_schema(EntityKind::Edge, &edge.edge_vertex.properties)?;
+
+        let edge = EdgeRecord::from_edge(edge, &mut self.symbols);
+        self.record_schema(EntityKind::Edge, &edge.properties);
+        self.edges.insert(id, edge);
+
+        self.link_edge(id, from_vertex_id, to_vertex_id);
+        self.edge_added(id);
+
+        Ok(DataResult::UnsignedInt(id))
+    }
+
+    fn update_vertex(
+        &mut self,
+        id: &usize,
+        properties: Vec<Property>,
+    ) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        let previous = match self.vertices.get(id) {
+            Some(val) => val.clone(),
+            None => return Err(GraphDbError::not_found(EntityKind::Vertex, id)),
+        };
+
+        self.check_schema(EntityKind::Vertex, &properties)?;
+
+        let mut vertex = previous.clone();
+        vertex.update(properties, &mut self.symbols);
+        self.record_schema(EntityKind::Vertex, &vertex.properties);
+
+        // Only reindex the properties that changed
+        if self.has_indexes() {
+            self.update_indexes(*id, &previous.without_properties_of(&vertex), false);
+            self.update_indexes(*id, &vertex.without_properties_of(&previous), true);
+        }
+
+        let result = vertex.to_vertex(&self.symbols);
+        if let Some(val) = self.vertices.get_mut(id) {
+            *val = vertex;
+        }
+        if self.is_observed() {
+            let before = previous.to_vertex(&self.symbols);
+            self.publish(Change::VertexUpdated {
+                id: *id,
+                before,
+                after: result.clone(),
+            });
+        }
+
+        Ok(DataResult::Vertex(result))
+    }
+
+    fn check_properties(&self, properties: &[Property]) -> Result<(), GraphDbError> {
+        self.check_schema(EntityKind::Vertex, properties)
+    }
+
+    fn update_edge(
+        &mut self,
+        id: &usize,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+        properties: Vec<Property>,
+    ) -> Result<DataResult, GraphDbError> {
+        self.check_storage()?;
+        let previous = match self.edges.get(id) {
+            Some(val) => val.clone(),
+            None => return Err(GraphDbError::not_found(EntityKind::Edge, id)),
+        };
+
+        // Check both vertices exist before changing anything, so a failure leaves the edge as it was
+        self.get_vertex(&from_vertex_id)?;
+        self.get_vertex(&to_vertex_id)?;
+        let label = self.symbols.resolve(previous.label);
+        self.check_edge_policy(Some(*id), from_vertex_id, to_vertex_id, label)?;
+        self.check_schema(EntityKind::Edge, &properties)?;
+
+        let mut edge = previous.clone();
+        edge.update(from_vertex_id, to_vertex_id, properties, &mut self.symbols);
+        self.record_schema(EntityKind::Edge, &edge.properties);
+
+        // A moved edge comes after its new vertices' other edges, as though it had just been added
+        if (from_vertex_id, to_vertex_id) != (previous.from_vertex_id, previous.to_vertex_id) {
+            self.unlink_edge(*id, previous.from_vertex_id, previous.to_vertex_id);
+            self.link_edge(*id, from_vertex_id, to_vertex_id);
+        }
+
+        let result = edge.to_edge(&self.symbols);
+        if let Some(val) = self.edges.get_mut(id) {
+            *val = edge;
+        }
+        if self.is_observed() {
+            let before = previous.to_edge(&self.symbols);
+            self.publish(Change::EdgeUpdated {
+                id: *id,
+                before,
+                after: result.clone(),
+            });
+        }
+
+        Ok(DataResult::Edge(result))
+    }
+
+    fn adjacent_edges(
+        &self,
+        id: &usize,
+        direction: Direction,
+    ) -> Result<Box<dyn Iterator<Item = AdjacentEdge> + '_>, GraphDbError> {
+        if self.vertices.get(id).is_none() {
+            return Err(GraphDbError::not_found(EntityKind::Vertex, id));
+        }
+
+        Ok(Box::new(self.adjacent(*id, direction)))
+    }
+
+    fn vertex_count(&self, label: Option<&str>) -> usize {
+        match label {
+            Some(label) => self
+                .symbols
+                .get(label)
+                .and_then(|label| self.vertex_labels.get(&label))
+                .map_or(0, |ids| ids.len()),
+            None => self.vertices.len(),
+        }
+    }
+
+    fn property_names(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        for vertex in self.vertices.values() {
+            for property in &vertex.properties {
+                if !keys.contains(&property.key) {
+                    keys.push(property.key);
+                }
+            }
+        }
+
+        keys.into_iter()
+            .map(|key| self.symbols.resolve(key))
+            .collect()
+    }
+
+    fn create_text_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError> {
+        let key = self.symbols.intern(property_name);
+        if self.text_indexes.contains_key(&key) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "A text index on property '{}' already exists",
+                property_name
+            )));
+        }
+
+        let mut index = TextIndex::new();
+        for (id, vertex) in self.vertices.iter() {
+            if let Some(PropertyValue::String(text)) = vertex.get_property_value(key) {
+                index.insert(id, text);
+            }
+        }
+        self.text_indexes.insert(key, index);
+
+        Ok(DataResult::Text(format!(
+            "Created text index on '{}'",
+            property_name
+        )))
+    }
+
+    fn drop_text_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError> {
+        match self
+            .symbols
+            .get(property_name)
+            .and_then(|key| self.text_indexes.remove(&key))
+        {
+            Some(_) => Ok(DataResult::Text(format!(
+                "Dropped text index on '{}'",
+                property_name
+            ))),
+            None => Err(no_text_index(property_name)),
+        }
+    }
+
+    fn text_search(&self, property_name: &str, query: &str) -> Result<DataResult, GraphDbError> {
+        let index = match self
+            .symbols
+            .get(property_name)
+            .and_then(|key| self.text_indexes.get(&key))
+        {
+            Some(index) => index,
+            None => return Err(no_text_index(property_name)),
+        };
+
+        let results = index
+            .search(query)
+            .into_iter()
+            .filter_map(|(id, score)| {
+                self.vertices
+                    .get(&id)
+                    .map(|vertex| (id, score, vertex.to_vertex(&self.symbols)))
+            })
+            .collect();
+
+        Ok(DataResult::ScoredVertexVector(results))
+    }
+
+    fn create_spatial_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError> {
+        let key = self.symbols.intern(property_name);
+        if self.spatial_indexes.contains_key(&key) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "A spatial index on property '{}' already exists",
+                property_name
+            )));
+        }
+
+        let mut index = SpatialIndex::new();
+        for (id, vertex) in self.vertices.iter() {
+            if let Some(point) = vertex.get_point(key) {
+                index.insert(id, *point);
+            }
+        }
+        self.spatial_indexes.insert(key, index);
+
+        Ok(DataResult::Text(format!(
+            "Created spatial index on '{}'",
+            property_name
+        )))
+    }
+
+    fn drop_spatial_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError> {
+        match self
+            .symbols
+            .get(property_name)
+            .and_then(|key| self.spatial_indexes.remove(&key))
+        {
+            Some(_) => Ok(DataResult::Text(format!(
+                "Dropped spatial index on '{}'",
+                property_name
+            ))),
+            None => Err(GraphDbError::InvalidArgument(format!(
+                "No spatial index on property '{}'",
+                property_name
+            ))),
+        }
+    }
+
+    fn create_vector_index(
+        &mut self,
+        property_name: &str,
+        metric: Metric,
+    ) -> Result<DataResult, GraphDbError> {
+        let key = self.symbols.intern(property_name);
+        if self.vector_indexes.contains_key(&key) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "A vector index on property '{}' already exists",
+                property_name
+            )));
+        }
+
+        let mut index = HnswIndex::new(metric);
+        for (id, vertex) in self.vertices.iter() {
+            if let Some(PropertyValue::Vector(vector)) = vertex.get_property_value(key) {
+                index.insert(id, vector.clone());
+            }
+        }
+        self.vector_indexes.insert(key, index);
+
+        Ok(DataResult::Text(format!(
+            "Created {} vector index on '{}'",
+            metric.as_str(),
+            property_name
+        )))
+    }
+
+    fn drop_vector_index(&mut self, property_name: &str) -> Result<DataResult, GraphDbError> {
+        match self
+            .symbols
+            .get(property_name)
+            .and_then(|key| self.vector_indexes.remove(&key))
+        {
+            Some(_) => Ok(DataResult::Text(format!(
+                "Dropped vector index on '{}'",
+                property_name
+            ))),
+            None => Err(GraphDbError::InvalidArgument(format!(
+                "No vector index on property '{}'",
+                property_name
+            ))),
+        }
+    }
+
+    fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
+
+    fn create_trigger(&mut self, trigger: Trigger) -> Result<DataResult, GraphDbError> {
+        trigger.validate()?;
+        if self.triggers.iter().any(|other| other.name == trigger.name) {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "A trigger named '{}' already exists",
+                trigger.name
+            )));
+        }
+
+        let message = format!("Created trigger '{}'", trigger.name);
+        self.triggers.push(trigger);
+
+        Ok(DataResult::Text(message))
+    }
+
+    fn drop_trigger(&mut self, name: &str) -> Result<DataResult, GraphDbError> {
+        match self
+            .triggers
+            .iter()
+            .position(|trigger| trigger.name == name)
+        {
+            Some(position) => {
+                self.triggers.remove(position);
+                Ok(DataResult::Text(format!("Dropped trigger '{}'", name)))
+            }
+            None => Err(GraphDbError::InvalidArgument(format!(
+                "No trigger named '{}'",
+                name
+            ))),
+        }
+    }
+
+    fn stats(&self) -> Result<DataResult, GraphDbError> {
+        let mut vertex_label_counts = Vec::new();
+        for (label, ids) in &self.vertex_labels {
+            if !ids.is_empty() {
+                vertex_label_counts.push((self.symbols.resolve(*label).to_string(), ids.len()));
+            }
+        }
+        vertex_label_counts.sort();
+
+        let mut property_stats: Vec<PropertyStats> = Vec::new();
+        let mut degree = DegreeStats {
+            min: 0,
+            max: 0,
+            mean: 0.0,
+        };
+        let mut total_degree = 0;
+        // Entity sizes include the struct itself, so only count the slabs' empty slots here
+        let mut approximate_memory_bytes = size_of::<InMemoryGraph>()
+            + self.name.capacity()
+            + self.symbols.approximate_size()
+            + self.vertices.approximate_size()
+            - self.vertices.len() * size_of::<VertexRecord>()
+            + self.edges.approximate_size()
+            - self.edges.len() * size_of::<EdgeRecord>()
+            + self.out_edges.approximate_size()
+            + self.in_edges.approximate_size();
+
+        for index in self.text_indexes.values() {
+            approximate_memory_bytes += size_of::<Symbol>() + index.approximate_size();
+        }
+
+        for index in self.spatial_indexes.values() {
+            approximate_memory_bytes += size_of::<Symbol>() + index.approximate_size();
+        }
+
+        for index in self.vector_indexes.values() {
+            approximate_memory_bytes += size_of::<Symbol>() + index.approximate_size();
+        }
+
+        for ids in self.vertex_labels.values() {
+            approximate_memory_bytes +=
+                size_of::<Symbol>() + size_of::<Vec<usize>>() + ids.capacity() * size_of::<usize>();
+        }
+
+        for (i, (id, vertex)) in self.vertices.iter().enumerate() {
+            for property in &vertex.properties {
+                let name = self.symbols.resolve(property.key);
+                let type_name = property.value.type_name();
+                match property_stats.iter_mut().find(|s| s.name == name) {
+                    Some(stats) => {
+                        stats.count += 1;
+                        if !stats.types.contains(&type_name) {
+                            stats.types.push(type_name);
+                        }
                     }
+                    None => property_stats.push(PropertyStats {
+                        name: name.to_owned(),
+                        count: 1,
+                        types: vec![type_name],
+                    }),
                 }
             }
+
+            let vertex_degree = self.out_edges.degree(id) + self.in_edges.degree(id);
+            total_degree += vertex_degree;
+            degree.max = degree.max.max(vertex_degree);
+            degree.min = match i {
+                0 => vertex_degree,
+                _ => degree.min.min(vertex_degree),
+            };
+
+            approximate_memory_bytes += vertex.approximate_size();
         }
 
-        match return_values {
-            true => Ok(DataResult::VertexValueVector(vertex_value_vector)),
-            false => Ok(DataResult::VertexIndexVector(vertex_indices)),
+        let mut edge_label_counts = Vec::new();
+        for edge in self.edges.values() {
+            increment_label_count(&mut edge_label_counts, self.symbols.resolve(edge.label));
+            approximate_memory_bytes += edge.approximate_size();
+        }
+
+        if self.vertices.len() > 0 {
+            degree.mean = total_degree as f64 / self.vertices.len() as f64;
         }
+
+        property_stats.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(DataResult::GraphStats(GraphStats {
+            vertex_count: self.vertices.len(),
+            edge_count: self.edges.len(),
+            vertex_label_counts,
+            edge_label_counts,
+            property_stats,
+            degree,
+            approximate_memory_bytes,
+        }))
     }
 
-    fn get_vertex(&self, id: &usize) -> Result<DataResult, String> {
-        match self.vertices.get(id) {
-            Some(val) => Ok(DataResult::VertexRef(val)),
-            None => Err(format!("Vertex ID: {} does not exist", id)),
+    fn describe(&self) -> Result<DataResult, GraphDbError> {
+        let text = |value: &str| Binding::Value(PropertyValue::String(value.to_string()));
+        let indexed = |mut names: Vec<String>| match names.is_empty() {
+            true => Binding::Null,
+            false => {
+                names.sort();
+                text(&names.join(", "))
+            }
+        };
+        let resolve = |key: &Symbol| self.symbols.resolve(*key).to_string();
+
+        let mut rows = vec![vec![text("type"), text(self.options.graph_type.as_str())]];
+        if let Some(path) = self.storage_path() {
+            rows.push(vec![
+                text(OptionKey::Path.as_str()),
+                text(&path.display().to_string()),
+            ]);
         }
+        rows.extend([
+            vec![
+                text(OptionKey::Directed.as_str()),
+                Binding::Value(PropertyValue::Boolean(self.options.directed)),
+            ],
+            vec![
+                text(OptionKey::EdgePolicy.as_str()),
+                text(self.options.edge_policy.as_str()),
+            ],
+            vec![
+                text(OptionKey::Schema.as_str()),
+                text(self.options.schema.as_str()),
+            ],
+            vec![
+                text(OptionKey::TextIndex.as_str()),
+                indexed(self.text_indexes.keys().map(resolve).collect()),
+            ],
+            vec![
+                text(OptionKey::SpatialIndex.as_str()),
+                indexed(self.spatial_indexes.keys().map(resolve).collect()),
+            ],
+            vec![
+                text(OptionKey::VectorIndex.as_str()),
+                indexed(
+                    self.vector_indexes
+                        .iter()
+                        .map(|(key, index)| {
+                            format!("{} ({})", resolve(key), index.metric().as_str())
+                        })
+                        .collect(),
+                ),
+            ],
+            vec![
+                text("versioning"),
+                Binding::Value(PropertyValue::Boolean(self.history.is_some())),
+            ],
+        ]);
+
+        Ok(DataResult::Table(
+            vec!["setting".to_string(), "value".to_string()],
+            rows,
+        ))
     }
 
-    fn remove_vertex(&mut self, id: &usize) -> Result<DataResult, String> {
-        // TODO: Delete any edges attached to the vertex
-        match self.vertices.remove(id) {
-            Some(_) => Ok(DataResult::UnsignedInt(*id)),
-            None => Err(format!("Vertex ID: {} does not exist", id)),
+    fn enable_versioning(&mut self) -> Result<DataResult, GraphDbError> {
+        if self.history.is_some() {
+            return Err(GraphDbError::ConstraintViolation(format!(
+                "Versioning is already enabled for graph '{}'",
+                self.name
+            )));
         }
+
+        self.start_history();
+        Ok(DataResult::Text(format!(
+            "Enabled versioning for graph '{}'",
+            self.name
+        )))
     }
 
-    fn get_mutable_vertex(&mut self, id: &usize) -> Result<DataResult, String> {
-        match self.vertices.get_mut(id) {
-            Some(val) => Ok(DataResult::MutableVertexRef(val)),
-            None => Err(format!("Vertex ID: {} does not exist", id)),
+    fn disable_versioning(&mut self) -> Result<DataResult, GraphDbError> {
+        match self.history.take() {
+            Some(_) => Ok(DataResult::Text(format!(
+                "Disabled versioning for graph '{}'",
+                self.name
+            ))),
+            None => Err(GraphDbError::InvalidArgument(format!(
+                "Versioning isn't enabled for graph '{}'",
+                self.name
+            ))),
         }
     }
 
+    fn as_of(&self, timestamp: i64) -> Result<Box<dyn Graph>, GraphDbError> {
+        Ok(Box::new(self.snapshot(timestamp)?))
+    }
+
+    fn vertex_history(&self, id: &usize) -> Result<DataResult, GraphDbError> {
+        self.vertex_versions(*id)
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn options(&self) -> &GraphOptions {
+        &self.options
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    fn set_session(&mut self, session: Option<SessionId>) {
+        self.session = session;
+    }
+
+    fn clear(&mut self) {
+        // Indexes and triggers stay defined, but indexes are emptied along with the graph
+        let resolve = |key: &Symbol| self.symbols.resolve(*key).to_string();
+        let text_indexed: Vec<String> = self.text_indexes.keys().map(resolve).collect();
+        let spatially_indexed: Vec<String> = self.spatial_indexes.keys().map(resolve).collect();
+        let vector_indexed: Vec<(String, Metric)> = self
+            .vector_indexes
+            .iter()
+            .map(|(key, index)| (resolve(key), index.metric()))
+            .collect();
+        self.text_indexes.clear();
+        self.spatial_indexes.clear();
+        self.vector_indexes.clear();
+        self.vector_dimensions.clear();
+        self.property_types.clear();
+
+        self.symbols.clear();
+        self.vertices.clear();
+        self.edges.clear();
+        self.out_edges.clear();
+        self.in_edges.clear();
+        self.vertex_labels.clear();
+        self.external_ids.clear();
+
+        for name in text_indexed {
+            let key = self.symbols.intern(&name);
+            self.text_indexes.insert(key, TextIndex::new());
+        }
+
+        for name in spatially_indexed {
+            let key = self.symbols.intern(&name);
+            self.spatial_indexes.insert(key, SpatialIndex::new());
+        }
+
+        for (name, metric) in vector_indexed {
+            let key = self.symbols.intern(&name);
+            self.vector_indexes.insert(key, HnswIndex::new(metric));
+        }
+
+        self.publish(Change::GraphCleared);
+    }
+
+    fn clone_graph(&self, name: String, session: Option<SessionId>) -> Box<dyn Graph> {
+        let mut graph = self.clone();
+        graph.name = name;
+
+        // A file belongs to one graph, so the copy of a persistent graph is kept in memory
+        graph.options.graph_type = GraphType::InMemory;
+        graph.options.path = None;
+        graph.storage = None;
+        graph.storage_error = None;
+
+        // Followers of the new graph see its contents arrive, as if they were added one by one.
+        // The copy keeps the history it was cloned with, so arrivals aren't added to it
+        graph.session = session;
+        graph.announce_contents();
+        graph.session = None;
+
+        Box::new(graph)
+    }
+
+    fn delete_storage(&mut self) -> Result<(), GraphDbError> {
+        if let Some(storage) = &self.storage {
+            storage.delete()?;
+        }
+        self.storage = None;
+        Ok(())
+    }
 }
 
+fn sorted(mut ids: Vec<usize>) -> Vec<usize> {
+    ids.sort_unstable();
+    ids
+}
+
+fn no_text_index(property_name: &str) -> GraphDbError {
+    GraphDbError::InvalidArgument(format!(
+        "No text index on property '{}'. Create one with createTextIndex({})",
+        property_name, property_name
+    ))
+}
+
+fn vector_dimension_error(property_name: &str, dimension: usize, length: usize) -> GraphDbError {
+    GraphDbError::ConstraintViolation(format!(
+        "Property '{}' holds vectors of length {}, not {}",
+        property_name, dimension, length
+    ))
+}
+
+/// Holds every graph by name. The registry has its own lock, separate from each graph's, so
+/// operations on different graphs never wait on each other. Locks are always taken registry first
 pub struct GraphFactory {
-    pub graphs: EntityMap<Box<dyn Graph>>,
+    registry: RwLock<Registry>,
+    changes: Arc<ChangeFeed>, // shared by every graph
 }
 
-impl GraphFactory {
-    pub fn new() -> GraphFactory {
-        GraphFactory {
-            graphs: EntityMap::new(),
+struct Registry {
+    graphs: EntityMap<SharedGraph>,
+    graph_ids: HashMap<String, usize>, // graph name, graph id
+}
+
+impl Registry {
+    fn get_graph_id(&self, graph_name: &str) -> Result<usize, GraphDbError> {
+        match self.graph_ids.get(graph_name) {
+            Some(index) => Ok(*index),
+            None => Err(GraphDbError::not_found(EntityKind::Graph, graph_name)),
         }
     }
 
-    pub fn create_graph(
-        &mut self,
-        graph_name: String,
-        graph_type: &GraphType,
-    ) -> Result<DataResult, String> {
+    fn get_graph(&self, graph_name: &str) -> Result<&SharedGraph, GraphDbError> {
+        let index = self.get_graph_id(graph_name)?;
+
+        match self.graphs.get(&index) {
+            Some(graph) => Ok(graph),
+            None => Err(GraphDbError::not_found(EntityKind::Graph, graph_name)),
+        }
+    }
+
+    fn validate_new_graph_name(&self, graph_name: &str) -> Result<(), GraphDbError> {
         if graph_name.trim().is_empty() {
-            return Err(format!("Must provide a graph name"));
+            return Err(GraphDbError::InvalidArgument(
+                "Must provide a graph name".to_string(),
+            ));
         }
 
-        for (_, graph) in self.graphs.entities() {
-            if graph.name() == graph_name {
-                return Err(format!("Graph with name '{}' already exists", graph.name()));
-            }
+        if self.graph_ids.contains_key(graph_name) {
+            return Err(GraphDbError::GraphExists(graph_name.to_string()));
         }
 
-        match graph_type {
-            GraphType::InMemory => {
-                let graph = Box::new(InMemoryGraph {
-                    name: graph_name,
-                    vertices: EntityMap::new(),
-                    edges: EntityMap::new(),
-                });
-                let index = self.graphs.push(graph);
-                Ok(DataResult::UnsignedInt(index))
+        Ok(())
+    }
+
+    /// Two graphs writing to one file would each read the other's changes back as their own
+    fn validate_new_graph_path(&self, path: &Path) -> Result<(), GraphDbError> {
+        for graph in self.graphs.values() {
+            let graph = read_lock(graph)?;
+            if graph.options().path.as_deref() == Some(path) {
+                return Err(GraphDbError::ConstraintViolation(format!(
+                    "Graph '{}' already keeps its data in {}",
+                    graph.name(),
+                    path.display()
+                )));
             }
         }
+
+        Ok(())
     }
 
-    pub fn list_graphs(&self) -> Result<DataResult, String> {
-        let mut graphs = Vec::new();
-        for (_, graph) in self.graphs.entities() {
-            graphs.push(graph.name());
-        }
+    fn insert_graph(&mut self, graph: Box<dyn Graph>) -> usize {
+        let graph_name = graph.name().to_string();
+        let index = self.graphs.push(Arc::new(RwLock::new(graph)));
+        self.graph_ids.insert(graph_name, index);
 
-        Ok(DataResult::StringVector(graphs))
+        index
     }
+}
 
-    pub fn get_graph(&mut self, graph_name: &str) -> Result<&mut Box<dyn Graph>, String> {
-        let mut graph_ref: Option<&mut Box<dyn Graph>> = None;
-        for (_, graph) in self.graphs.entities_mut() {
-            if (*graph).name() == graph_name {
-                graph_ref = Some(graph);
-                break;
-            }
+impl GraphFactory {
+    pub fn new() -> GraphFactory {
+        GraphFactory {
+            registry: RwLock::new(Registry {
+                graphs: EntityMap::new(),
+                graph_ids: HashMap::new(),
+            }),
+            changes: Arc::new(ChangeFeed::new()),
         }
+    }
 
-        match graph_ref {
-            Some(graph) => Ok(graph),
-            None => Err(format!("Unknown graph: {}", graph_name)),
+    /// Every change made to the factory's graphs
+    pub fn changes(&self) -> &ChangeFeed {
+        &self.changes
+    }
+
+    /// Creates a graph of the type its options name. A persistent graph is read back from its
+    /// file if it already exists. Each of these calls makes its changes on behalf of the session,
+    /// if one is given
+    pub fn create_graph(
+        &self,
+        graph_name: String,
+        options: GraphOptions,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
+        let mut registry = write_lock(&self.registry)?;
+        registry.validate_new_graph_name(&graph_name)?;
+        if let Some(path) = &options.path {
+            registry.validate_new_graph_path(path)?;
         }
+
+        let graph = Box::new(InMemoryGraph::create(
+            graph_name,
+            options,
+            self.changes.clone(),
+            session,
+        )?);
+        let index = registry.insert_graph(graph);
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    pub fn drop_graph(
+        &self,
+        graph_name: &str,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
+        let mut registry = write_lock(&self.registry)?;
+        let index = registry.get_graph_id(graph_name)?;
+
+        // The file goes with the graph, so a graph created later with the same path starts empty
+        write_lock(registry.get_graph(graph_name)?)?.delete_storage()?;
+
+        // Anyone still holding the graph keeps a detached copy until they let it go
+        registry.graph_ids.remove(graph_name);
+        registry.graphs.remove(&index);
+        self.changes
+            .publish_at(graph_name, Change::GraphDropped, now(), session);
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    pub fn rename_graph(
+        &self,
+        graph_name: &str,
+        new_graph_name: String,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
+        let mut registry = write_lock(&self.registry)?;
+        let index = registry.get_graph_id(graph_name)?;
+        registry.validate_new_graph_name(&new_graph_name)?;
+
+        // Published before the graph is unlocked, so no change under the new name comes first
+        let mut graph = write_lock(registry.get_graph(graph_name)?)?;
+        graph.set_name(new_graph_name.to_owned());
+        self.changes.publish_at(
+            graph_name,
+            Change::GraphRenamed {
+                new_name: new_graph_name.to_owned(),
+            },
+            now(),
+            session,
+        );
+        drop(graph);
+        registry.graph_ids.remove(graph_name);
+        registry.graph_ids.insert(new_graph_name, index);
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    pub fn clear_graph(
+        &self,
+        graph_name: &str,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
+        let registry = read_lock(&self.registry)?;
+        let index = registry.get_graph_id(graph_name)?;
+        write_lock_for(registry.get_graph(graph_name)?, session)?.clear();
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    pub fn clone_graph(
+        &self,
+        graph_name: &str,
+        new_graph_name: String,
+        session: Option<SessionId>,
+    ) -> Result<DataResult, GraphDbError> {
+        let mut registry = write_lock(&self.registry)?;
+        registry.validate_new_graph_name(&new_graph_name)?;
+
+        let graph =
+            read_lock(registry.get_graph(graph_name)?)?.clone_graph(new_graph_name, session);
+        let index = registry.insert_graph(graph);
+
+        Ok(DataResult::UnsignedInt(index))
+    }
+
+    /// A snapshot of the graphs that exist right now
+    pub fn graphs(&self) -> Result<Vec<SharedGraph>, GraphDbError> {
+        let registry = read_lock(&self.registry)?;
+        Ok(registry.graphs.values().cloned().collect())
+    }
+
+    pub fn list_graphs(&self) -> Result<DataResult, GraphDbError> {
+        let registry = read_lock(&self.registry)?;
+        let mut graphs: Vec<String> = registry.graph_ids.keys().cloned().collect();
+        graphs.sort();
+
+        Ok(DataResult::StringVector(graphs))
+    }
+
+    pub fn get_graph(&self, graph_name: &str) -> Result<SharedGraph, GraphDbError> {
+        let registry = read_lock(&self.registry)?;
+        registry.get_graph(graph_name).cloned()
+    }
+}
+
+impl Default for GraphFactory {
+    fn default() -> Self {
+        Self::new()
     }
 }
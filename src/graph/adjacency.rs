@@ -0,0 +1,212 @@
+use std::{collections::HashMap, mem::size_of};
+
+/// Marks an entry whose edge has been removed, until the next compaction drops it
+const REMOVED: usize = usize::MAX;
+
+/// Compaction is skipped until this many entries are waiting, so small graphs never pay for it
+const MIN_COMPACTION_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Out,
+    In,
+}
+
+/// An edge as seen from one of its vertices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdjacentEdge {
+    pub edge_id: usize,
+    pub vertex_id: usize, // the vertex at the other end
+}
+
+/// One direction of a graph's adjacency lists, in compressed sparse row form: every vertex's
+/// edges sit next to each other in a single array, found through a per-vertex offset.
+///
+/// Edges added since the last compaction are kept in a per-vertex overflow, and removed edges
+/// are marked in place. Once either grows past a fraction of the whole, the rows are rebuilt
+#[derive(Debug, Clone)]
+pub struct Adjacency {
+    first_vertex_id: usize,
+    offsets: Vec<usize>, // row i holds entries[offsets[i]..offsets[i + 1]], for first_vertex_id + i
+    entries: Vec<AdjacentEdge>,
+    overflow: HashMap<usize, Vec<AdjacentEdge>>, // vertex id, edges added since compaction
+    overflow_len: usize,
+    removed_len: usize,
+}
+
+impl Adjacency {
+    pub fn new() -> Self {
+        Adjacency {
+            first_vertex_id: 0,
+            offsets: vec![0],
+            entries: Vec::new(),
+            overflow: HashMap::new(),
+            overflow_len: 0,
+            removed_len: 0,
+        }
+    }
+
+    /// Number of edges held
+    fn len(&self) -> usize {
+        self.entries.len() - self.removed_len + self.overflow_len
+    }
+
+    pub fn insert(&mut self, vertex_id: usize, edge: AdjacentEdge) {
+        self.overflow.entry(vertex_id).or_default().push(edge);
+        self.overflow_len += 1;
+
+        self.compact_if_needed();
+    }
+
+    /// Removes the given edge from a vertex's row, returning whether it was found
+    pub fn remove(&mut self, vertex_id: usize, edge_id: usize) -> bool {
+        if let Some(edges) = self.overflow.get_mut(&vertex_id) {
+            if let Some(position) = edges.iter().position(|edge| edge.edge_id == edge_id) {
+                edges.remove(position);
+                self.overflow_len -= 1;
+                if edges.is_empty() {
+                    self.overflow.remove(&vertex_id);
+                }
+
+                return true;
+            }
+        }
+
+        let found = match self.row_range(vertex_id) {
+            Some(range) => match self.entries[range]
+                .iter_mut()
+                .find(|edge| edge.edge_id == edge_id)
+            {
+                Some(edge) => {
+                    edge.edge_id = REMOVED;
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        };
+
+        if found {
+            self.removed_len += 1;
+            self.compact_if_needed();
+        }
+
+        found
+    }
+
+    /// Removes a vertex's whole row, returning the edges it held
+    pub fn remove_vertex(&mut self, vertex_id: usize) -> Vec<AdjacentEdge> {
+        let mut removed = Vec::new();
+
+        if let Some(range) = self.row_range(vertex_id) {
+            for edge in &mut self.entries[range] {
+                if edge.edge_id != REMOVED {
+                    removed.push(*edge);
+                    edge.edge_id = REMOVED;
+                    self.removed_len += 1;
+                }
+            }
+        }
+
+        if let Some(edges) = self.overflow.remove(&vertex_id) {
+            self.overflow_len -= edges.len();
+            removed.extend(edges);
+        }
+
+        self.compact_if_needed();
+
+        removed
+    }
+
+    /// The edges in a vertex's row, in the order they were added
+    pub fn edges(&self, vertex_id: usize) -> impl Iterator<Item = AdjacentEdge> + '_ {
+        let row = match self.row_range(vertex_id) {
+            Some(range) => &self.entries[range],
+            None => &[],
+        };
+
+        row.iter()
+            .filter(|edge| edge.edge_id != REMOVED)
+            .chain(self.overflow.get(&vertex_id).into_iter().flatten())
+            .copied()
+    }
+
+    pub fn degree(&self, vertex_id: usize) -> usize {
+        self.edges(vertex_id).count()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Adjacency::new();
+    }
+
+    /// Approximate bytes used, including heap allocations
+    pub fn approximate_size(&self) -> usize {
+        let overflow_size: usize = self
+            .overflow
+            .values()
+            .map(|edges| {
+                size_of::<usize>()
+                    + size_of::<Vec<AdjacentEdge>>()
+                    + edges.capacity() * size_of::<AdjacentEdge>()
+            })
+            .sum();
+
+        size_of::<Adjacency>()
+            + self.offsets.capacity() * size_of::<usize>()
+            + self.entries.capacity() * size_of::<AdjacentEdge>()
+            + overflow_size
+    }
+
+    fn row_range(&self, vertex_id: usize) -> Option<std::ops::Range<usize>> {
+        let row = vertex_id.checked_sub(self.first_vertex_id)?;
+        let start = *self.offsets.get(row)?;
+        let end = *self.offsets.get(row + 1)?;
+
+        Some(start..end)
+    }
+
+    fn compact_if_needed(&mut self) {
+        let pending = self.overflow_len + self.removed_len;
+        if pending >= MIN_COMPACTION_SIZE && pending * 4 >= self.entries.len() {
+            self.compact();
+        }
+    }
+
+    /// Rebuilds the rows, folding in the overflow and dropping removed entries
+    fn compact(&mut self) {
+        let row_ids = (self.first_vertex_id..self.first_vertex_id + self.offsets.len() - 1)
+            .filter(|vertex_id| self.edges(*vertex_id).next().is_some());
+        let vertex_ids: Vec<usize> = row_ids.chain(self.overflow.keys().copied()).collect();
+        let (first_vertex_id, last_vertex_id) =
+            match (vertex_ids.iter().min(), vertex_ids.iter().max()) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => {
+                    self.clear();
+                    return;
+                }
+            };
+
+        let mut offsets = Vec::with_capacity(last_vertex_id - first_vertex_id + 2);
+        let mut entries = Vec::with_capacity(self.len());
+        offsets.push(0);
+        for vertex_id in first_vertex_id..=last_vertex_id {
+            entries.extend(self.edges(vertex_id));
+            offsets.push(entries.len());
+        }
+
+        *self = Adjacency {
+            first_vertex_id,
+            offsets,
+            entries,
+            overflow: HashMap::new(),
+            overflow_len: 0,
+            removed_len: 0,
+        };
+    }
+}
+
+impl Default for Adjacency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
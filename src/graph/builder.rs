@@ -0,0 +1,95 @@
+use std::sync::RwLock;
+
+use crate::error::GraphDbError;
+
+use super::{
+    edge::Edge,
+    property::{Property, PropertyValue},
+    vertex::Vertex,
+    write_lock, DataResult, Graph,
+};
+
+/// Builds a vertex property by property, then adds it to the graph on `insert`. The graph is
+/// only locked while inserting
+pub struct VertexBuilder<'a> {
+    graph: &'a RwLock<Box<dyn Graph>>,
+    vertex: Vertex,
+}
+
+impl<'a> VertexBuilder<'a> {
+    pub fn new(graph: &'a RwLock<Box<dyn Graph>>, label: &str) -> Self {
+        VertexBuilder {
+            graph,
+            vertex: Vertex::new(label.to_string(), Vec::new()),
+        }
+    }
+
+    /// Sets a property, replacing any earlier value with the same name
+    pub fn property(mut self, name: &str, value: impl Into<PropertyValue>) -> Self {
+        set_property(&mut self.vertex, name, value.into());
+        self
+    }
+
+    pub fn external_id(mut self, external_id: &str) -> Self {
+        self.vertex.external_id = Some(external_id.to_string());
+        self
+    }
+
+    /// Adds the vertex to the graph, returning its id
+    pub fn insert(mut self) -> Result<usize, GraphDbError> {
+        self.vertex.properties.shrink_to_fit();
+        to_id(write_lock(self.graph)?.add_vertex(self.vertex)?)
+    }
+}
+
+/// Builds an edge property by property, then adds it to the graph on `insert`
+pub struct EdgeBuilder<'a> {
+    graph: &'a RwLock<Box<dyn Graph>>,
+    edge: Edge,
+}
+
+impl<'a> EdgeBuilder<'a> {
+    pub fn new(
+        graph: &'a RwLock<Box<dyn Graph>>,
+        label: &str,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+    ) -> Self {
+        EdgeBuilder {
+            graph,
+            edge: Edge {
+                from_vertex_id,
+                to_vertex_id,
+                edge_vertex: Vertex::new(label.to_string(), Vec::new()),
+            },
+        }
+    }
+
+    /// Sets a property, replacing any earlier value with the same name
+    pub fn property(mut self, name: &str, value: impl Into<PropertyValue>) -> Self {
+        set_property(&mut self.edge.edge_vertex, name, value.into());
+        self
+    }
+
+    /// Adds the edge to the graph, returning its id
+    pub fn insert(mut self) -> Result<usize, GraphDbError> {
+        self.edge.edge_vertex.properties.shrink_to_fit();
+        to_id(write_lock(self.graph)?.add_edge(self.edge)?)
+    }
+}
+
+fn set_property(vertex: &mut Vertex, name: &str, value: PropertyValue) {
+    vertex.properties.retain(|property| property.name != name);
+    vertex.properties.push(Property {
+        name: name.to_string(),
+        value,
+        flagged_for_removal: false,
+    });
+}
+
+fn to_id(result: DataResult) -> Result<usize, GraphDbError> {
+    match result {
+        DataResult::UnsignedInt(id) => Ok(id),
+        _ => Err(GraphDbError::Internal("Mismatched return type".to_string())),
+    }
+}
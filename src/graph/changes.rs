@@ -0,0 +1,437 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::OpenOptions,
+    io::Write,
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use json::JsonValue as Json;
+
+use crate::error::GraphDbError;
+
+use super::{edge::Edge, property::Property, vertex::Vertex};
+
+/// Events kept for subscribers resuming from an earlier sequence number, once the feed has had a
+/// subscriber or sink
+pub const DEFAULT_RETAINED_EVENTS: usize = 10_000;
+
+/// Identifies a session, such as a REPL user's, so the changes made by its commands can be told
+/// apart from everyone else's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionId(u64);
+
+impl SessionId {
+    /// An id no other session has
+    pub fn new() -> Self {
+        static NEXT_SESSION: AtomicU64 = AtomicU64::new(0);
+        SessionId(NEXT_SESSION.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for SessionId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single mutation, with images of the entity before and after it where they exist
+#[derive(Debug, Clone)]
+pub enum Change {
+    VertexAdded {
+        id: usize,
+        after: Vertex,
+    },
+    VertexUpdated {
+        id: usize,
+        before: Vertex,
+        after: Vertex,
+    },
+    /// Preceded by an EdgeRemoved event for each edge attached to the vertex
+    VertexRemoved {
+        id: usize,
+        before: Vertex,
+    },
+    EdgeAdded {
+        id: usize,
+        after: Edge,
+    },
+    /// Its properties changed, or it was moved between vertices
+    EdgeUpdated {
+        id: usize,
+        before: Edge,
+        after: Edge,
+    },
+    EdgeRemoved {
+        id: usize,
+        before: Edge,
+    },
+    /// Every vertex and edge was removed, without an event for each
+    GraphCleared,
+    /// A persistent graph was read back from its file, without an event for each vertex and edge
+    GraphLoaded,
+    GraphDropped,
+    GraphRenamed {
+        new_name: String,
+    },
+}
+
+impl Change {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Change::VertexAdded { .. } => "vertexAdded",
+            Change::VertexUpdated { .. } => "vertexUpdated",
+            Change::VertexRemoved { .. } => "vertexRemoved",
+            Change::EdgeAdded { .. } => "edgeAdded",
+            Change::EdgeUpdated { .. } => "edgeUpdated",
+            Change::EdgeRemoved { .. } => "edgeRemoved",
+            Change::GraphCleared => "graphCleared",
+            Change::GraphLoaded => "graphLoaded",
+            Change::GraphDropped => "graphDropped",
+            Change::GraphRenamed { .. } => "graphRenamed",
+        }
+    }
+}
+
+/// A change to a graph, numbered in the order changes were made across every graph
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub sequence: u64,
+    pub timestamp: i64, // milliseconds since January 1, 1970
+    pub graph: String,
+    pub change: Change,
+}
+
+impl ChangeEvent {
+    /// E.g. {"sequence":3,"timestamp":..,"graph":"g","type":"vertexUpdated","id":0,"label":"person",
+    /// "before":{"age":30},"after":{"age":31}}
+    pub fn to_json(&self) -> Json {
+        let mut event = json::object! {
+            sequence: self.sequence,
+            timestamp: self.timestamp,
+            graph: self.graph.as_str(),
+            type: self.change.as_str(),
+        };
+
+        let (id, edge, before, after) = match &self.change {
+            Change::VertexAdded { id, after } => (id, None, None, Some(after)),
+            Change::VertexUpdated { id, before, after } => (id, None, Some(before), Some(after)),
+            Change::VertexRemoved { id, before } => (id, None, Some(before), None),
+            Change::EdgeAdded { id, after } => (id, Some(after), None, Some(&after.edge_vertex)),
+            Change::EdgeUpdated { id, before, after } => {
+                event["previousFrom"] = before.from_vertex_id.into();
+                event["previousTo"] = before.to_vertex_id.into();
+                (
+                    id,
+                    Some(after),
+                    Some(&before.edge_vertex),
+                    Some(&after.edge_vertex),
+                )
+            }
+            Change::EdgeRemoved { id, before } => {
+                (id, Some(before), Some(&before.edge_vertex), None)
+            }
+            Change::GraphCleared | Change::GraphLoaded | Change::GraphDropped => return event,
+            Change::GraphRenamed { new_name } => {
+                event["newName"] = new_name.as_str().into();
+                return event;
+            }
+        };
+
+        event["id"] = (*id).into();
+        if let Some(edge) = edge {
+            event["from"] = edge.from_vertex_id.into();
+            event["to"] = edge.to_vertex_id.into();
+        }
+        if let Some(vertex) = after.or(before) {
+            event["label"] = vertex.label.as_str().into();
+            if let Some(external_id) = &vertex.external_id {
+                event["externalId"] = external_id.as_str().into();
+            }
+        }
+        if let Some(vertex) = before {
+            event["before"] = properties_to_json(&vertex.properties);
+        }
+        if let Some(vertex) = after {
+            event["after"] = properties_to_json(&vertex.properties);
+        }
+
+        event
+    }
+}
+
+fn properties_to_json(properties: &[Property]) -> Json {
+    let mut object = Json::new_object();
+    for property in properties {
+        object[property.name.as_str()] = property.value.to_json();
+    }
+
+    object
+}
+
+/// Publishes every change made to the graphs of a database, in order. Consumers either
+/// subscribe for events over a channel, or add a sink that has each event written to it as a
+/// line of JSON. Once a consumer has been added, the most recent events are retained, so a
+/// consumer that stops can resume from the last sequence number it saw. Until then nothing is
+/// retained, and graphs skip building events no one would see. A session can also capture just
+/// the events its own commands publish, as its journal does.
+///
+/// Events are published while the changed graph is locked for writing, and sinks are written
+/// to before the lock is released, so a slow sink slows writers down
+pub struct ChangeFeed {
+    state: Mutex<FeedState>,
+}
+
+struct FeedState {
+    last_sequence: u64,
+    retained: VecDeque<ChangeEvent>, // ascending sequence numbers, without gaps
+    retention: Option<usize>,        // none until chosen, or until the first consumer is added
+    subscribers: Vec<Sender<ChangeEvent>>,
+    sinks: Vec<Box<dyn Write + Send>>,
+    captures: HashMap<SessionId, Vec<ChangeEvent>>, // events published for sessions capturing them
+}
+
+impl ChangeFeed {
+    pub fn new() -> Self {
+        ChangeFeed {
+            state: Mutex::new(FeedState {
+                last_sequence: 0,
+                retained: VecDeque::new(),
+                retention: None,
+                subscribers: Vec::new(),
+                sinks: Vec::new(),
+                captures: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Numbers the change and sends it to every subscriber and sink
+    pub fn publish(&self, graph: &str, change: Change) {
+        self.publish_at(graph, change, now(), None);
+    }
+
+    /// Publishes the change as made at the given time, in milliseconds since January 1, 1970,
+    /// by a command run for the session if one is given
+    pub(crate) fn publish_at(
+        &self,
+        graph: &str,
+        change: Change,
+        timestamp: i64,
+        session: Option<SessionId>,
+    ) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        state.last_sequence += 1;
+        let event = ChangeEvent {
+            sequence: state.last_sequence,
+            timestamp,
+            graph: graph.to_string(),
+            change,
+        };
+
+        if let Some(captured) = session.and_then(|session| state.captures.get_mut(&session)) {
+            captured.push(event.clone());
+        }
+
+        // A subscriber that has dropped its receiver has gone away
+        state
+            .subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        if !state.sinks.is_empty() {
+            let line = event.to_json().dump();
+            // A sink that fails to write, e.g. a closed socket, is dropped
+            state
+                .sinks
+                .retain_mut(|sink| write_line(sink, &line).is_ok());
+        }
+
+        let retention = state.retention.unwrap_or(0);
+        if retention > 0 {
+            state.retained.push_back(event);
+            while state.retained.len() > retention {
+                state.retained.pop_front();
+            }
+        }
+    }
+
+    /// Whether published events would reach anyone: a subscriber, a sink, a capture, or the
+    /// retained events
+    pub fn is_followed(&self) -> bool {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        !state.subscribers.is_empty()
+            || !state.sinks.is_empty()
+            || !state.captures.is_empty()
+            || state.retention.unwrap_or(0) > 0
+    }
+
+    /// Runs the function, collecting the events published meanwhile by commands run for the
+    /// session. Anyone else's changes are left out, whichever thread they're made on
+    pub fn capture<T>(
+        &self,
+        session: SessionId,
+        run: impl FnOnce() -> T,
+    ) -> Result<(T, Vec<ChangeEvent>), GraphDbError> {
+        let outer = self.lock()?.captures.insert(session, Vec::new());
+
+        let result = run();
+
+        let mut state = self.lock()?;
+        let captured = state.captures.remove(&session).unwrap_or_default();
+        if let Some(mut outer) = outer {
+            // A capture within another collects its events for both
+            outer.extend(captured.iter().cloned());
+            state.captures.insert(session, outer);
+        }
+
+        Ok((result, captured))
+    }
+
+    /// The sequence number of the latest event, or 0 if there are none
+    pub fn last_sequence(&self) -> Result<u64, GraphDbError> {
+        Ok(self.lock()?.last_sequence)
+    }
+
+    /// How many of the latest events are kept for resuming consumers. Set before any consumer is
+    /// added, this keeps events from then on, rather than from when the first consumer is added
+    pub fn set_retention(&self, events: usize) -> Result<(), GraphDbError> {
+        let mut state = self.lock()?;
+        state.retention = Some(events);
+        while state.retained.len() > events {
+            state.retained.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Receives every event after the given sequence number, or only new events if none is
+    /// given. Fails if events after it are no longer retained
+    pub fn subscribe(&self, after: Option<u64>) -> Result<Receiver<ChangeEvent>, GraphDbError> {
+        let mut state = self.lock()?;
+        state.retention.get_or_insert(DEFAULT_RETAINED_EVENTS);
+        let (sender, receiver) = channel();
+        for event in state.events_after(after)? {
+            // Can't fail, as the receiver is still held here
+            let _ = sender.send(event.clone());
+        }
+        state.subscribers.push(sender);
+
+        Ok(receiver)
+    }
+
+    /// Writes every event after the given sequence number, or only new events if none is given,
+    /// as newline delimited JSON
+    pub fn add_sink(
+        &self,
+        mut sink: Box<dyn Write + Send>,
+        after: Option<u64>,
+    ) -> Result<(), GraphDbError> {
+        let mut state = self.lock()?;
+        state.retention.get_or_insert(DEFAULT_RETAINED_EVENTS);
+        for event in state.events_after(after)? {
+            write_line(&mut sink, &event.to_json().dump()).map_err(sink_error)?;
+        }
+        state.sinks.push(sink);
+
+        Ok(())
+    }
+
+    /// Appends events to a file, creating it if it doesn't exist
+    pub fn add_file_sink(
+        &self,
+        path: impl AsRef<Path>,
+        after: Option<u64>,
+    ) -> Result<(), GraphDbError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(sink_error)?;
+
+        self.add_sink(Box::new(file), after)
+    }
+
+    /// Streams events to a TCP socket, e.g. "localhost:9000"
+    pub fn add_socket_sink(
+        &self,
+        address: impl ToSocketAddrs,
+        after: Option<u64>,
+    ) -> Result<(), GraphDbError> {
+        let stream = TcpStream::connect(address).map_err(sink_error)?;
+
+        self.add_sink(Box::new(stream), after)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, FeedState>, GraphDbError> {
+        self.state
+            .lock()
+            .map_err(|_| GraphDbError::Internal("The change feed lock was poisoned".to_string()))
+    }
+}
+
+impl FeedState {
+    fn events_after(
+        &self,
+        after: Option<u64>,
+    ) -> Result<impl Iterator<Item = &ChangeEvent>, GraphDbError> {
+        let after = after.unwrap_or(self.last_sequence);
+        if after > self.last_sequence {
+            return Err(GraphDbError::InvalidArgument(format!(
+                "Sequence number {} hasn't been reached. The latest is {}",
+                after, self.last_sequence
+            )));
+        }
+
+        // Retained events have no gaps, so the first one tells us whether any are missing
+        let oldest = self
+            .retained
+            .front()
+            .map_or(self.last_sequence + 1, |event| event.sequence);
+        if after + 1 < oldest {
+            return Err(GraphDbError::InvalidArgument(format!(
+                "Events after sequence number {} are no longer retained. The oldest is {}",
+                after, oldest
+            )));
+        }
+
+        Ok(self
+            .retained
+            .iter()
+            .skip_while(move |event| event.sequence <= after))
+    }
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_line(sink: &mut Box<dyn Write + Send>, line: &str) -> std::io::Result<()> {
+    sink.write_all(line.as_bytes())?;
+    sink.write_all(b"\n")?;
+    sink.flush()
+}
+
+fn sink_error(error: std::io::Error) -> GraphDbError {
+    GraphDbError::InvalidArgument(format!("Unable to write changes to the sink: {}", error))
+}
+
+/// Milliseconds since January 1, 1970
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
@@ -1,3 +1,5 @@
+use crate::error::GraphDbError;
+
 use super::{
     property::{Property, PropertyValue},
     vertex::Vertex,
@@ -12,21 +14,23 @@ pub struct Edge {
 }
 
 impl Edge {
+    /// Changes this copy of the edge only. Graph::update_edge changes it in the graph, moving it
+    /// between its vertices' adjacency lists
     pub fn update(
         &mut self,
         from_vertex_id: usize,
         to_vertex_id: usize,
         properties: Option<Vec<Property>>,
-    ) -> Result<DataResult, String> {
+    ) -> Result<DataResult, GraphDbError> {
         self.from_vertex_id = from_vertex_id;
         self.to_vertex_id = to_vertex_id;
 
         match properties {
             Some(properties) => match self.edge_vertex.update(properties) {
-                Ok(_) => Ok(DataResult::EdgeRef(self)),
+                Ok(_) => Ok(DataResult::Edge(self.clone())),
                 Err(msg) => Err(msg),
             },
-            None => Ok(DataResult::EdgeRef(self)),
+            None => Ok(DataResult::Edge(self.clone())),
         }
     }
 
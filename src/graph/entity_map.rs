@@ -1,50 +1,116 @@
-use std::collections::HashMap;
+use std::mem::size_of;
 
+/// Stores entities against ids that are allocated in increasing order and never reused,
+/// so a removed entity's id can't later refer to a different entity.
+///
+/// Entities live in a dense slab indexed by id, so lookups are a bounds check and an index
+/// rather than a hash. Removed entities leave an empty slot behind
+#[derive(Clone)]
 pub struct EntityMap<T> {
-    entities: HashMap<usize, T>,
+    entities: Vec<Option<T>>,
+    first_index: usize, // id of the entity in the first slot. Moves forward when cleared
+    len: usize,
 }
 
 impl<T> EntityMap<T> {
     pub fn new() -> EntityMap<T> {
-        let entities = HashMap::new();
-        EntityMap { entities }
+        EntityMap {
+            entities: Vec::new(),
+            first_index: 0,
+            len: 0,
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.entities.len()
+        self.len
     }
 
     pub fn push(&mut self, entity: T) -> usize {
-        let mut index = 0;
-        if let Some(i) = self.entities.keys().max() {
-            index = *i + 1;
-        }
+        let index = self.first_index + self.entities.len();
+        self.entities.push(Some(entity));
+        self.len += 1;
 
-        self.entities.insert(index, entity);
         index
     }
 
-    pub fn get_indices(&self) -> Vec<&usize> {
-        self.entities.keys().collect::<Vec<_>>()
+    /// Stores the entity against a given id, e.g. when rebuilding a map or putting back a removed
+    /// entity. Returns false, storing nothing, if the id is taken or from before the last clear
+    pub fn insert(&mut self, index: usize, entity: T) -> bool {
+        let Some(slot) = index.checked_sub(self.first_index) else {
+            return false;
+        };
+        if slot >= self.entities.len() {
+            self.entities.resize_with(slot + 1, || None);
+        }
+        if self.entities[slot].is_some() {
+            return false;
+        }
+
+        self.entities[slot] = Some(entity);
+        self.len += 1;
+        true
+    }
+
+    /// Whether insert would store an entity against the id
+    pub fn can_insert(&self, index: usize) -> bool {
+        index >= self.first_index && self.get(&index).is_none()
+    }
+
+    pub fn get_indices(&self) -> Vec<usize> {
+        self.iter().map(|(index, _)| index).collect()
     }
 
     pub fn get(&self, index: &usize) -> Option<&T> {
-        self.entities.get(index)
+        let slot = index.checked_sub(self.first_index)?;
+        self.entities.get(slot)?.as_ref()
     }
 
     pub fn get_mut(&mut self, index: &usize) -> Option<&mut T> {
-        self.entities.get_mut(index)
+        let slot = index.checked_sub(self.first_index)?;
+        self.entities.get_mut(slot)?.as_mut()
     }
 
     pub fn remove(&mut self, index: &usize) -> Option<T> {
-        self.entities.remove(index)
+        let slot = index.checked_sub(self.first_index)?;
+        let entity = self.entities.get_mut(slot)?.take()?;
+        self.len -= 1;
+
+        Some(entity)
+    }
+
+    /// Removes every entity. Ids handed out before the clear are still not reused
+    pub fn clear(&mut self) {
+        self.first_index += self.entities.len();
+        self.entities = Vec::new();
+        self.len = 0;
+    }
+
+    /// Removes every entity, and hands out ids from the given one on if it's later than the next
+    /// would have been, e.g. when reading back ids handed out before a graph's file was rewritten
+    pub fn clear_to(&mut self, index: usize) {
+        self.clear();
+        self.first_index = self.first_index.max(index);
+    }
+
+    /// The id the next entity pushed will get
+    pub fn next_index(&self) -> usize {
+        self.first_index + self.entities.len()
+    }
+
+    /// Entities in id order, alongside their ids
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, entity)| Some((self.first_index + slot, entity.as_ref()?)))
     }
 
-    pub fn entities(&self) -> &HashMap<usize, T> {
-        &self.entities
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entities.iter().flatten()
     }
 
-    pub fn entities_mut(&mut self) -> &mut HashMap<usize, T> {
-        &mut self.entities
+    /// Bytes used by the slab itself, not counting the entities' own heap allocations
+    pub fn approximate_size(&self) -> usize {
+        self.entities.capacity() * size_of::<Option<T>>()
     }
 }
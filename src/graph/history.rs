@@ -0,0 +1,231 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    error::{EntityKind, GraphDbError},
+    executor::pattern::Binding,
+};
+
+use super::{
+    changes::{now, Change, ChangeFeed},
+    edge::Edge,
+    property::PropertyValue,
+    record::{EdgeRecord, VertexRecord},
+    vertex::Vertex,
+    DataResult, InMemoryGraph,
+};
+
+/// Every version of each vertex and edge since versioning was enabled, kept by id. Entities are
+/// kept as they appear outside the graph, so don't depend on its symbols, which clearing resets
+#[derive(Clone, Default)]
+pub(super) struct History {
+    vertices: BTreeMap<usize, Vec<Version<Vertex>>>,
+    edges: BTreeMap<usize, Vec<Version<Edge>>>,
+    latest: i64, // the latest time recorded, which later versions never come before
+}
+
+/// An entity as it was from a time until its next version. None from when it was removed
+#[derive(Clone)]
+struct Version<T> {
+    from: i64, // milliseconds since January 1, 1970
+    entity: Option<T>,
+}
+
+impl History {
+    /// Records the change as made at the given time, returning the time it was recorded at. The
+    /// system clock can be set back, so a time before the latest recorded is taken as the latest,
+    /// keeping each entity's versions in order
+    pub(super) fn record(&mut self, change: &Change, timestamp: i64) -> i64 {
+        let timestamp = timestamp.max(self.latest);
+        self.latest = timestamp;
+
+        match change {
+            Change::VertexAdded { id, after } | Change::VertexUpdated { id, after, .. } => {
+                push_version(&mut self.vertices, *id, timestamp, Some(after.clone()))
+            }
+            Change::VertexRemoved { id, .. } => {
+                push_version(&mut self.vertices, *id, timestamp, None)
+            }
+            Change::EdgeAdded { id, after } | Change::EdgeUpdated { id, after, .. } => {
+                push_version(&mut self.edges, *id, timestamp, Some(after.clone()))
+            }
+            Change::EdgeRemoved { id, .. } => push_version(&mut self.edges, *id, timestamp, None),
+            Change::GraphCleared => {
+                end_versions(&mut self.vertices, timestamp);
+                end_versions(&mut self.edges, timestamp);
+            }
+            Change::GraphLoaded | Change::GraphDropped | Change::GraphRenamed { .. } => (),
+        }
+
+        timestamp
+    }
+}
+
+impl InMemoryGraph {
+    /// Starts keeping history, from the graph as it is now
+    pub(super) fn start_history(&mut self) {
+        let timestamp = now();
+        let mut history = History::default();
+        for (id, vertex) in self.vertices.iter() {
+            let after = vertex.to_vertex(&self.symbols);
+            history.record(&Change::VertexAdded { id, after }, timestamp);
+        }
+        for (id, edge) in self.edges.iter() {
+            let after = edge.to_edge(&self.symbols);
+            history.record(&Change::EdgeAdded { id, after }, timestamp);
+        }
+
+        self.history = Some(history);
+    }
+
+    /// The graph as it was at the given time, rebuilt from its history without any indexes.
+    /// Nothing is known from before versioning was enabled, so the graph was empty then. Nothing
+    /// is cached, so every entity that existed is copied and its names interned afresh each time
+    pub(super) fn snapshot(&self, timestamp: i64) -> Result<InMemoryGraph, GraphDbError> {
+        let history = self.history()?;
+        let mut graph = InMemoryGraph::new(
+            self.name.clone(),
+            self.options.clone(),
+            Arc::new(ChangeFeed::new()),
+        );
+
+        // In id order, so the label index stays sorted and edges are adjacent in the order added
+        for (id, vertex) in versions_at(&history.vertices, timestamp) {
+            let vertex = VertexRecord::from_vertex(vertex.clone(), &mut graph.symbols);
+            graph
+                .vertex_labels
+                .entry(vertex.label)
+                .or_default()
+                .push(id);
+            if let Some(external_id) = &vertex.external_id {
+                graph.external_ids.insert(external_id.clone(), id);
+            }
+            graph.vertices.insert(id, vertex);
+        }
+
+        for (id, edge) in versions_at(&history.edges, timestamp) {
+            let edge = EdgeRecord::from_edge(edge.clone(), &mut graph.symbols);
+            graph.link_edge(id, edge.from_vertex_id, edge.to_vertex_id);
+            graph.edges.insert(id, edge);
+        }
+
+        Ok(graph)
+    }
+
+    /// A row for each version of the vertex, oldest first, with when it was valid from and to
+    pub(super) fn vertex_versions(&self, id: usize) -> Result<DataResult, GraphDbError> {
+        let versions = match self.history()?.vertices.get(&id) {
+            Some(versions) => versions,
+            None => return Err(GraphDbError::not_found(EntityKind::Vertex, id)),
+        };
+
+        let mut rows = Vec::new();
+        for (i, version) in versions.iter().enumerate() {
+            let existed = i > 0 && versions[i - 1].entity.is_some();
+            let (change, vertex) = match &version.entity {
+                Some(vertex) if existed => ("updated", Binding::Vertex(id, vertex.clone())),
+                Some(vertex) => ("added", Binding::Vertex(id, vertex.clone())),
+                None => ("removed", Binding::Null),
+            };
+            let to = match versions.get(i + 1) {
+                Some(next) => Binding::Value(PropertyValue::DateTime(next.from)),
+                None => Binding::Null,
+            };
+
+            rows.push(vec![
+                Binding::Value(PropertyValue::DateTime(version.from)),
+                to,
+                Binding::Value(PropertyValue::String(change.to_string())),
+                vertex,
+            ]);
+        }
+
+        Ok(DataResult::Table(
+            vec![
+                "validFrom".to_string(),
+                "validTo".to_string(),
+                "change".to_string(),
+                "vertex".to_string(),
+            ],
+            rows,
+        ))
+    }
+
+    fn history(&self) -> Result<&History, GraphDbError> {
+        self.history.as_ref().ok_or_else(|| {
+            GraphDbError::InvalidArgument(format!(
+                "Versioning isn't enabled for graph '{}'. Enable it with {}.enableVersioning()",
+                self.name, self.name
+            ))
+        })
+    }
+}
+
+fn push_version<T>(
+    versions: &mut BTreeMap<usize, Vec<Version<T>>>,
+    id: usize,
+    timestamp: i64,
+    entity: Option<T>,
+) {
+    versions.entry(id).or_default().push(Version {
+        from: timestamp,
+        entity,
+    });
+}
+
+/// Marks every entity that exists as removed
+fn end_versions<T>(versions: &mut BTreeMap<usize, Vec<Version<T>>>, timestamp: i64) {
+    for versions in versions.values_mut() {
+        if versions
+            .last()
+            .is_some_and(|version| version.entity.is_some())
+        {
+            versions.push(Version {
+                from: timestamp,
+                entity: None,
+            });
+        }
+    }
+}
+
+/// Each entity that existed at the time, in id order. Versions made in the same millisecond
+/// replace each other, so the last of them is the one seen
+fn versions_at<T>(
+    versions: &BTreeMap<usize, Vec<Version<T>>>,
+    timestamp: i64,
+) -> impl Iterator<Item = (usize, &T)> {
+    versions.iter().filter_map(move |(id, versions)| {
+        let version = versions
+            .iter()
+            .rev()
+            .find(|version| version.from <= timestamp)?;
+        Some((*id, version.entity.as_ref()?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added(id: usize, label: &str) -> Change {
+        Change::VertexAdded {
+            id,
+            after: Vertex::new(label.to_string(), Vec::new()),
+        }
+    }
+
+    fn labels_at(history: &History, timestamp: i64) -> Vec<String> {
+        versions_at(&history.vertices, timestamp)
+            .map(|(_, vertex)| vertex.label.clone())
+            .collect()
+    }
+
+    #[test]
+    fn versions_stay_in_order_when_the_clock_goes_back() {
+        let mut history = History::default();
+        assert_eq!(history.record(&added(0, "person"), 100), 100);
+        assert_eq!(history.record(&added(0, "robot"), 50), 100);
+
+        assert!(labels_at(&history, 99).is_empty());
+        assert_eq!(labels_at(&history, 100), ["robot"]);
+    }
+}
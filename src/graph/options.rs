@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use crate::error::GraphDbError;
+
+use super::{vector::Metric, GraphType};
+
+/// Which edges a graph allows between the same two vertices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgePolicy {
+    Multigraph,     // any number, including self loops
+    Simple,         // at most one, and no self loops
+    UniquePerLabel, // at most one of each label
+}
+
+impl EdgePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgePolicy::Multigraph => "multigraph",
+            EdgePolicy::Simple => "simple",
+            EdgePolicy::UniquePerLabel => "uniquePerLabel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == EdgePolicy::Multigraph.as_str() => Ok(EdgePolicy::Multigraph),
+            _ if name == EdgePolicy::Simple.as_str() => Ok(EdgePolicy::Simple),
+            _ if name == EdgePolicy::UniquePerLabel.as_str() => Ok(EdgePolicy::UniquePerLabel),
+            other => Err(GraphDbError::parse(
+                "one of multigraph, simple or uniquePerLabel",
+                other,
+            )),
+        }
+    }
+}
+
+/// Whether a graph holds its properties to the types they were first stored with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaMode {
+    Flexible, // a property can hold a value of any type
+    Strict, // a property always holds values of the type it was first given, on any vertex or edge
+}
+
+impl SchemaMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaMode::Flexible => "flexible",
+            SchemaMode::Strict => "strict",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == SchemaMode::Flexible.as_str() => Ok(SchemaMode::Flexible),
+            _ if name == SchemaMode::Strict.as_str() => Ok(SchemaMode::Strict),
+            other => Err(GraphDbError::parse("flexible or strict", other)),
+        }
+    }
+}
+
+/// The settings that can be given with createGraph(name).option(<key>, <value>)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKey {
+    Path,
+    Directed,
+    EdgePolicy,
+    Schema,
+    TextIndex,
+    SpatialIndex,
+    VectorIndex,
+}
+
+impl OptionKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OptionKey::Path => "path",
+            OptionKey::Directed => "directed",
+            OptionKey::EdgePolicy => "edgePolicy",
+            OptionKey::Schema => "schema",
+            OptionKey::TextIndex => "textIndex",
+            OptionKey::SpatialIndex => "spatialIndex",
+            OptionKey::VectorIndex => "vectorIndex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == OptionKey::Path.as_str() => Ok(OptionKey::Path),
+            _ if name == OptionKey::Directed.as_str() => Ok(OptionKey::Directed),
+            _ if name == OptionKey::EdgePolicy.as_str() => Ok(OptionKey::EdgePolicy),
+            _ if name == OptionKey::Schema.as_str() => Ok(OptionKey::Schema),
+            _ if name == OptionKey::TextIndex.as_str() => Ok(OptionKey::TextIndex),
+            _ if name == OptionKey::SpatialIndex.as_str() => Ok(OptionKey::SpatialIndex),
+            _ if name == OptionKey::VectorIndex.as_str() => Ok(OptionKey::VectorIndex),
+            other => Err(GraphDbError::parse(
+                "one of path, directed, edgePolicy, schema, textIndex, spatialIndex or vectorIndex",
+                other,
+            )),
+        }
+    }
+}
+
+/// How a graph is set up. Chosen when the graph is created, and kept for its lifetime
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphOptions {
+    pub graph_type: GraphType,
+    pub path: Option<PathBuf>, // where a persistent graph keeps its vertices and edges
+    pub edge_policy: EdgePolicy,
+    pub directed: bool, // undirected graphs follow edges either way, whichever way they were added
+    pub schema: SchemaMode,
+    pub text_indexes: Vec<String>, // property names, indexed from the start
+    pub spatial_indexes: Vec<String>,
+    pub vector_indexes: Vec<(String, Metric)>,
+    pub given: Vec<OptionKey>, // those set explicitly, even if to their default
+}
+
+impl GraphOptions {
+    /// Fails if the options contradict each other
+    pub fn validate(&self) -> Result<(), GraphDbError> {
+        match (self.graph_type, &self.path) {
+            (GraphType::Persistent, None) => Err(GraphDbError::InvalidArgument(format!(
+                "A persistent graph needs a file to keep its data in, e.g. option({}, graph.log)",
+                OptionKey::Path.as_str()
+            ))),
+            (GraphType::InMemory, Some(path)) => Err(GraphDbError::InvalidArgument(format!(
+                "Only persistent graphs are kept in a file, so {} needs type({})",
+                path.display(),
+                GraphType::Persistent.as_str()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the option was chosen rather than left at its default. Any value other than the
+    /// default counts as chosen, so options built in code needn't list those they set in given
+    pub fn is_given(&self, key: OptionKey) -> bool {
+        let default = GraphOptions::default();
+        self.given.contains(&key)
+            || match key {
+                OptionKey::Directed => self.directed != default.directed,
+                OptionKey::EdgePolicy => self.edge_policy != default.edge_policy,
+                OptionKey::Schema => self.schema != default.schema,
+                _ => false,
+            }
+    }
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        GraphOptions {
+            graph_type: GraphType::InMemory,
+            path: None,
+            edge_policy: EdgePolicy::Multigraph,
+            directed: true,
+            schema: SchemaMode::Flexible,
+            text_indexes: Vec::new(),
+            spatial_indexes: Vec::new(),
+            vector_indexes: Vec::new(),
+            given: Vec::new(),
+        }
+    }
+}
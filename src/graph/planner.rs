@@ -0,0 +1,278 @@
+use std::time::Duration;
+
+use crate::executor::{pattern::Binding, TextPredicate, VertexFilterCommandType};
+
+use super::{property::PropertyValue, vector::Metric, DataResult};
+
+/// Without statistics on values, filters are assumed to keep these fractions of their vertices
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+const MATCH_SELECTIVITY: f64 = 0.25; // partial text, regex and geo matches
+const EXISTENCE_SELECTIVITY: f64 = 0.5;
+
+/// What the planner needs to know about a graph
+pub trait Statistics {
+    /// Number of vertices with the given label, or of every vertex
+    fn vertices(&self, label: Option<&str>) -> usize;
+
+    fn edges(&self) -> usize;
+
+    fn has_spatial_index(&self, name: &str) -> bool;
+
+    fn has_vector_index(&self, name: &str, metric: Metric) -> bool;
+}
+
+/// Where a V() query's first vertices come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Scan,
+    Index(usize), // the filter answered by an index
+}
+
+/// The order a V() query's filters run in, once the first vertices have been found
+#[derive(Debug)]
+pub struct QueryPlan {
+    pub access: Access,
+    pub steps: Vec<usize>,   // indices into the query's filters
+    pub estimates: Vec<f64>, // rows expected after the access, then after each step
+}
+
+/// Filters between traversals keep or drop each vertex on its own, so can run in any order. The
+/// most selective, cheapest ones run first, and the most selective indexed one finds the first
+/// vertices rather than a scan
+pub fn plan(filters: &[VertexFilterCommandType], statistics: &dyn Statistics) -> QueryPlan {
+    let total = statistics.vertices(None) as f64;
+
+    let mut steps = Vec::new();
+    let mut run: Vec<usize> = Vec::new();
+    for (i, filter) in filters.iter().enumerate() {
+        match is_reorderable(filter) {
+            true => run.push(i),
+            false => {
+                steps.extend(ordered(&mut run, filters, statistics, total));
+                steps.push(i);
+            }
+        }
+    }
+    steps.extend(ordered(&mut run, filters, statistics, total));
+
+    let access = choose_access(filters, &steps, statistics, total);
+    if let Access::Index(index) = access {
+        // Index lookups answer their filter exactly, so it needn't be checked again
+        steps.retain(|step| *step != index);
+    }
+
+    let mut rows = match access {
+        Access::Scan => total,
+        Access::Index(index) => access_estimate(&filters[index], statistics, total),
+    };
+    let mut estimates = vec![rows];
+    for step in &steps {
+        rows = estimate(&filters[*step], rows, statistics, total);
+        estimates.push(rows);
+    }
+
+    QueryPlan {
+        access,
+        steps,
+        estimates,
+    }
+}
+
+/// The plan as a table, one row per step. Profiles add each step's actual rows and time, for the
+/// steps that ran
+pub fn describe(
+    filters: &[VertexFilterCommandType],
+    plan: &QueryPlan,
+    profile: Option<&[(usize, Duration)]>,
+) -> DataResult {
+    let mut columns = vec!["step", "operation", "estimated rows"];
+    if profile.is_some() {
+        columns.extend(["rows", "ms"]);
+    }
+
+    let access = match plan.access {
+        Access::Scan => "scan every vertex".to_string(),
+        Access::Index(index) => format!("{} using {}", filters[index], index_name(&filters[index])),
+    };
+    let operations =
+        std::iter::once(access).chain(plan.steps.iter().map(|step| filters[*step].to_string()));
+
+    let rows = operations
+        .zip(&plan.estimates)
+        .enumerate()
+        .map(|(i, (operation, estimate))| {
+            let mut row = vec![
+                Binding::Value(PropertyValue::Int64(i as i64)),
+                Binding::Value(PropertyValue::String(operation)),
+                Binding::Value(PropertyValue::Int64(estimate.round() as i64)),
+            ];
+            if let Some(profile) = profile {
+                match profile.get(i) {
+                    Some((rows, elapsed)) => row.extend([
+                        Binding::Value(PropertyValue::Int64(*rows as i64)),
+                        Binding::Value(PropertyValue::Float64(
+                            (elapsed.as_secs_f64() * 1_000_000.0).round() / 1000.0,
+                        )),
+                    ]),
+                    None => row.extend([Binding::Null, Binding::Null]),
+                }
+            }
+            row
+        })
+        .collect();
+
+    DataResult::Table(columns.into_iter().map(String::from).collect(), rows)
+}
+
+/// Traversals, walks, rankings and values depend on the vertices before them, so stay where they are
+fn is_reorderable(filter: &VertexFilterCommandType) -> bool {
+    !matches!(
+        filter,
+        VertexFilterCommandType::Out(_)
+            | VertexFilterCommandType::In(_)
+            | VertexFilterCommandType::Nearest(..)
+            | VertexFilterCommandType::HasPropertyNearest(..)
+            | VertexFilterCommandType::Values(_)
+            | VertexFilterCommandType::Repeat(_)
+            | VertexFilterCommandType::Edges(_)
+    )
+}
+
+/// Drains the run, most selective first and cheapest first between equals
+fn ordered(
+    run: &mut Vec<usize>,
+    filters: &[VertexFilterCommandType],
+    statistics: &dyn Statistics,
+    total: f64,
+) -> Vec<usize> {
+    let key = |i: &usize| {
+        let filter = &filters[*i];
+        (selectivity(filter, statistics, total), cost(filter))
+    };
+
+    let mut ordered: Vec<usize> = std::mem::take(run);
+    ordered.sort_by(|a, b| {
+        key(a)
+            .partial_cmp(&key(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    ordered
+}
+
+/// A ranking straight after V() uses its index, as does the most selective indexed filter
+/// before the first traversal
+fn choose_access(
+    filters: &[VertexFilterCommandType],
+    steps: &[usize],
+    statistics: &dyn Statistics,
+    total: f64,
+) -> Access {
+    match filters.first() {
+        Some(VertexFilterCommandType::Nearest(name, _, _, metric))
+            if statistics.has_vector_index(name, *metric) =>
+        {
+            return Access::Index(0)
+        }
+        Some(VertexFilterCommandType::HasPropertyNearest(name, ..))
+            if statistics.has_spatial_index(name) =>
+        {
+            return Access::Index(0)
+        }
+        _ => (),
+    }
+
+    steps
+        .iter()
+        .take_while(|step| is_reorderable(&filters[**step]))
+        .filter(|step| is_indexed(&filters[**step], statistics))
+        .map(|step| (*step, access_estimate(&filters[*step], statistics, total)))
+        .filter(|(_, estimate)| *estimate < total)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map_or(Access::Scan, |(step, _)| Access::Index(step))
+}
+
+fn is_indexed(filter: &VertexFilterCommandType, statistics: &dyn Statistics) -> bool {
+    match filter {
+        VertexFilterCommandType::HasId(_)
+        | VertexFilterCommandType::HasName(_)
+        | VertexFilterCommandType::HasExternalId(_) => true,
+        VertexFilterCommandType::HasPropertyWithinRadius(name, ..)
+        | VertexFilterCommandType::HasPropertyWithinBox(name, _) => {
+            statistics.has_spatial_index(name)
+        }
+        _ => false,
+    }
+}
+
+fn index_name(filter: &VertexFilterCommandType) -> &'static str {
+    match filter {
+        VertexFilterCommandType::HasId(_) => "vertex id",
+        VertexFilterCommandType::HasName(_) => "label index",
+        VertexFilterCommandType::HasExternalId(_) => "external id index",
+        VertexFilterCommandType::Nearest(..) => "vector index",
+        _ => "spatial index",
+    }
+}
+
+fn access_estimate(
+    filter: &VertexFilterCommandType,
+    statistics: &dyn Statistics,
+    total: f64,
+) -> f64 {
+    match filter {
+        VertexFilterCommandType::Nearest(_, _, k, _)
+        | VertexFilterCommandType::HasPropertyNearest(_, _, k) => (*k as f64).min(total),
+        _ => total * selectivity(filter, statistics, total),
+    }
+}
+
+/// The fraction of vertices a filter keeps
+fn selectivity(filter: &VertexFilterCommandType, statistics: &dyn Statistics, total: f64) -> f64 {
+    match filter {
+        VertexFilterCommandType::HasId(_) | VertexFilterCommandType::HasExternalId(_) => {
+            1.0 / total.max(1.0)
+        }
+        VertexFilterCommandType::HasName(label) => {
+            statistics.vertices(Some(label)) as f64 / total.max(1.0)
+        }
+        VertexFilterCommandType::HasPropertyValue(..) => EQUALITY_SELECTIVITY,
+        VertexFilterCommandType::HasPropertyText(_, TextPredicate::Equals(_), _) => {
+            EQUALITY_SELECTIVITY
+        }
+        VertexFilterCommandType::HasProperty(_) => EXISTENCE_SELECTIVITY,
+        _ => MATCH_SELECTIVITY,
+    }
+}
+
+/// Relative work per vertex
+fn cost(filter: &VertexFilterCommandType) -> u8 {
+    match filter {
+        VertexFilterCommandType::HasPropertyRegex(..) => 3,
+        VertexFilterCommandType::HasPropertyLike(..)
+        | VertexFilterCommandType::HasPropertyText(..)
+        | VertexFilterCommandType::HasPropertyWithinRadius(..)
+        | VertexFilterCommandType::HasPropertyWithinBox(..) => 2,
+        _ => 1,
+    }
+}
+
+fn estimate(
+    filter: &VertexFilterCommandType,
+    rows: f64,
+    statistics: &dyn Statistics,
+    total: f64,
+) -> f64 {
+    match filter {
+        VertexFilterCommandType::Out(_)
+        | VertexFilterCommandType::In(_)
+        | VertexFilterCommandType::Repeat(_)
+        | VertexFilterCommandType::Edges(_) => rows * statistics.edges() as f64 / total.max(1.0),
+        VertexFilterCommandType::Nearest(_, _, k, _)
+        | VertexFilterCommandType::HasPropertyNearest(_, _, k) => rows.min(*k as f64),
+        VertexFilterCommandType::Values(_) => rows,
+        VertexFilterCommandType::HasId(_) | VertexFilterCommandType::HasExternalId(_) => {
+            rows.min(1.0)
+        }
+        _ => rows * selectivity(filter, statistics, total),
+    }
+}
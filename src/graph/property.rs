@@ -1,3 +1,15 @@
+use std::{fmt, str::FromStr};
+
+use json::JsonValue as Json;
+use regex::Regex;
+
+use crate::{
+    executor::TextPredicate,
+    parser::{parse_vector, ValidTypes},
+};
+
+use super::spatial::{BoundingBox, Point};
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum PropertyValue {
     Boolean(bool),
@@ -7,6 +19,8 @@ pub enum PropertyValue {
     Float64(f64),
     String(String),
     DateTime(i64), // milliseconds since January 1, 1970
+    Point(Point),
+    Vector(Vec<f32>),
 }
 
 #[derive(Debug, Clone)]
@@ -15,3 +29,197 @@ pub struct Property {
     pub value: PropertyValue,
     pub flagged_for_removal: bool,
 }
+
+impl PropertyValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::Boolean(_) => ValidTypes::Boolean.as_str(),
+            PropertyValue::Int32(_) => ValidTypes::Int32.as_str(),
+            PropertyValue::Int64(_) => ValidTypes::Int64.as_str(),
+            PropertyValue::Float32(_) => ValidTypes::Float32.as_str(),
+            PropertyValue::Float64(_) => ValidTypes::Float64.as_str(),
+            PropertyValue::String(_) => ValidTypes::String.as_str(),
+            PropertyValue::DateTime(_) => ValidTypes::DateTime.as_str(),
+            PropertyValue::Point(_) => ValidTypes::Point.as_str(),
+            PropertyValue::Vector(_) => ValidTypes::Vector.as_str(),
+        }
+    }
+
+    /// Whether the value equals the given text, once parsed as the value's type
+    pub fn matches_str(&self, value: &str) -> bool {
+        match self {
+            PropertyValue::Boolean(val) => compare_to_string(*val, value),
+            PropertyValue::Int32(val) => compare_to_string(*val, value),
+            PropertyValue::Int64(val) => compare_to_string(*val, value),
+            PropertyValue::Float32(val) => compare_to_string(*val, value),
+            PropertyValue::Float64(val) => compare_to_string(*val, value),
+            PropertyValue::String(val) => val == value.trim(),
+
+            // TODO: This isn't that practical unless searching for a specific epoch
+            PropertyValue::DateTime(val) => compare_to_string(*val, value),
+            PropertyValue::Point(val) => compare_to_string(*val, value),
+            PropertyValue::Vector(val) => parse_vector(value).is_ok_and(|vector| &vector == val),
+        }
+    }
+
+    /// Only works for string values
+    pub fn contains_str(&self, search_term: &str) -> bool {
+        match self {
+            PropertyValue::String(val) => val.contains(search_term.trim()),
+            _ => false,
+        }
+    }
+
+    /// Only works for string values
+    pub fn matches_text(&self, predicate: &TextPredicate, ignore_case: bool) -> bool {
+        let val = match self {
+            PropertyValue::String(val) if ignore_case => val.to_lowercase(),
+            PropertyValue::String(val) => val.to_owned(),
+            _ => return false,
+        };
+
+        match predicate {
+            TextPredicate::Equals(term) => &val == term,
+            TextPredicate::Contains(term) => val.contains(term.as_str()),
+            TextPredicate::StartsWith(term) => val.starts_with(term.as_str()),
+            TextPredicate::EndsWith(term) => val.ends_with(term.as_str()),
+        }
+    }
+
+    /// Only works for string values
+    pub fn matches_regex(&self, regex: &Regex) -> bool {
+        match self {
+            PropertyValue::String(val) => regex.is_match(val),
+            _ => false,
+        }
+    }
+
+    /// Only works for point values
+    pub fn within_radius(&self, center: &Point, km: f64) -> bool {
+        match self {
+            PropertyValue::Point(val) => val.distance_km(center) <= km,
+            _ => false,
+        }
+    }
+
+    /// Only works for point values
+    pub fn within_box(&self, bounding_box: &BoundingBox) -> bool {
+        match self {
+            PropertyValue::Point(val) => bounding_box.contains(val),
+            _ => false,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        match self {
+            PropertyValue::Boolean(val) => Json::from(*val),
+            PropertyValue::Int32(val) => Json::from(*val),
+            PropertyValue::Int64(val) => Json::from(*val),
+            PropertyValue::Float32(val) => Json::from(*val),
+            PropertyValue::Float64(val) => Json::from(*val),
+            PropertyValue::String(val) => Json::from(val.as_str()),
+            PropertyValue::DateTime(val) => Json::from(*val),
+            PropertyValue::Point(val) => json::object! {
+                latitude: val.latitude,
+                longitude: val.longitude,
+            },
+            PropertyValue::Vector(val) => Json::from(val.clone()),
+        }
+    }
+
+    /// Heap bytes owned by the value, excluding the value itself
+    pub fn heap_size(&self) -> usize {
+        match self {
+            PropertyValue::String(val) => val.capacity(),
+            PropertyValue::Vector(val) => val.capacity() * std::mem::size_of::<f32>(),
+            _ => 0,
+        }
+    }
+}
+
+impl Property {
+    /// Heap bytes owned by the property, excluding the property itself
+    pub fn heap_size(&self) -> usize {
+        self.name.capacity() + self.value.heap_size()
+    }
+}
+
+fn compare_to_string<T>(number: T, string: &str) -> bool
+where
+    T: FromStr + PartialEq,
+{
+    match string.parse::<T>() {
+        Ok(parsed_val) => parsed_val == number,
+        Err(_) => false,
+    }
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Boolean(val) => write!(f, "{}", val),
+            PropertyValue::Int32(val) => write!(f, "{}", val),
+            PropertyValue::Int64(val) => write!(f, "{}", val),
+            PropertyValue::Float32(val) => write!(f, "{}", val),
+            PropertyValue::Float64(val) => write!(f, "{}", val),
+            PropertyValue::String(val) => write!(f, "{}", val),
+            PropertyValue::DateTime(val) => write!(f, "{}", val),
+            PropertyValue::Point(val) => write!(f, "{}", val),
+            PropertyValue::Vector(val) => write!(f, "{:?}", val),
+        }
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::Boolean(value)
+    }
+}
+
+impl From<i32> for PropertyValue {
+    fn from(value: i32) -> Self {
+        PropertyValue::Int32(value)
+    }
+}
+
+impl From<i64> for PropertyValue {
+    fn from(value: i64) -> Self {
+        PropertyValue::Int64(value)
+    }
+}
+
+impl From<f32> for PropertyValue {
+    fn from(value: f32) -> Self {
+        PropertyValue::Float32(value)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        PropertyValue::Float64(value)
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        PropertyValue::String(value)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::String(value.to_string())
+    }
+}
+
+impl From<Point> for PropertyValue {
+    fn from(value: Point) -> Self {
+        PropertyValue::Point(value)
+    }
+}
+
+impl From<Vec<f32>> for PropertyValue {
+    fn from(value: Vec<f32>) -> Self {
+        PropertyValue::Vector(value)
+    }
+}
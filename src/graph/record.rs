@@ -0,0 +1,256 @@
+use std::mem::{size_of, size_of_val};
+
+use regex::Regex;
+
+use crate::executor::TextPredicate;
+
+use super::{
+    edge::Edge,
+    property::{Property, PropertyValue},
+    spatial::{BoundingBox, Point},
+    symbols::{Symbol, SymbolTable},
+    vertex::Vertex,
+};
+
+/// How a property is stored in a graph: its key is a symbol rather than a string
+#[derive(Debug, Clone)]
+pub struct PropertyRecord {
+    pub key: Symbol,
+    pub value: PropertyValue,
+}
+
+/// How a vertex is stored in a graph. Converted to and from `Vertex` at the graph's boundary
+#[derive(Debug, Clone)]
+pub struct VertexRecord {
+    pub label: Symbol,
+    pub external_id: Option<String>,
+    pub properties: Vec<PropertyRecord>,
+}
+
+/// How an edge is stored in a graph. Converted to and from `Edge` at the graph's boundary
+#[derive(Debug, Clone)]
+pub struct EdgeRecord {
+    pub from_vertex_id: usize,
+    pub to_vertex_id: usize,
+    pub label: Symbol,
+    pub properties: Vec<PropertyRecord>,
+}
+
+impl PropertyRecord {
+    fn to_property(&self, symbols: &SymbolTable) -> Property {
+        Property {
+            name: symbols.resolve(self.key).to_string(),
+            value: self.value.clone(),
+            flagged_for_removal: false,
+        }
+    }
+}
+
+impl VertexRecord {
+    pub fn from_vertex(vertex: Vertex, symbols: &mut SymbolTable) -> Self {
+        VertexRecord {
+            label: symbols.intern(&vertex.label),
+            external_id: vertex.external_id,
+            properties: to_property_records(vertex.properties, symbols),
+        }
+    }
+
+    pub fn to_vertex(&self, symbols: &SymbolTable) -> Vertex {
+        Vertex {
+            label: symbols.resolve(self.label).to_string(),
+            external_id: self.external_id.clone(),
+            properties: to_properties(&self.properties, symbols),
+        }
+    }
+
+    /// Sets and removes properties. A property that is both set and removed ends up removed
+    pub fn update(&mut self, properties: Vec<Property>, symbols: &mut SymbolTable) {
+        update_property_records(&mut self.properties, properties, symbols);
+    }
+
+    /// A copy holding only the properties whose values differ from, or are missing in, the other
+    pub fn without_properties_of(&self, other: &VertexRecord) -> VertexRecord {
+        VertexRecord {
+            label: self.label,
+            external_id: self.external_id.clone(),
+            properties: self
+                .properties
+                .iter()
+                .filter(|property| other.get_property_value(property.key) != Some(&property.value))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn has_property(&self, key: Symbol) -> bool {
+        self.properties.iter().any(|property| property.key == key)
+    }
+
+    pub fn has_property_value(&self, key: Symbol, value: &str) -> bool {
+        self.properties
+            .iter()
+            .any(|property| property.key == key && property.value.matches_str(value))
+    }
+
+    /// Only works for string properties
+    pub fn has_property_like(&self, key: Symbol, search_term: &str) -> bool {
+        self.properties
+            .iter()
+            .any(|property| property.key == key && property.value.contains_str(search_term))
+    }
+
+    pub fn has_property_text(
+        &self,
+        key: Symbol,
+        predicate: &TextPredicate,
+        ignore_case: bool,
+    ) -> bool {
+        self.properties.iter().any(|property| {
+            property.key == key && property.value.matches_text(predicate, ignore_case)
+        })
+    }
+
+    pub fn has_property_regex(&self, key: Symbol, regex: &Regex) -> bool {
+        self.properties
+            .iter()
+            .any(|property| property.key == key && property.value.matches_regex(regex))
+    }
+
+    pub fn has_property_within_radius(&self, key: Symbol, center: &Point, km: f64) -> bool {
+        self.properties
+            .iter()
+            .any(|property| property.key == key && property.value.within_radius(center, km))
+    }
+
+    pub fn has_property_within_box(&self, key: Symbol, bounding_box: &BoundingBox) -> bool {
+        self.properties
+            .iter()
+            .any(|property| property.key == key && property.value.within_box(bounding_box))
+    }
+
+    pub fn get_point(&self, key: Symbol) -> Option<&Point> {
+        match self.get_property_value(key) {
+            Some(PropertyValue::Point(point)) => Some(point),
+            _ => None,
+        }
+    }
+
+    pub fn get_property_value(&self, key: Symbol) -> Option<&PropertyValue> {
+        self.properties
+            .iter()
+            .find(|property| property.key == key)
+            .map(|property| &property.value)
+    }
+
+    /// Approximate bytes used by the vertex, including its heap allocations
+    pub fn approximate_size(&self) -> usize {
+        size_of::<VertexRecord>()
+            + self.external_id.as_ref().map_or(0, |id| id.capacity())
+            + properties_size(&self.properties)
+    }
+}
+
+impl EdgeRecord {
+    pub fn from_edge(edge: Edge, symbols: &mut SymbolTable) -> Self {
+        EdgeRecord {
+            from_vertex_id: edge.from_vertex_id,
+            to_vertex_id: edge.to_vertex_id,
+            label: symbols.intern(&edge.edge_vertex.label),
+            properties: to_property_records(edge.edge_vertex.properties, symbols),
+        }
+    }
+
+    pub fn to_edge(&self, symbols: &SymbolTable) -> Edge {
+        Edge {
+            from_vertex_id: self.from_vertex_id,
+            to_vertex_id: self.to_vertex_id,
+            edge_vertex: Vertex {
+                label: symbols.resolve(self.label).to_string(),
+                external_id: None,
+                properties: to_properties(&self.properties, symbols),
+            },
+        }
+    }
+
+    /// Moves the edge to the given vertices, and sets and removes its properties as a vertex's are
+    pub fn update(
+        &mut self,
+        from_vertex_id: usize,
+        to_vertex_id: usize,
+        properties: Vec<Property>,
+        symbols: &mut SymbolTable,
+    ) {
+        self.from_vertex_id = from_vertex_id;
+        self.to_vertex_id = to_vertex_id;
+        update_property_records(&mut self.properties, properties, symbols);
+    }
+
+    pub fn get_property_value(&self, key: Symbol) -> Option<&PropertyValue> {
+        self.properties
+            .iter()
+            .find(|property| property.key == key)
+            .map(|property| &property.value)
+    }
+
+    /// Approximate bytes used by the edge, including its heap allocations
+    pub fn approximate_size(&self) -> usize {
+        size_of::<EdgeRecord>() + properties_size(&self.properties)
+    }
+}
+
+fn update_property_records(
+    records: &mut Vec<PropertyRecord>,
+    properties: Vec<Property>,
+    symbols: &mut SymbolTable,
+) {
+    let (removed, set): (Vec<_>, Vec<_>) = properties
+        .into_iter()
+        .partition(|property| property.flagged_for_removal);
+
+    for property in set {
+        let key = symbols.intern(&property.name);
+        match records.iter_mut().find(|existing| existing.key == key) {
+            Some(existing) => existing.value = property.value,
+            None => records.push(PropertyRecord {
+                key,
+                value: property.value,
+            }),
+        }
+    }
+
+    for property in removed {
+        if let Some(key) = symbols.get(&property.name) {
+            records.retain(|existing| existing.key != key);
+        }
+    }
+}
+
+fn to_property_records(
+    properties: Vec<Property>,
+    symbols: &mut SymbolTable,
+) -> Vec<PropertyRecord> {
+    properties
+        .into_iter()
+        .filter(|property| !property.flagged_for_removal)
+        .map(|property| PropertyRecord {
+            key: symbols.intern(&property.name),
+            value: property.value,
+        })
+        .collect()
+}
+
+fn to_properties(properties: &[PropertyRecord], symbols: &SymbolTable) -> Vec<Property> {
+    properties
+        .iter()
+        .map(|property| property.to_property(symbols))
+        .collect()
+}
+
+fn properties_size(properties: &[PropertyRecord]) -> usize {
+    let heap_size: usize = properties
+        .iter()
+        .map(|property| property.value.heap_size())
+        .sum();
+
+    size_of_val(properties) + heap_size
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::GraphDbError,
+    executor::{PathFilter, Repeat, VertexFilterCommandType},
+};
+
+use super::InMemoryGraph;
+
+/// A walk in progress. Its path, from where it started, is only kept while the paths filter
+/// needs it: a walk that has been round a cycle goes on the same way however it got there
+struct Walk {
+    vertex: usize,
+    path: Vec<usize>,
+    cyclic: bool,
+}
+
+impl InMemoryGraph {
+    /// Walks the repeat's steps from each vertex. Walks without a path are only walked on once
+    /// from each vertex, each time round when limited by times, or at all otherwise. So cycles,
+    /// including self-loops, end the walk rather than repeating it forever
+    pub(super) fn repeat(
+        &self,
+        repeat: &Repeat,
+        vertex_ids: Vec<usize>,
+    ) -> Result<Vec<usize>, GraphDbError> {
+        let tracks_paths = repeat.paths != PathFilter::Any;
+        let mut walks: Vec<Walk> = vertex_ids
+            .into_iter()
+            .map(|vertex| Walk {
+                vertex,
+                path: match tracks_paths {
+                    true => vec![vertex],
+                    false => Vec::new(),
+                },
+                cyclic: false,
+            })
+            .collect();
+
+        let mut kept = Vec::new();
+        let mut seen = HashSet::new();
+        let mut keep = |vertex: usize| {
+            if seen.insert(vertex) {
+                kept.push(vertex);
+            }
+        };
+
+        let mut walked_from = HashSet::new();
+        let mut rounds = 0;
+        while !walks.is_empty() && repeat.times.is_none_or(|times| rounds < times) {
+            rounds += 1;
+            if repeat.times.is_some() {
+                walked_from.clear();
+            }
+
+            let mut next = Vec::new();
+            for walk in walks {
+                let steps = self.run_steps(&repeat.steps, vec![walk.vertex])?;
+                // Without a stopping point, walks end where they can go no further
+                if steps.is_empty() && repeat.times.is_none() && repeat.until.is_none() {
+                    if rounds > 1 && keeps(repeat.paths, walk.cyclic) {
+                        keep(walk.vertex);
+                    }
+                    continue;
+                }
+
+                let mut stepped_to = HashSet::new();
+                for vertex in steps {
+                    // A vertex reached along several edges continues one walk
+                    if !stepped_to.insert(vertex) {
+                        continue;
+                    }
+
+                    let cyclic = walk.cyclic || walk.path.contains(&vertex);
+                    if cyclic && repeat.paths == PathFilter::Simple {
+                        continue;
+                    }
+                    let path = match tracks_paths && !cyclic {
+                        true => walk.path.iter().copied().chain([vertex]).collect(),
+                        false => Vec::new(),
+                    };
+                    next.push(Walk {
+                        vertex,
+                        path,
+                        cyclic,
+                    });
+                }
+            }
+
+            walks = Vec::new();
+            for walk in next {
+                let done = repeat.times == Some(rounds) || self.ends_walk(repeat, walk.vertex)?;
+                if (done || repeat.emit) && keeps(repeat.paths, walk.cyclic) {
+                    keep(walk.vertex);
+                }
+
+                if done || walk.path.is_empty() && !walked_from.insert((walk.vertex, walk.cyclic)) {
+                    continue;
+                }
+                walks.push(walk);
+            }
+        }
+
+        Ok(kept)
+    }
+
+    fn ends_walk(&self, repeat: &Repeat, vertex: usize) -> Result<bool, GraphDbError> {
+        match &repeat.until {
+            Some(until) => Ok(!self.run_steps(until, vec![vertex])?.is_empty()),
+            None => Ok(false),
+        }
+    }
+
+    /// Runs filters in the order written, with no values selected
+    fn run_steps(
+        &self,
+        steps: &[VertexFilterCommandType],
+        vertex_ids: Vec<usize>,
+    ) -> Result<Vec<usize>, GraphDbError> {
+        let mut values = Vec::new();
+        let mut return_values = false;
+        steps.iter().try_fold(vertex_ids, |vertex_ids, step| {
+            self.apply_filter(step, vertex_ids, &mut values, &mut return_values)
+        })
+    }
+}
+
+fn keeps(paths: PathFilter, cyclic: bool) -> bool {
+    match paths {
+        PathFilter::Cyclic => cyclic,
+        _ => true,
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_2, PI},
+    fmt,
+    mem::size_of,
+    str::FromStr,
+};
+
+use crate::error::GraphDbError;
+
+/// Mean radius of the Earth
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Grid cells of the spatial index are this many degrees across, in both directions
+const CELL_DEGREES: f64 = 1.0;
+
+/// Nearest neighbour searches start from roughly one cell's radius, doubling until enough are found
+const INITIAL_SEARCH_KM: f64 = 100.0;
+
+/// A location on the Earth, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A latitude and longitude range. A box whose minimum longitude is greater than its maximum
+/// crosses the antimeridian
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub min_longitude: f64,
+    pub max_latitude: f64,
+    pub max_longitude: f64,
+}
+
+impl Point {
+    pub fn new(latitude: f64, longitude: f64) -> Result<Self, GraphDbError> {
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(GraphDbError::InvalidArgument(format!(
+                "({}, {}) is not a valid point. Latitude must be within ±90 and longitude ±180",
+                latitude, longitude
+            )));
+        }
+
+        Ok(Point {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Great circle distance, using the haversine formula
+    pub fn distance_km(&self, other: &Point) -> f64 {
+        let (latitude, other_latitude) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let half_latitude = (other_latitude - latitude) / 2.0;
+        let half_longitude = (other.longitude - self.longitude).to_radians() / 2.0;
+
+        let a = half_latitude.sin().powi(2)
+            + latitude.cos() * other_latitude.cos() * half_longitude.sin().powi(2);
+
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+}
+
+/// Parses a latitude and longitude separated by a comma or whitespace, e.g. "51.5, -0.12"
+impl FromStr for Point {
+    type Err = GraphDbError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let coordinates: Vec<&str> = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|coordinate| !coordinate.is_empty())
+            .collect();
+
+        let parsed: Vec<f64> = coordinates
+            .iter()
+            .filter_map(|coordinate| coordinate.parse::<f64>().ok())
+            .collect();
+
+        match parsed[..] {
+            [latitude, longitude] if coordinates.len() == 2 => Point::new(latitude, longitude),
+            _ => Err(GraphDbError::TypeMismatch {
+                expected: "a latitude and longitude".to_string(),
+                found: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.latitude, self.longitude)
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Result<Self, GraphDbError> {
+        if min.latitude > max.latitude {
+            return Err(GraphDbError::InvalidArgument(format!(
+                "The minimum latitude {} is greater than the maximum {}",
+                min.latitude, max.latitude
+            )));
+        }
+
+        Ok(BoundingBox {
+            min_latitude: min.latitude,
+            min_longitude: min.longitude,
+            max_latitude: max.latitude,
+            max_longitude: max.longitude,
+        })
+    }
+
+    /// The smallest box holding every point within the given distance of the center
+    pub fn around(center: &Point, km: f64) -> Self {
+        let angular_distance = km / EARTH_RADIUS_KM;
+        let latitude = center.latitude.to_radians();
+        let min_latitude = latitude - angular_distance;
+        let max_latitude = latitude + angular_distance;
+
+        // Reaching a pole takes in every longitude
+        let whole_longitude = |min_latitude: f64, max_latitude: f64| BoundingBox {
+            min_latitude: min_latitude.max(-FRAC_PI_2).to_degrees(),
+            min_longitude: -180.0,
+            max_latitude: max_latitude.min(FRAC_PI_2).to_degrees(),
+            max_longitude: 180.0,
+        };
+        if min_latitude <= -FRAC_PI_2 || max_latitude >= FRAC_PI_2 || angular_distance >= PI {
+            return whole_longitude(min_latitude, max_latitude);
+        }
+
+        let delta_longitude = (angular_distance.sin() / latitude.cos()).asin();
+        if delta_longitude.is_nan() {
+            return whole_longitude(min_latitude, max_latitude);
+        }
+
+        let longitude = center.longitude.to_radians();
+        let wrap = |longitude: f64| match longitude {
+            _ if longitude < -PI => longitude + 2.0 * PI,
+            _ if longitude > PI => longitude - 2.0 * PI,
+            _ => longitude,
+        };
+
+        BoundingBox {
+            min_latitude: min_latitude.to_degrees(),
+            min_longitude: wrap(longitude - delta_longitude).to_degrees(),
+            max_latitude: max_latitude.to_degrees(),
+            max_longitude: wrap(longitude + delta_longitude).to_degrees(),
+        }
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        let latitude_matches = (self.min_latitude..=self.max_latitude).contains(&point.latitude);
+        let longitude_matches = match self.crosses_antimeridian() {
+            true => point.longitude >= self.min_longitude || point.longitude <= self.max_longitude,
+            false => (self.min_longitude..=self.max_longitude).contains(&point.longitude),
+        };
+
+        latitude_matches && longitude_matches
+    }
+
+    fn crosses_antimeridian(&self) -> bool {
+        self.min_longitude > self.max_longitude
+    }
+}
+
+/// A grid over latitude and longitude, holding the points of one property. Finds points in an
+/// area by visiting only the cells that overlap it
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<(i32, i32), Vec<usize>>, // latitude cell, longitude cell, vertex ids
+    points: HashMap<usize, Point>,          // vertex id, point
+}
+
+fn cell_of(degrees: f64) -> i32 {
+    (degrees / CELL_DEGREES).floor() as i32
+}
+
+fn cell_key(point: &Point) -> (i32, i32) {
+    (cell_of(point.latitude), cell_of(point.longitude))
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        SpatialIndex::default()
+    }
+
+    pub fn insert(&mut self, vertex_id: usize, point: Point) {
+        self.remove(vertex_id);
+
+        self.cells
+            .entry(cell_key(&point))
+            .or_default()
+            .push(vertex_id);
+        self.points.insert(vertex_id, point);
+    }
+
+    pub fn remove(&mut self, vertex_id: usize) {
+        let point = match self.points.remove(&vertex_id) {
+            Some(point) => point,
+            None => return,
+        };
+
+        let key = cell_key(&point);
+        if let Some(ids) = self.cells.get_mut(&key) {
+            ids.retain(|id| *id != vertex_id);
+            if ids.is_empty() {
+                self.cells.remove(&key);
+            }
+        }
+    }
+
+    /// Vertices with points inside the box, in ascending id order
+    pub fn within_box(&self, bounding_box: &BoundingBox) -> Vec<usize> {
+        let latitude_cells =
+            cell_of(bounding_box.min_latitude)..=cell_of(bounding_box.max_latitude);
+        let longitude_cells = match bounding_box.crosses_antimeridian() {
+            true => vec![
+                cell_of(bounding_box.min_longitude)..=cell_of(180.0),
+                cell_of(-180.0)..=cell_of(bounding_box.max_longitude),
+            ],
+            false => {
+                vec![cell_of(bounding_box.min_longitude)..=cell_of(bounding_box.max_longitude)]
+            }
+        };
+
+        let box_cell_count = latitude_cells.clone().count()
+            * longitude_cells
+                .iter()
+                .map(|cells| cells.clone().count())
+                .sum::<usize>();
+
+        // A large box is quicker to check against the occupied cells than cell by cell
+        let cells: Vec<&Vec<usize>> = match box_cell_count > self.cells.len() {
+            true => self
+                .cells
+                .iter()
+                .filter(|((latitude, longitude), _)| {
+                    latitude_cells.contains(latitude)
+                        && longitude_cells
+                            .iter()
+                            .any(|cells| cells.contains(longitude))
+                })
+                .map(|(_, ids)| ids)
+                .collect(),
+            false => latitude_cells
+                .flat_map(|latitude| {
+                    longitude_cells.iter().flat_map(move |cells| {
+                        cells.clone().map(move |longitude| (latitude, longitude))
+                    })
+                })
+                .filter_map(|key| self.cells.get(&key))
+                .collect(),
+        };
+
+        let mut ids: Vec<usize> = cells
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|id| {
+                self.points
+                    .get(id)
+                    .is_some_and(|point| bounding_box.contains(point))
+            })
+            .collect();
+        ids.sort_unstable();
+
+        ids
+    }
+
+    /// Vertices with points within the given distance of the center, in ascending id order
+    pub fn within_radius(&self, center: &Point, km: f64) -> Vec<usize> {
+        let mut ids = self.within_box(&BoundingBox::around(center, km));
+        ids.retain(|id| {
+            self.points
+                .get(id)
+                .is_some_and(|point| point.distance_km(center) <= km)
+        });
+
+        ids
+    }
+
+    /// The k vertices with points nearest the center, nearest first
+    pub fn nearest(&self, center: &Point, k: usize) -> Vec<usize> {
+        if k == 0 || self.points.is_empty() {
+            return Vec::new();
+        }
+
+        // Every point within the radius is found, so once there are k, the nearest k are among them
+        let mut km = INITIAL_SEARCH_KM;
+        loop {
+            let ids = self.within_radius(center, km);
+            if ids.len() >= k || km >= PI * EARTH_RADIUS_KM {
+                let mut distances: Vec<(usize, f64)> = ids
+                    .into_iter()
+                    .filter_map(|id| Some((id, self.points.get(&id)?.distance_km(center))))
+                    .collect();
+
+                return nearest_first(&mut distances, k);
+            }
+
+            km *= 2.0;
+        }
+    }
+
+    /// Approximate bytes used, including heap allocations
+    pub fn approximate_size(&self) -> usize {
+        let cells_size: usize = self
+            .cells
+            .values()
+            .map(|ids| {
+                size_of::<(i32, i32)>()
+                    + size_of::<Vec<usize>>()
+                    + ids.capacity() * size_of::<usize>()
+            })
+            .sum();
+
+        size_of::<SpatialIndex>()
+            + cells_size
+            + self.points.capacity() * (size_of::<usize>() + size_of::<Point>())
+    }
+}
+
+/// Sorts vertex ids by distance, ties broken by id, and keeps the first k
+pub fn nearest_first(distances: &mut [(usize, f64)], k: usize) -> Vec<usize> {
+    distances.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+    distances.iter().take(k).map(|(id, _)| *id).collect()
+}
@@ -0,0 +1,71 @@
+use json::JsonValue as Json;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyStats {
+    pub name: String,
+    pub count: usize,
+    pub types: Vec<&'static str>, // observed value types, in order of first appearance
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+/// Size and shape of a graph, as returned by the stats() command
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub vertex_label_counts: Vec<(String, usize)>, // label, count
+    pub edge_label_counts: Vec<(String, usize)>,   // label, count
+    pub property_stats: Vec<PropertyStats>,
+    pub degree: DegreeStats,
+    pub approximate_memory_bytes: usize,
+}
+
+impl GraphStats {
+    pub fn to_json(&self) -> Json {
+        let mut vertex_labels = Json::new_object();
+        for (label, count) in &self.vertex_label_counts {
+            vertex_labels[label.as_str()] = (*count).into();
+        }
+
+        let mut edge_labels = Json::new_object();
+        for (label, count) in &self.edge_label_counts {
+            edge_labels[label.as_str()] = (*count).into();
+        }
+
+        let mut properties = Json::new_object();
+        for property in &self.property_stats {
+            properties[property.name.as_str()] = json::object! {
+                count: property.count,
+                types: property.types.clone(),
+            };
+        }
+
+        json::object! {
+            vertex_count: self.vertex_count,
+            edge_count: self.edge_count,
+            vertex_labels: vertex_labels,
+            edge_labels: edge_labels,
+            properties: properties,
+            degree: json::object! {
+                min: self.degree.min,
+                max: self.degree.max,
+                mean: self.degree.mean,
+            },
+            approximate_memory_bytes: self.approximate_memory_bytes,
+        }
+    }
+}
+
+/// Accumulates label counts, keeping labels sorted so output is stable
+pub fn increment_label_count(counts: &mut Vec<(String, usize)>, label: &str) {
+    match counts.binary_search_by(|(existing, _)| existing.as_str().cmp(label)) {
+        Ok(index) => counts[index].1 += 1,
+        Err(index) => counts.insert(index, (label.to_string(), 1)),
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use json::JsonValue as Json;
+
+use crate::{error::GraphDbError, parser::ValidTypes};
+
+use super::{
+    changes::{Change, ChangeFeed},
+    edge::Edge,
+    options::{EdgePolicy, GraphOptions, OptionKey, SchemaMode},
+    property::{Property, PropertyValue},
+    spatial::Point,
+    vector::Metric,
+    vertex::Vertex,
+    Graph, InMemoryGraph,
+};
+
+/// The file a persistent graph keeps its vertices and edges in. It starts with the options the
+/// graph was created with, e.g. {"options":{"directed":true,"edgePolicy":"multigraph",..}}. Then
+/// each change is appended as a line of JSON holding the entity as it is afterwards, e.g.
+/// {"vertex":0,"label":"person","properties":[{"name":"age","type":"int32","value":30}]},
+/// {"edge":0,"removed":true}. Reading the lines back in order rebuilds the graph with the ids it
+/// had. Clearing the graph rewrites the file to hold just its options and the ids to carry on
+/// from, e.g. {"cleared":true,"nextVertex":4,"nextEdge":2}, so it doesn't keep growing.
+/// Triggers and history aren't kept, nor indexes created later
+pub(super) struct Storage {
+    path: PathBuf,
+    file: File,
+}
+
+impl Storage {
+    fn open(path: &Path) -> Result<Storage, GraphDbError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| storage_error(path, err))?;
+
+        Ok(Storage {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    fn append(&self, change: &Change) -> std::io::Result<()> {
+        match change_to_json(change) {
+            Some(line) => self.write(&line),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the file with one holding the given lines, then appends to that. The lines are
+    /// written to a file alongside first, so a failed write leaves the old file whole
+    fn rewrite(&self, lines: &[Json]) -> std::io::Result<Storage> {
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".compacting");
+        let temporary_path = PathBuf::from(temporary_path);
+
+        let text: String = lines
+            .iter()
+            .map(|line| format!("{}\n", line.dump()))
+            .collect();
+        fs::write(&temporary_path, text)?;
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(Storage {
+            path: self.path.clone(),
+            file: OpenOptions::new().append(true).open(&self.path)?,
+        })
+    }
+
+    /// Deletes the file, which is only kept for as long as its graph is
+    pub(super) fn delete(&self) -> Result<(), GraphDbError> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(storage_error(&self.path, err)),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, line: &Json) -> std::io::Result<()> {
+        let mut file = &self.file;
+        file.write_all(format!("{}\n", line.dump()).as_bytes())?;
+        file.flush()
+    }
+}
+
+impl InMemoryGraph {
+    /// Rebuilds the graph from its file, if it has one yet, then keeps every later change in it.
+    /// Followers of the graph are told it was loaded, rather than of each vertex and edge, so
+    /// undoing can't mistake its contents for additions
+    pub(super) fn open_storage(&mut self, path: &Path) -> Result<(), GraphDbError> {
+        let lines = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(storage_error(path, err)),
+        };
+
+        // Replayed changes aren't news, so they're published to a feed no one follows
+        let changes = std::mem::replace(&mut self.changes, Arc::new(ChangeFeed::new()));
+        for (number, line) in lines.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.replay(line).map_err(|err| {
+                GraphDbError::InvalidArgument(format!(
+                    "Line {} of {} can't be read back into graph '{}': {}",
+                    number + 1,
+                    path.display(),
+                    self.name,
+                    err
+                ))
+            })?;
+        }
+        self.changes = changes;
+        if self.vertices.len() > 0 {
+            self.publish(Change::GraphLoaded);
+        }
+
+        let storage = Storage::open(path)?;
+        if lines.trim().is_empty() {
+            storage
+                .write(&json::object! { options: options_to_json(&self.options) })
+                .map_err(|err| storage_error(path, err))?;
+        }
+        self.storage = Some(Arc::new(storage));
+        Ok(())
+    }
+
+    /// Appends the change to the graph's file, if it has one. After a failed write the file no
+    /// longer matches the graph, so later changes fail rather than being lost without a word
+    pub(super) fn store(&mut self, change: &Change) {
+        if self.storage_error.is_some() {
+            return;
+        }
+
+        if let Some(storage) = self.storage.clone() {
+            let result = match change {
+                Change::GraphCleared => self.compact_storage(&storage),
+                _ => storage.append(change),
+            };
+            if let Err(err) = result {
+                self.storage_error = Some(format!(
+                    "Graph '{}' can no longer be written to {}, so can't be changed: {}",
+                    self.name,
+                    storage.path.display(),
+                    err
+                ));
+            }
+        }
+    }
+
+    /// Rewrites the file of a graph that has just been cleared, dropping every line before the
+    /// clear. The ids handed out so far are kept, so they aren't reused once it's read back
+    fn compact_storage(&mut self, storage: &Storage) -> std::io::Result<()> {
+        let lines = [
+            json::object! { options: options_to_json(&self.options) },
+            json::object! {
+                cleared: true,
+                nextVertex: self.vertices.next_index(),
+                nextEdge: self.edges.next_index(),
+            },
+        ];
+        self.storage = Some(Arc::new(storage.rewrite(&lines)?));
+        Ok(())
+    }
+
+    pub(super) fn check_storage(&self) -> Result<(), GraphDbError> {
+        match &self.storage_error {
+            Some(err) => Err(GraphDbError::InvalidArgument(err.clone())),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn storage_path(&self) -> Option<&Path> {
+        self.storage.as_ref().map(|storage| storage.path.as_path())
+    }
+
+    /// Applies one line of the graph's file
+    fn replay(&mut self, line: &str) -> Result<(), GraphDbError> {
+        let entry =
+            json::parse(line).map_err(|err| GraphDbError::parse("JSON", err.to_string()))?;
+        let malformed = || GraphDbError::parse("a vertex, an edge or a clear", line);
+        let removed = entry["removed"].as_bool().unwrap_or(false);
+
+        if let Some(id) = entry["vertex"].as_usize() {
+            let current = self.vertices.get(&id).map(|v| v.to_vertex(&self.symbols));
+            match (removed, current) {
+                (true, _) => self.remove_vertex(&id)?,
+                (false, current) => {
+                    let vertex = vertex_from_json(&entry).ok_or_else(malformed)?;
+                    match current {
+                        Some(current) => {
+                            self.update_vertex(&id, current.properties_changed_to(&vertex))?
+                        }
+                        None => self.restore_vertex(id, vertex)?,
+                    }
+                }
+            };
+        } else if let Some(id) = entry["edge"].as_usize() {
+            let current = self.edges.get(&id).map(|e| e.to_edge(&self.symbols));
+            match (removed, current) {
+                (true, _) => self.remove_edge(&id)?,
+                (false, current) => {
+                    let edge = edge_from_json(&entry).ok_or_else(malformed)?;
+                    match current {
+                        Some(current) => self.update_edge(
+                            &id,
+                            edge.from_vertex_id,
+                            edge.to_vertex_id,
+                            current.edge_vertex.properties_changed_to(&edge.edge_vertex),
+                        )?,
+                        None => self.restore_edge(id, edge)?,
+                    }
+                }
+            };
+        } else if entry["cleared"].as_bool() == Some(true) {
+            self.clear();
+            if let Some(id) = entry["nextVertex"].as_usize() {
+                self.vertices.clear_to(id);
+            }
+            if let Some(id) = entry["nextEdge"].as_usize() {
+                self.edges.clear_to(id);
+            }
+        } else if entry["options"].is_object() {
+            self.adopt_options(&entry["options"])?;
+        } else {
+            return Err(malformed());
+        }
+
+        Ok(())
+    }
+
+    /// Takes on the options the file was created with, unless they were given differently now,
+    /// along with the indexes the file names as well as any given now
+    fn adopt_options(&mut self, options: &Json) -> Result<(), GraphDbError> {
+        let malformed = || GraphDbError::parse("the graph's options", options.dump());
+        let directed = options["directed"].as_bool().ok_or_else(malformed)?;
+        let edge_policy = EdgePolicy::from_name(options["edgePolicy"].as_str().unwrap_or(""))?;
+        let schema = SchemaMode::from_name(options["schema"].as_str().unwrap_or(""))?;
+
+        for (key, created, opened) in [
+            (
+                OptionKey::Directed,
+                directed.to_string(),
+                self.options.directed.to_string(),
+            ),
+            (
+                OptionKey::EdgePolicy,
+                edge_policy.as_str().to_string(),
+                self.options.edge_policy.as_str().to_string(),
+            ),
+            (
+                OptionKey::Schema,
+                schema.as_str().to_string(),
+                self.options.schema.as_str().to_string(),
+            ),
+        ] {
+            if self.options.is_given(key) && created != opened {
+                return Err(GraphDbError::InvalidArgument(format!(
+                    "The graph was created with option({key}, {created}), so can't be opened \
+                     with option({key}, {opened})",
+                    key = key.as_str()
+                )));
+            }
+        }
+        self.options.directed = directed;
+        self.options.edge_policy = edge_policy;
+        self.options.schema = schema;
+
+        for property_name in options["textIndexes"].members() {
+            let property_name = property_name.as_str().ok_or_else(malformed)?.to_string();
+            if !self.options.text_indexes.contains(&property_name) {
+                self.options.text_indexes.push(property_name);
+            }
+        }
+        for property_name in options["spatialIndexes"].members() {
+            let property_name = property_name.as_str().ok_or_else(malformed)?.to_string();
+            if !self.options.spatial_indexes.contains(&property_name) {
+                self.options.spatial_indexes.push(property_name);
+            }
+        }
+        for index in options["vectorIndexes"].members() {
+            let property_name = index["property"].as_str().ok_or_else(malformed)?;
+            let metric = Metric::from_name(index["metric"].as_str().ok_or_else(malformed)?)?;
+            if !self
+                .options
+                .vector_indexes
+                .iter()
+                .any(|(name, _)| name == property_name)
+            {
+                self.options
+                    .vector_indexes
+                    .push((property_name.to_string(), metric));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The options that decide how the file's lines are read back, and the indexes built on them
+fn options_to_json(options: &GraphOptions) -> Json {
+    json::object! {
+        directed: options.directed,
+        edgePolicy: options.edge_policy.as_str(),
+        schema: options.schema.as_str(),
+        textIndexes: options.text_indexes.clone(),
+        spatialIndexes: options.spatial_indexes.clone(),
+        vectorIndexes: options
+            .vector_indexes
+            .iter()
+            .map(|(property_name, metric)| json::object! {
+                property: property_name.as_str(),
+                metric: metric.as_str(),
+            })
+            .collect::<Vec<Json>>(),
+    }
+}
+
+/// The line a change is kept as, or None for changes to the graph as a whole that aren't appended
+fn change_to_json(change: &Change) -> Option<Json> {
+    let line = match change {
+        Change::VertexAdded { id, after } | Change::VertexUpdated { id, after, .. } => {
+            let mut line = vertex_to_json(after);
+            line["vertex"] = (*id).into();
+            line
+        }
+        Change::VertexRemoved { id, .. } => json::object! { vertex: *id, removed: true },
+        Change::EdgeAdded { id, after } | Change::EdgeUpdated { id, after, .. } => {
+            let mut line = vertex_to_json(&after.edge_vertex);
+            line["edge"] = (*id).into();
+            line["from"] = after.from_vertex_id.into();
+            line["to"] = after.to_vertex_id.into();
+            line
+        }
+        Change::EdgeRemoved { id, .. } => json::object! { edge: *id, removed: true },
+        // Clearing rewrites the file rather than adding to it
+        Change::GraphCleared
+        | Change::GraphLoaded
+        | Change::GraphDropped
+        | Change::GraphRenamed { .. } => return None,
+    };
+
+    Some(line)
+}
+
+/// Unlike change events, properties keep their types, so they read back exactly as they were
+fn vertex_to_json(vertex: &Vertex) -> Json {
+    let mut object = json::object! { label: vertex.label.as_str() };
+    if let Some(external_id) = &vertex.external_id {
+        object["externalId"] = external_id.as_str().into();
+    }
+    object["properties"] = vertex
+        .properties
+        .iter()
+        .map(|property| {
+            json::object! {
+                name: property.name.as_str(),
+                type: property.value.type_name(),
+                value: property.value.to_json(),
+            }
+        })
+        .collect::<Vec<Json>>()
+        .into();
+
+    object
+}
+
+fn vertex_from_json(object: &Json) -> Option<Vertex> {
+    let properties = object["properties"]
+        .members()
+        .map(property_from_json)
+        .collect::<Option<Vec<Property>>>()?;
+
+    let mut vertex = Vertex::new(object["label"].as_str()?.to_string(), properties);
+    vertex.external_id = object["externalId"].as_str().map(str::to_string);
+    Some(vertex)
+}
+
+fn edge_from_json(object: &Json) -> Option<Edge> {
+    Some(Edge {
+        from_vertex_id: object["from"].as_usize()?,
+        to_vertex_id: object["to"].as_usize()?,
+        edge_vertex: vertex_from_json(object)?,
+    })
+}
+
+fn property_from_json(object: &Json) -> Option<Property> {
+    let value = &object["value"];
+    let value = match ValidTypes::from_name(object["type"].as_str()?).ok()? {
+        ValidTypes::Boolean => PropertyValue::Boolean(value.as_bool()?),
+        ValidTypes::Int32 => PropertyValue::Int32(value.as_i32()?),
+        ValidTypes::Int64 => PropertyValue::Int64(value.as_i64()?),
+        ValidTypes::Float32 => PropertyValue::Float32(value.as_f32()?),
+        ValidTypes::Float64 => PropertyValue::Float64(value.as_f64()?),
+        ValidTypes::String => PropertyValue::String(value.as_str()?.to_string()),
+        ValidTypes::DateTime => PropertyValue::DateTime(value.as_i64()?),
+        ValidTypes::Point => PropertyValue::Point(Point {
+            latitude: value["latitude"].as_f64()?,
+            longitude: value["longitude"].as_f64()?,
+        }),
+        ValidTypes::Vector => PropertyValue::Vector(
+            value
+                .members()
+                .map(|number| number.as_f32())
+                .collect::<Option<Vec<f32>>>()?,
+        ),
+    };
+
+    Some(Property {
+        name: object["name"].as_str()?.to_string(),
+        value,
+        flagged_for_removal: false,
+    })
+}
+
+fn storage_error(path: &Path, error: std::io::Error) -> GraphDbError {
+    GraphDbError::InvalidArgument(format!(
+        "Unable to use {} to store the graph: {}",
+        path.display(),
+        error
+    ))
+}
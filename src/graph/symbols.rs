@@ -0,0 +1,59 @@
+use std::{collections::HashMap, mem::size_of};
+
+/// A label or property key, interned into a graph's symbol table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Maps each distinct label and property key in a graph to a small integer, so entities store
+/// and compare integers rather than strings. Symbols are never removed
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    names: Vec<String>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            names: Vec::new(),
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Returns the name's symbol, adding it if it hasn't been seen before
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.symbols.insert(name.to_string(), symbol);
+
+        symbol
+    }
+
+    /// Looks a name up without adding it. A name with no symbol is on no entity in the graph
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+
+    pub fn clear(&mut self) {
+        self.names.clear();
+        self.symbols.clear();
+    }
+
+    /// Approximate bytes used, including heap allocations
+    pub fn approximate_size(&self) -> usize {
+        let names_size: usize = self.names.iter().map(|name| name.capacity()).sum();
+
+        size_of::<SymbolTable>()
+            + self.names.capacity() * size_of::<String>()
+            + names_size * 2 // each name is held by both the list and the map
+            + self.symbols.capacity() * (size_of::<String>() + size_of::<Symbol>())
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, mem::size_of};
+
+// BM25 parameters: how quickly repeated terms stop adding to a score, and how much long
+// documents are penalised
+const TERM_SATURATION: f64 = 1.2;
+const LENGTH_NORMALISATION: f64 = 0.75;
+
+/// Where a term occurs in one document
+#[derive(Debug, Clone)]
+struct Posting {
+    vertex_id: usize,
+    positions: Vec<u32>, // token positions, ascending
+}
+
+/// An inverted index over one string property: for every term, the vertices whose value contains
+/// it and where. Text is split into lower case alphanumeric words
+#[derive(Debug, Clone, Default)]
+pub struct TextIndex {
+    postings: HashMap<String, Vec<Posting>>, // term, postings in ascending vertex id order
+    document_lengths: HashMap<usize, usize>, // vertex id, number of tokens
+    total_length: usize,
+}
+
+/// A search query: words that may match on their own, and phrases whose words must be adjacent
+#[derive(Debug)]
+struct Query {
+    terms: Vec<String>,
+    phrases: Vec<Vec<String>>,
+}
+
+fn tokenise(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+impl TextIndex {
+    pub fn new() -> Self {
+        TextIndex::default()
+    }
+
+    pub fn insert(&mut self, vertex_id: usize, text: &str) {
+        let tokens = tokenise(text);
+        self.total_length += tokens.len();
+        self.document_lengths.insert(vertex_id, tokens.len());
+
+        for (position, token) in tokens.into_iter().enumerate() {
+            let postings = self.postings.entry(token).or_default();
+            match postings.binary_search_by_key(&vertex_id, |posting| posting.vertex_id) {
+                Ok(index) => postings[index].positions.push(position as u32),
+                Err(index) => postings.insert(
+                    index,
+                    Posting {
+                        vertex_id,
+                        positions: vec![position as u32],
+                    },
+                ),
+            }
+        }
+    }
+
+    /// Removes a document, given the text it was indexed with
+    pub fn remove(&mut self, vertex_id: usize, text: &str) {
+        let length = match self.document_lengths.remove(&vertex_id) {
+            Some(length) => length,
+            None => return,
+        };
+        self.total_length -= length;
+
+        for token in tokenise(text) {
+            if let Some(postings) = self.postings.get_mut(&token) {
+                if let Ok(index) =
+                    postings.binary_search_by_key(&vertex_id, |posting| posting.vertex_id)
+                {
+                    postings.remove(index);
+                }
+
+                if postings.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+    }
+
+    /// Vertices matching the query with their relevance scores, most relevant first. A vertex
+    /// matches if it contains any of the query's words, or any of its phrases
+    pub fn search(&self, query: &str) -> Vec<(usize, f64)> {
+        let query = parse_query(query);
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        for term in &query.terms {
+            for (vertex_id, score) in self.term_scores(term) {
+                *scores.entry(vertex_id).or_default() += score;
+            }
+        }
+
+        for phrase in &query.phrases {
+            for vertex_id in self.phrase_matches(phrase) {
+                let score: f64 = phrase
+                    .iter()
+                    .map(|term| self.term_score(term, vertex_id))
+                    .sum();
+                *scores.entry(vertex_id).or_default() += score;
+            }
+        }
+
+        let mut results: Vec<(usize, f64)> = scores.into_iter().collect();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        results
+    }
+
+    /// Approximate bytes used, including heap allocations
+    pub fn approximate_size(&self) -> usize {
+        let postings_size: usize = self
+            .postings
+            .iter()
+            .map(|(term, postings)| {
+                let positions: usize = postings
+                    .iter()
+                    .map(|posting| posting.positions.capacity() * size_of::<u32>())
+                    .sum();
+
+                size_of::<String>()
+                    + term.capacity()
+                    + size_of::<Vec<Posting>>()
+                    + postings.capacity() * size_of::<Posting>()
+                    + positions
+            })
+            .sum();
+
+        size_of::<TextIndex>()
+            + postings_size
+            + self.document_lengths.capacity() * 2 * size_of::<usize>()
+    }
+
+    fn term_scores(&self, term: &str) -> Vec<(usize, f64)> {
+        match self.postings.get(term) {
+            Some(postings) => postings
+                .iter()
+                .map(|posting| {
+                    let score = self.bm25(postings.len(), posting);
+                    (posting.vertex_id, score)
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn term_score(&self, term: &str, vertex_id: usize) -> f64 {
+        self.postings
+            .get(term)
+            .and_then(|postings| {
+                let index = postings
+                    .binary_search_by_key(&vertex_id, |posting| posting.vertex_id)
+                    .ok()?;
+                Some(self.bm25(postings.len(), &postings[index]))
+            })
+            .unwrap_or(0.0)
+    }
+
+    fn bm25(&self, document_frequency: usize, posting: &Posting) -> f64 {
+        let document_count = self.document_lengths.len() as f64;
+        let document_frequency = document_frequency as f64;
+        let idf =
+            (1.0 + (document_count - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+
+        let term_frequency = posting.positions.len() as f64;
+        let length = self
+            .document_lengths
+            .get(&posting.vertex_id)
+            .copied()
+            .unwrap_or_default() as f64;
+        let average_length = self.total_length as f64 / document_count.max(1.0);
+        let normalisation =
+            1.0 - LENGTH_NORMALISATION + LENGTH_NORMALISATION * length / average_length.max(1.0);
+
+        idf * term_frequency * (TERM_SATURATION + 1.0)
+            / (term_frequency + TERM_SATURATION * normalisation)
+    }
+
+    /// Vertices containing the phrase's words next to each other, in order
+    fn phrase_matches(&self, phrase: &[String]) -> Vec<usize> {
+        let postings: Option<Vec<&Vec<Posting>>> =
+            phrase.iter().map(|term| self.postings.get(term)).collect();
+        let postings = match postings {
+            Some(postings) if !postings.is_empty() => postings,
+            _ => return Vec::new(),
+        };
+
+        let mut matches = Vec::new();
+        for first in postings[0] {
+            let following: Option<Vec<&Posting>> = postings[1..]
+                .iter()
+                .map(|term_postings| {
+                    term_postings
+                        .binary_search_by_key(&first.vertex_id, |posting| posting.vertex_id)
+                        .ok()
+                        .map(|index| &term_postings[index])
+                })
+                .collect();
+
+            let following = match following {
+                Some(following) => following,
+                None => continue,
+            };
+
+            let is_match = first.positions.iter().any(|start| {
+                following.iter().enumerate().all(|(offset, posting)| {
+                    posting
+                        .positions
+                        .binary_search(&(start + offset as u32 + 1))
+                        .is_ok()
+                })
+            });
+
+            if is_match {
+                matches.push(first.vertex_id);
+            }
+        }
+
+        matches
+    }
+}
+
+/// Splits a query into loose words, and phrases written in double quotes
+fn parse_query(query: &str) -> Query {
+    let mut terms = Vec::new();
+    let mut phrases = Vec::new();
+
+    // Every other part of the query, split on quotes, is inside a phrase
+    for (i, part) in query.split('"').enumerate() {
+        let tokens = tokenise(part);
+        match i % 2 {
+            1 if tokens.len() > 1 => phrases.push(tokens),
+            _ => terms.extend(tokens),
+        }
+    }
+
+    Query { terms, phrases }
+}
@@ -0,0 +1,387 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    mem::size_of,
+};
+
+use crate::error::GraphDbError;
+
+/// Links per vertex on the upper layers of the index. The bottom layer allows twice as many
+const MAX_NEIGHBOURS: usize = 16;
+
+/// Candidates kept while linking a new vertex into the index
+const CONSTRUCTION_CANDIDATES: usize = 100;
+
+/// Candidates kept while searching. Searches for more than this many vertices keep k instead
+const SEARCH_CANDIDATES: usize = 64;
+
+/// How the distance between two vectors is measured. Smaller distances are nearer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Cosine, // 1 - cosine similarity
+    L2,     // euclidean distance
+    Dot,    // negated dot product
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Cosine => "cosine",
+            Metric::L2 => "l2",
+            Metric::Dot => "dot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == Metric::Cosine.as_str() => Ok(Metric::Cosine),
+            _ if name == Metric::L2.as_str() => Ok(Metric::L2),
+            _ if name == Metric::Dot.as_str() => Ok(Metric::Dot),
+            other => Err(GraphDbError::parse("one of cosine, l2 or dot", other)),
+        }
+    }
+
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        let dot = || a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        match self {
+            Metric::Cosine => {
+                let norms = norm(a) * norm(b);
+                match norms == 0.0 {
+                    true => 1.0,
+                    false => 1.0 - dot() / norms,
+                }
+            }
+            Metric::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt(),
+            Metric::Dot => -dot(),
+        }
+    }
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// A vertex and its distance from the vector being searched for, ordered by distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    vector: Vec<f32>,
+    neighbours: Vec<Vec<usize>>, // per layer, from the bottom up to the node's own level
+}
+
+/// An approximate nearest neighbour index over one vector property: a hierarchical navigable
+/// small world graph. Each vertex is linked to its nearest neighbours on the bottom layer, and
+/// a shrinking random subset of vertices on each layer above, so a search can descend quickly
+/// towards the right region before looking closely
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    metric: Metric,
+    nodes: HashMap<usize, Node>, // vertex id, node
+    entry_point: Option<usize>,
+    rng_state: u64,
+}
+
+impl HnswIndex {
+    pub fn new(metric: Metric) -> Self {
+        HnswIndex {
+            metric,
+            nodes: HashMap::new(),
+            entry_point: None,
+            rng_state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn insert(&mut self, vertex_id: usize, vector: Vec<f32>) {
+        self.remove(vertex_id);
+
+        let level = self.random_level();
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.nodes.insert(
+                    vertex_id,
+                    Node {
+                        vector,
+                        neighbours: vec![Vec::new(); level + 1],
+                    },
+                );
+                self.entry_point = Some(vertex_id);
+                return;
+            }
+        };
+
+        let top_level = self.level_of(entry_point);
+        let mut entry_points = vec![self.candidate(&vector, entry_point)];
+        for layer in (level + 1..=top_level).rev() {
+            entry_points = self.search_layer(&vector, entry_points, 1, layer);
+        }
+
+        let mut neighbours = vec![Vec::new(); level + 1];
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(
+                &vector,
+                entry_points.clone(),
+                CONSTRUCTION_CANDIDATES,
+                layer,
+            );
+            neighbours[layer] = candidates
+                .iter()
+                .take(max_neighbours(layer))
+                .map(|candidate| candidate.id)
+                .collect();
+            entry_points = candidates;
+        }
+
+        self.nodes.insert(
+            vertex_id,
+            Node {
+                vector,
+                neighbours: neighbours.clone(),
+            },
+        );
+
+        // Link back from each neighbour, pruning any that now have too many links
+        for (layer, ids) in neighbours.iter().enumerate() {
+            for id in ids {
+                if let Some(links) = self.links_mut(*id, layer) {
+                    links.push(vertex_id);
+                }
+                self.prune(*id, layer);
+            }
+        }
+
+        if level > top_level {
+            self.entry_point = Some(vertex_id);
+        }
+    }
+
+    pub fn remove(&mut self, vertex_id: usize) {
+        let removed = match self.nodes.remove(&vertex_id) {
+            Some(node) => node,
+            None => return,
+        };
+
+        // Reconnect the removed vertex's neighbours through each other, so the graph stays navigable
+        for (layer, ids) in removed.neighbours.iter().enumerate() {
+            for id in ids {
+                if let Some(links) = self.links_mut(*id, layer) {
+                    links.retain(|neighbour| *neighbour != vertex_id);
+                    for other in ids {
+                        if other != id && !links.contains(other) {
+                            links.push(*other);
+                        }
+                    }
+                }
+                self.prune(*id, layer);
+            }
+        }
+
+        // Links aren't always symmetric after pruning, so others may still point to the removed
+        // vertex, or to a later vertex with the same id on fewer layers. Searches skip them, and
+        // pruning drops them
+
+        if self.entry_point == Some(vertex_id) {
+            self.entry_point = self
+                .nodes
+                .iter()
+                .max_by_key(|(id, node)| (node.neighbours.len(), Reverse(**id)))
+                .map(|(id, _)| *id);
+        }
+    }
+
+    /// The approximately k nearest vertices to the vector, with their distances, nearest first
+    pub fn search(&self, vector: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let entry_point = match self.entry_point {
+            Some(entry_point) if k > 0 => entry_point,
+            _ => return Vec::new(),
+        };
+
+        let mut entry_points = vec![self.candidate(vector, entry_point)];
+        for layer in (1..=self.level_of(entry_point)).rev() {
+            entry_points = self.search_layer(vector, entry_points, 1, layer);
+        }
+
+        self.search_layer(vector, entry_points, SEARCH_CANDIDATES.max(k), 0)
+            .into_iter()
+            .take(k)
+            .map(|candidate| (candidate.id, candidate.distance))
+            .collect()
+    }
+
+    /// Approximate bytes used, including heap allocations
+    pub fn approximate_size(&self) -> usize {
+        let nodes_size: usize = self
+            .nodes
+            .values()
+            .map(|node| {
+                let links: usize = node
+                    .neighbours
+                    .iter()
+                    .map(|ids| size_of::<Vec<usize>>() + ids.capacity() * size_of::<usize>())
+                    .sum();
+
+                size_of::<usize>()
+                    + size_of::<Node>()
+                    + node.vector.capacity() * size_of::<f32>()
+                    + links
+            })
+            .sum();
+
+        size_of::<HnswIndex>() + nodes_size
+    }
+
+    fn level_of(&self, vertex_id: usize) -> usize {
+        self.nodes
+            .get(&vertex_id)
+            .map_or(0, |node| node.neighbours.len() - 1)
+    }
+
+    fn is_on_layer(&self, vertex_id: usize, layer: usize) -> bool {
+        self.nodes
+            .get(&vertex_id)
+            .is_some_and(|node| node.neighbours.len() > layer)
+    }
+
+    fn links_mut(&mut self, vertex_id: usize, layer: usize) -> Option<&mut Vec<usize>> {
+        self.nodes
+            .get_mut(&vertex_id)
+            .and_then(|node| node.neighbours.get_mut(layer))
+    }
+
+    fn candidate(&self, vector: &[f32], vertex_id: usize) -> Candidate {
+        let distance = match self.nodes.get(&vertex_id) {
+            Some(node) => self.metric.distance(vector, &node.vector),
+            None => f32::INFINITY,
+        };
+
+        Candidate {
+            distance,
+            id: vertex_id,
+        }
+    }
+
+    /// Best first search of one layer, returning up to ef candidates, nearest first
+    fn search_layer(
+        &self,
+        vector: &[f32],
+        entry_points: Vec<Candidate>,
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().map(|c| c.id).collect();
+        let mut to_visit: BinaryHeap<Reverse<Candidate>> =
+            entry_points.iter().copied().map(Reverse).collect();
+        let mut found: BinaryHeap<Candidate> = entry_points.into_iter().collect();
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(Reverse(current)) = to_visit.pop() {
+            let furthest = found.peek().map_or(f32::INFINITY, |c| c.distance);
+            if current.distance > furthest && found.len() >= ef {
+                break;
+            }
+
+            let neighbours = self
+                .nodes
+                .get(&current.id)
+                .and_then(|node| node.neighbours.get(layer));
+            for id in neighbours.into_iter().flatten() {
+                if !visited.insert(*id) || !self.is_on_layer(*id, layer) {
+                    continue;
+                }
+
+                let candidate = self.candidate(vector, *id);
+                let furthest = found.peek().map_or(f32::INFINITY, |c| c.distance);
+                if found.len() < ef || candidate.distance < furthest {
+                    to_visit.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    /// Keeps only a vertex's nearest links on a layer, once it has more than allowed
+    fn prune(&mut self, vertex_id: usize, layer: usize) {
+        let (vector, ids) = match self.nodes.get(&vertex_id) {
+            Some(node) => match node.neighbours.get(layer) {
+                Some(ids) if ids.len() > max_neighbours(layer) => {
+                    (node.vector.clone(), ids.clone())
+                }
+                _ => return,
+            },
+            None => return,
+        };
+
+        let mut candidates: Vec<Candidate> = ids
+            .iter()
+            .filter(|id| self.is_on_layer(**id, layer))
+            .map(|id| self.candidate(&vector, *id))
+            .collect();
+        candidates.sort();
+
+        if let Some(links) = self.links_mut(vertex_id, layer) {
+            *links = candidates
+                .into_iter()
+                .take(max_neighbours(layer))
+                .map(|candidate| candidate.id)
+                .collect();
+        }
+    }
+
+    /// Levels follow a geometric distribution, so each layer holds about 1/MAX_NEIGHBOURS of
+    /// the one below. Uses xorshift, seeded the same every time, so builds are reproducible
+    fn random_level(&mut self) -> usize {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+
+        let uniform = (self.rng_state >> 11) as f64 / (1u64 << 53) as f64;
+        let level = -(1.0 - uniform).ln() / (MAX_NEIGHBOURS as f64).ln();
+
+        level.floor() as usize
+    }
+}
+
+fn max_neighbours(layer: usize) -> usize {
+    match layer {
+        0 => MAX_NEIGHBOURS * 2,
+        _ => MAX_NEIGHBOURS,
+    }
+}
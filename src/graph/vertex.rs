@@ -1,31 +1,30 @@
-use std::str::FromStr;
-
-use crate::graph::DataResult;
+use crate::{error::GraphDbError, graph::DataResult};
 
 use super::property::{Property, PropertyValue};
 
 #[derive(Debug, Clone)]
 pub struct Vertex {
     pub label: String,
+    pub external_id: Option<String>, // optional user supplied id, e.g. a UUID
     pub properties: Vec<Property>,
-    pub out_edge_ids: Vec<usize>,
-    pub in_edge_ids: Vec<usize>,
 }
 
 impl Vertex {
-    pub fn new(label: String, properties: Vec<Property>) -> Self {
+    pub fn new(label: String, mut properties: Vec<Property>) -> Self {
+        // Properties are usually collected one at a time, leaving spare capacity behind
+        properties.shrink_to_fit();
+
         Vertex {
             label,
+            external_id: None,
             properties,
-            in_edge_ids: Vec::new(),
-            out_edge_ids: Vec::new(),
         }
     }
 
-    pub fn update(&mut self, properties: Vec<Property>) -> Result<DataResult, String> {
-        if self.properties.len() < 1 {
+    pub fn update(&mut self, properties: Vec<Property>) -> Result<DataResult, GraphDbError> {
+        if self.properties.is_empty() {
             self.properties = properties;
-            return Ok(DataResult::VertexRef(self));
+            return Ok(DataResult::Vertex(self.clone()));
         }
 
         let mut property_indices_to_remove = Vec::new();
@@ -54,7 +53,7 @@ impl Vertex {
             self.properties.remove(index);
         }
 
-        Ok(DataResult::VertexRef(self))
+        Ok(DataResult::Vertex(self.clone()))
     }
 
     pub fn has_property(&self, name: &str) -> bool {
@@ -68,56 +67,16 @@ impl Vertex {
     }
 
     pub fn has_property_value(&self, name: &str, value: &str) -> bool {
-        for property in &self.properties {
-            if property.name == name {
-                let is_match = match &property.value {
-                    PropertyValue::Boolean(val) => compare_to_string(*val, value),
-                    PropertyValue::Int32(val) => compare_to_string(*val, value),
-                    PropertyValue::Int64(val) => compare_to_string(*val, value),
-                    PropertyValue::Float32(val) => compare_to_string(*val, value),
-                    PropertyValue::Float64(val) => compare_to_string(*val, value),
-                    PropertyValue::String(val) => val == value.trim(),
-
-                    // TODO: This isn't that practical unless searching for a specific epoch
-                    PropertyValue::DateTime(val) => compare_to_string(*val, value),
-                };
-
-                match is_match {
-                    true => return true,
-                    false => (),
-                }
-            }
-        }
-
-        false
+        self.properties
+            .iter()
+            .any(|property| property.name == name && property.value.matches_str(value))
     }
 
     /// Only works for string properties
     pub fn has_property_like(&self, name: &str, search_term: &str) -> bool {
-        let string_properties = self
-            .properties
+        self.properties
             .iter()
-            .filter(|property| match property.value {
-                PropertyValue::String(_) => true,
-                _ => false,
-            })
-            .collect::<Vec<_>>();
-
-        for property in string_properties {
-            if property.name == name {
-                let is_match = match &property.value {
-                    PropertyValue::String(val) => val.contains(search_term.trim()),
-                    _ => false,
-                };
-
-                match is_match {
-                    true => return true,
-                    false => (),
-                }
-            }
-        }
-
-        false
+            .any(|property| property.name == name && property.value.contains_str(search_term))
     }
 
     pub fn get_property_value(&self, name: &str) -> Option<&PropertyValue> {
@@ -129,30 +88,24 @@ impl Vertex {
 
         None
     }
-}
 
-fn compare_to_string<T>(number: T, string: &str) -> bool
-where
-    T: FromStr + PartialEq,
-{
-    match string.parse::<T>() {
-        Ok(parsed_val) => {
-            if parsed_val == number {
-                return true;
+    /// Properties that, passed to an update, give this vertex the other's properties. Those the
+    /// other doesn't have are removed
+    pub fn properties_changed_to(&self, other: &Vertex) -> Vec<Property> {
+        let mut properties = other.properties.clone();
+        for property in &self.properties {
+            if other.get_property_value(&property.name).is_none() {
+                properties.push(Property {
+                    flagged_for_removal: true,
+                    ..property.clone()
+                });
             }
-
-            false
         }
-        Err(_) => false,
-    }
-}
 
-fn get_first_property_index_by_name<'a>(properties: &Vec<Property>, name: &str) -> Option<usize> {
-    for i in 0..properties.len() {
-        if properties[i].name == name {
-            return Some(i);
-        }
+        properties
     }
+}
 
-    None
+fn get_first_property_index_by_name(properties: &[Property], name: &str) -> Option<usize> {
+    properties.iter().position(|property| property.name == name)
 }
@@ -0,0 +1,44 @@
+//! A simple graph database, usable as a library or through the `rust_graph_db` REPL.
+//!
+//! Graphs can be built with the typed API:
+//!
+//! ```
+//! use rust_graph_db::Database;
+//!
+//! let database = Database::new();
+//! let graph = database.create_graph("social").unwrap();
+//!
+//! let alice = graph.add_vertex("person").property("age", 30i32).insert().unwrap();
+//! let bob = graph.add_vertex("person").property("name", "bob").insert().unwrap();
+//! graph.add_edge("knows", alice, bob).insert().unwrap();
+//! ```
+//!
+//! Or queried with the same commands the REPL accepts, e.g. `database.query("social.V().hasLabel(person)")`,
+//! including Cypher style patterns, e.g. `social.MATCH (a:person)-[:knows]->(b) RETURN b`.
+//! Values can be passed alongside a query rather than written into it, e.g.
+//! `database.query_with("social.V().hasPropertyValue(name, $name)", &Parameters::new().set("name", "bob"))`.
+//!
+//! Changes to every graph can be followed with `database.changes().subscribe(None)`, or written
+//! to a file or socket as newline delimited JSON.
+
+mod database;
+pub mod error;
+pub mod executor;
+pub mod graph;
+pub mod parser;
+
+pub use database::{Database, GraphHandle, Journal};
+pub use error::GraphDbError;
+pub use executor::{
+    parameters::{Parameters, PreparedQuery},
+    pattern::Binding,
+};
+pub use graph::{
+    builder::{EdgeBuilder, VertexBuilder},
+    changes::{Change, ChangeEvent, ChangeFeed, SessionId},
+    edge::Edge,
+    options::{EdgePolicy, GraphOptions, SchemaMode},
+    property::{Property, PropertyValue},
+    vertex::Vertex,
+    DataResult, Graph, GraphFactory, GraphType,
+};
@@ -1,31 +1,200 @@
+mod create_graph;
 mod list_vertices;
 mod mutate_vertex;
+mod pattern;
+mod trigger;
 
-use crate::executor::{Command, CommandType, EdgeMutationCommandType};
+use crate::{
+    error::GraphDbError,
+    executor::{Command, CommandType, EdgeMutationCommandType, VertexFilterCommandType},
+    graph::vector::Metric,
+};
 use json::object as JsonObject;
 
 use self::{
-    list_vertices::parse_list_vertices_commands, mutate_vertex::parse_entity_mutation_commmands,
+    create_graph::parse_graph_options,
+    list_vertices::parse_list_vertices_commands,
+    mutate_vertex::{parse_edge_mutation_commands, parse_entity_mutation_commmands},
+    pattern::{is_match_query, parse_match_query},
+    trigger::{parse_create_trigger_command, parse_trigger_commands},
 };
 
+const PARAMETER_PREFIX: char = '$';
 const HELP_KEY: &str = "help";
-const CREATE_GRAPH_KEY: &str = "createGraph(";
+pub const CREATE_GRAPH_KEY: &str = "createGraph(";
 const LIST_GRAPHS_KEY: &str = "listGraph()";
+const DROP_GRAPH_KEY: &str = "dropGraph(";
+const RENAME_GRAPH_KEY: &str = "renameGraph(";
+const CLEAR_GRAPH_KEY: &str = "clearGraph(";
+const CLONE_GRAPH_KEY: &str = "cloneGraph(";
+const DESCRIBE_GRAPH_KEY: &str = "describeGraph(";
+const EDGE_POLICY_KEY: &str = "edgePolicy(";
+const DIRECTED_KEY: &str = "directed(";
+const TYPE_KEY: &str = "type(";
+const OPTION_KEY: &str = "option(";
 const LIST_VERTICES_KEY: &str = "V()";
 const GET_VERTEX_KEY: &str = "V(";
 const ADD_VERTEX_KEY: &str = "addV(";
 const EDIT_VERTEX_KEY: &str = "editV(";
 const DELETE_VERTEX_KEY: &str = "deleteV(";
 const ADD_EDGE_KEY: &str = "addE(";
+const EDIT_EDGE_KEY: &str = "editE(";
+const DELETE_EDGE_KEY: &str = "deleteE(";
+const FROM_KEY: &str = "from(";
+const TO_KEY: &str = "to(";
 const PROPERTY_KEY: &str = "property(";
 const REMOVE_PROPERTY_KEY: &str = "removeProperty(";
+const EXTERNAL_ID_KEY: &str = "externalId(";
 const HAS_LABEL_KEY: &str = "hasLabel(";
+const HAS_EXTERNAL_ID_KEY: &str = "hasExternalId(";
 const HAS_PROPERTY_KEY: &str = "hasProperty(";
 const HAS_PROPERTY_VALUE_KEY: &str = "hasPropertyValue(";
 const HAS_PROPERTY_LIKE_KEY: &str = "hasPropertyLike(";
+const HAS_PROPERTY_STARTS_WITH_KEY: &str = "hasPropertyStartsWith(";
+const HAS_PROPERTY_ENDS_WITH_KEY: &str = "hasPropertyEndsWith(";
+const HAS_PROPERTY_VALUE_IGNORE_CASE_KEY: &str = "hasPropertyValueIgnoreCase(";
+const HAS_PROPERTY_LIKE_IGNORE_CASE_KEY: &str = "hasPropertyLikeIgnoreCase(";
+const HAS_PROPERTY_STARTS_WITH_IGNORE_CASE_KEY: &str = "hasPropertyStartsWithIgnoreCase(";
+const HAS_PROPERTY_ENDS_WITH_IGNORE_CASE_KEY: &str = "hasPropertyEndsWithIgnoreCase(";
+const HAS_PROPERTY_REGEX_KEY: &str = "hasPropertyRegex(";
+const HAS_PROPERTY_WITHIN_RADIUS_KEY: &str = "hasPropertyWithinRadius(";
+const HAS_PROPERTY_WITHIN_BOX_KEY: &str = "hasPropertyWithinBox(";
+const HAS_PROPERTY_NEAREST_KEY: &str = "hasPropertyNearest(";
+const NEAREST_KEY: &str = "nearest(";
+const OUT_KEY: &str = "out(";
+const IN_KEY: &str = "in(";
 const VALUES_KEY: &str = "values(";
+const OUT_EDGES_KEY: &str = "outE(";
+const IN_EDGES_KEY: &str = "inE(";
+const IN_VERTEX_KEY: &str = "inV()";
+const OUT_VERTEX_KEY: &str = "outV()";
+const OTHER_VERTEX_KEY: &str = "otherV()";
+const REPEAT_KEY: &str = "repeat(";
+const TIMES_KEY: &str = "times(";
+const UNTIL_KEY: &str = "until(";
+const EMIT_KEY: &str = "emit()";
+const SIMPLE_PATH_KEY: &str = "simplePath()";
+const CYCLIC_PATH_KEY: &str = "cyclicPath()";
+const STATS_KEY: &str = "stats()";
+const CREATE_TEXT_INDEX_KEY: &str = "createTextIndex(";
+const DROP_TEXT_INDEX_KEY: &str = "dropTextIndex(";
+const SEARCH_KEY: &str = "search(";
+const CREATE_SPATIAL_INDEX_KEY: &str = "createSpatialIndex(";
+const DROP_SPATIAL_INDEX_KEY: &str = "dropSpatialIndex(";
+const CREATE_VECTOR_INDEX_KEY: &str = "createVectorIndex(";
+const DROP_VECTOR_INDEX_KEY: &str = "dropVectorIndex(";
+const CREATE_TRIGGER_KEY: &str = "createTrigger(";
+const DROP_TRIGGER_KEY: &str = "dropTrigger(";
+const LIST_TRIGGERS_KEY: &str = "triggers()";
+const TARGET_KEY: &str = "target(";
+const NOW_KEY: &str = "now(";
+const INCREMENT_KEY: &str = "increment(";
+const REQUIRE_KEY: &str = "require(";
+const REJECT_KEY: &str = "reject(";
+const MATCH_KEY: &str = "MATCH ";
+const EXPLAIN_KEY: &str = "explain(";
+const PROFILE_KEY: &str = "profile(";
+const AS_OF_KEY: &str = "asOf(";
+const HISTORY_KEY: &str = "history(";
+const ENABLE_VERSIONING_KEY: &str = "enableVersioning()";
+const DISABLE_VERSIONING_KEY: &str = "disableVersioning()";
 const END_COMMAND_KEY: &str = ")";
 
+/// Commands that are not preceded by a graph name
+pub const STANDALONE_COMMAND_KEYS: [&str; 8] = [
+    HELP_KEY,
+    CREATE_GRAPH_KEY,
+    LIST_GRAPHS_KEY,
+    DROP_GRAPH_KEY,
+    RENAME_GRAPH_KEY,
+    CLEAR_GRAPH_KEY,
+    CLONE_GRAPH_KEY,
+    DESCRIBE_GRAPH_KEY,
+];
+
+/// Standalone commands whose parameters are existing graph names
+pub const GRAPH_NAME_COMMAND_KEYS: [&str; 5] = [
+    DROP_GRAPH_KEY,
+    RENAME_GRAPH_KEY,
+    CLEAR_GRAPH_KEY,
+    CLONE_GRAPH_KEY,
+    DESCRIBE_GRAPH_KEY,
+];
+
+/// Commands that follow createGraph(<graph name>), setting up the new graph
+pub const CREATE_GRAPH_COMMAND_KEYS: [&str; 4] =
+    [TYPE_KEY, OPTION_KEY, EDGE_POLICY_KEY, DIRECTED_KEY];
+
+/// Commands that follow a graph name, or another command on the same graph
+pub const GRAPH_COMMAND_KEYS: [&str; 66] = [
+    LIST_VERTICES_KEY,
+    GET_VERTEX_KEY,
+    ADD_VERTEX_KEY,
+    EDIT_VERTEX_KEY,
+    DELETE_VERTEX_KEY,
+    ADD_EDGE_KEY,
+    EDIT_EDGE_KEY,
+    DELETE_EDGE_KEY,
+    FROM_KEY,
+    TO_KEY,
+    PROPERTY_KEY,
+    REMOVE_PROPERTY_KEY,
+    EXTERNAL_ID_KEY,
+    HAS_LABEL_KEY,
+    HAS_EXTERNAL_ID_KEY,
+    HAS_PROPERTY_KEY,
+    HAS_PROPERTY_VALUE_KEY,
+    HAS_PROPERTY_LIKE_KEY,
+    HAS_PROPERTY_STARTS_WITH_KEY,
+    HAS_PROPERTY_ENDS_WITH_KEY,
+    HAS_PROPERTY_VALUE_IGNORE_CASE_KEY,
+    HAS_PROPERTY_LIKE_IGNORE_CASE_KEY,
+    HAS_PROPERTY_STARTS_WITH_IGNORE_CASE_KEY,
+    HAS_PROPERTY_ENDS_WITH_IGNORE_CASE_KEY,
+    HAS_PROPERTY_REGEX_KEY,
+    HAS_PROPERTY_WITHIN_RADIUS_KEY,
+    HAS_PROPERTY_WITHIN_BOX_KEY,
+    HAS_PROPERTY_NEAREST_KEY,
+    NEAREST_KEY,
+    OUT_KEY,
+    IN_KEY,
+    VALUES_KEY,
+    OUT_EDGES_KEY,
+    IN_EDGES_KEY,
+    IN_VERTEX_KEY,
+    OUT_VERTEX_KEY,
+    OTHER_VERTEX_KEY,
+    REPEAT_KEY,
+    TIMES_KEY,
+    UNTIL_KEY,
+    EMIT_KEY,
+    SIMPLE_PATH_KEY,
+    CYCLIC_PATH_KEY,
+    STATS_KEY,
+    CREATE_TEXT_INDEX_KEY,
+    DROP_TEXT_INDEX_KEY,
+    SEARCH_KEY,
+    CREATE_SPATIAL_INDEX_KEY,
+    DROP_SPATIAL_INDEX_KEY,
+    CREATE_VECTOR_INDEX_KEY,
+    DROP_VECTOR_INDEX_KEY,
+    CREATE_TRIGGER_KEY,
+    DROP_TRIGGER_KEY,
+    LIST_TRIGGERS_KEY,
+    TARGET_KEY,
+    NOW_KEY,
+    INCREMENT_KEY,
+    REQUIRE_KEY,
+    REJECT_KEY,
+    MATCH_KEY,
+    EXPLAIN_KEY,
+    PROFILE_KEY,
+    AS_OF_KEY,
+    HISTORY_KEY,
+    ENABLE_VERSIONING_KEY,
+    DISABLE_VERSIONING_KEY,
+];
+
 pub enum JsonProperty {
     GraphName,
 }
@@ -38,6 +207,7 @@ impl JsonProperty {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ValidTypes {
     Boolean,
     Int32,
@@ -46,6 +216,8 @@ pub enum ValidTypes {
     Float64,
     String,
     DateTime,
+    Point,
+    Vector,
 }
 
 impl ValidTypes {
@@ -58,19 +230,81 @@ impl ValidTypes {
             ValidTypes::Float64 => "float64",
             ValidTypes::String => "string",
             ValidTypes::DateTime => "datetime",
+            ValidTypes::Point => "point",
+            ValidTypes::Vector => "vector",
         }
     }
+
+    pub fn from_name(name: &str) -> Result<Self, GraphDbError> {
+        let name = name.trim();
+        match name {
+            _ if name == ValidTypes::Boolean.as_str() => Ok(ValidTypes::Boolean),
+            _ if name == ValidTypes::Int32.as_str() => Ok(ValidTypes::Int32),
+            _ if name == ValidTypes::Int64.as_str() => Ok(ValidTypes::Int64),
+            _ if name == ValidTypes::Float32.as_str() => Ok(ValidTypes::Float32),
+            _ if name == ValidTypes::Float64.as_str() => Ok(ValidTypes::Float64),
+            _ if name == ValidTypes::String.as_str() => Ok(ValidTypes::String),
+            _ if name == ValidTypes::DateTime.as_str() => Ok(ValidTypes::DateTime),
+            _ if name == ValidTypes::Point.as_str() => Ok(ValidTypes::Point),
+            _ if name == ValidTypes::Vector.as_str() => Ok(ValidTypes::Vector),
+            other => Err(GraphDbError::parse("a property type", other)),
+        }
+    }
+}
+
+/// The name of a placeholder argument, e.g. name for $name. Its value is bound when the command
+/// is run, so is never written into, or split out of, the command's text
+pub fn parameter_name(argument: &str) -> Option<&str> {
+    let name = argument.trim().strip_prefix(PARAMETER_PREFIX)?;
+    let mut chars = name.chars();
+    let starts_well = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_');
+    (starts_well && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(name)
 }
 
-pub fn parse(command: String) -> Result<Command, String> {
-    let command_components: Vec<&str> = command.split(".").collect();
+pub fn parse(command: String) -> Result<Command, GraphDbError> {
+    // A MATCH query has dots of its own, e.g. WHERE a.name = 'alice', so is kept whole. It may
+    // follow asOf(...) as well as the graph name
+    let mut command_components = split_commands(&command);
+    if let Some(index) =
+        (1..command_components.len()).find(|index| is_match_query(command_components[*index]))
+    {
+        let offset: usize = command_components[..index]
+            .iter()
+            .map(|component| component.len() + 1)
+            .sum();
+        command_components.truncate(index);
+        command_components.push(&command[offset..]);
+    }
 
     // Graph commands
     let mut command_type = None;
     if command.starts_with(CREATE_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
-        let graph_name = extract_graph_name(&command_components[0])?;
+        let graph_name = extract_graph_name(CREATE_GRAPH_KEY, command_components[0])?;
+        let options = parse_graph_options(&command_components)?;
+
+        command_type = Some(Ok(CommandType::CreateGraph(graph_name, options)));
+    } else if command.starts_with(DROP_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
+        let graph_name = extract_graph_name(DROP_GRAPH_KEY, &command)?;
+
+        command_type = Some(Ok(CommandType::DropGraph(graph_name)));
+    } else if command.starts_with(RENAME_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
+        let (old_name, new_name) = extract_graph_name_pair(RENAME_GRAPH_KEY, &command)?;
+
+        command_type = Some(Ok(CommandType::RenameGraph(old_name, new_name)));
+    } else if command.starts_with(CLEAR_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
+        let graph_name = extract_graph_name(CLEAR_GRAPH_KEY, &command)?;
 
-        command_type = Some(Ok(CommandType::CreateGraph(graph_name)));
+        command_type = Some(Ok(CommandType::ClearGraph(graph_name)));
+    } else if command.starts_with(CLONE_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
+        let (source_name, destination_name) = extract_graph_name_pair(CLONE_GRAPH_KEY, &command)?;
+
+        command_type = Some(Ok(CommandType::CloneGraph(source_name, destination_name)));
+    } else if command.starts_with(DESCRIBE_GRAPH_KEY) && command.ends_with(END_COMMAND_KEY) {
+        let graph_name = extract_graph_name(DESCRIBE_GRAPH_KEY, &command)?;
+
+        command_type = Some(Ok(CommandType::DescribeGraph(graph_name)));
     } else if command == LIST_GRAPHS_KEY {
         command_type = Some(Ok(CommandType::ListGraphs));
     } else if command.to_lowercase() == HELP_KEY {
@@ -90,8 +324,33 @@ pub fn parse(command: String) -> Result<Command, String> {
                 command_json: None,
             }),
 
-            CommandType::CreateGraph(name) => Ok(Command {
-                command_type: CommandType::CreateGraph(name),
+            CommandType::CreateGraph(name, options) => Ok(Command {
+                command_type: CommandType::CreateGraph(name, options),
+                command_json: None,
+            }),
+
+            CommandType::DropGraph(name) => Ok(Command {
+                command_type: CommandType::DropGraph(name),
+                command_json: None,
+            }),
+
+            CommandType::RenameGraph(old_name, new_name) => Ok(Command {
+                command_type: CommandType::RenameGraph(old_name, new_name),
+                command_json: None,
+            }),
+
+            CommandType::ClearGraph(name) => Ok(Command {
+                command_type: CommandType::ClearGraph(name),
+                command_json: None,
+            }),
+
+            CommandType::CloneGraph(source_name, destination_name) => Ok(Command {
+                command_type: CommandType::CloneGraph(source_name, destination_name),
+                command_json: None,
+            }),
+
+            CommandType::DescribeGraph(name) => Ok(Command {
+                command_type: CommandType::DescribeGraph(name),
                 command_json: None,
             }),
 
@@ -137,6 +396,153 @@ pub fn parse(command: String) -> Result<Command, String> {
                 }),
             }),
 
+            CommandType::EditEdge(id, mutation_command) => Ok(Command {
+                command_type: CommandType::EditEdge(id, mutation_command),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::RemoveEdge(id) => Ok(Command {
+                command_type: CommandType::RemoveEdge(id),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::GraphStats => Ok(Command {
+                command_type: CommandType::GraphStats,
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::CreateTextIndex(property_name) => Ok(Command {
+                command_type: CommandType::CreateTextIndex(property_name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::DropTextIndex(property_name) => Ok(Command {
+                command_type: CommandType::DropTextIndex(property_name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::TextSearch(property_name, query) => Ok(Command {
+                command_type: CommandType::TextSearch(property_name, query),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::CreateSpatialIndex(property_name) => Ok(Command {
+                command_type: CommandType::CreateSpatialIndex(property_name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::DropSpatialIndex(property_name) => Ok(Command {
+                command_type: CommandType::DropSpatialIndex(property_name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::CreateVectorIndex(property_name, metric) => Ok(Command {
+                command_type: CommandType::CreateVectorIndex(property_name, metric),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::DropVectorIndex(property_name) => Ok(Command {
+                command_type: CommandType::DropVectorIndex(property_name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::CreateTrigger(trigger) => Ok(Command {
+                command_type: CommandType::CreateTrigger(trigger),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::DropTrigger(name) => Ok(Command {
+                command_type: CommandType::DropTrigger(name),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::ListTriggers => Ok(Command {
+                command_type: CommandType::ListTriggers,
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::Match(query) => Ok(Command {
+                command_type: CommandType::Match(query),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::Explain(filters) => Ok(Command {
+                command_type: CommandType::Explain(filters),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::Profile(filters) => Ok(Command {
+                command_type: CommandType::Profile(filters),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::AsOf(timestamp, filters) => Ok(Command {
+                command_type: CommandType::AsOf(timestamp, filters),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::MatchAsOf(timestamp, query) => Ok(Command {
+                command_type: CommandType::MatchAsOf(timestamp, query),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::History(id) => Ok(Command {
+                command_type: CommandType::History(id),
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::EnableVersioning => Ok(Command {
+                command_type: CommandType::EnableVersioning,
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
+            CommandType::DisableVersioning => Ok(Command {
+                command_type: CommandType::DisableVersioning,
+                command_json: Some(JsonObject! {
+                    graph_name: identify_graph(&command_components)
+                }),
+            }),
+
             CommandType::Help => Ok(Command {
                 command_type: CommandType::Help,
                 command_json: None,
@@ -146,19 +552,211 @@ pub fn parse(command: String) -> Result<Command, String> {
     }
 }
 
-fn identify_graph(command_components: &Vec<&str>) -> String {
+/// Splits a command into its dot separated parts. Dots inside brackets (e.g. in a float value or
+/// regex) don't split, and nor do brackets escaped with a backslash count towards nesting
+fn split_commands(command: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in command.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '.' if depth <= 0 => {
+                components.push(&command[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    components.push(&command[start..]);
+
+    components
+}
+
+fn identify_graph(command_components: &[&str]) -> String {
     command_components[0].trim().to_string()
 }
 
-fn get_command_type(command_components: &Vec<&str>) -> Result<CommandType, String> {
+/// Character offset of the given component within the full command, ignoring leading whitespace
+fn component_offset(command_components: &[&str], index: usize) -> usize {
+    let preceding: usize = command_components[..index]
+        .iter()
+        .map(|component| component.len() + 1) // +1 for the '.' separator
+        .sum();
+    let component = command_components[index];
+
+    preceding + component.len() - component.trim_start().len()
+}
+
+fn get_command_type(command_components: &[&str]) -> Result<CommandType, GraphDbError> {
     if command_components.len() < 2 {
-        return Err(format!("Invalid command"));
+        return Err(GraphDbError::parse(
+            "a standalone command, or a graph name followed by a command",
+            command_components[0].trim(),
+        ));
+    }
+
+    if let Some(filters) = parse_wrapped_query(EXPLAIN_KEY, command_components)? {
+        return Ok(CommandType::Explain(filters));
+    }
+    if let Some(filters) = parse_wrapped_query(PROFILE_KEY, command_components)? {
+        return Ok(CommandType::Profile(filters));
+    }
+    if let Some(command_type) = parse_as_of_query(command_components)? {
+        return Ok(command_type);
+    }
+
+    let command = command_components[1].trim();
+    let mut command_type = parse_graph_command(command)
+        .map_err(|err| err.with_offset(component_offset(command_components, 1)))?;
+
+    // Add follow up commands
+    match command_type {
+        CommandType::ListVertices(_) => {
+            let filter_commands = parse_list_vertices_commands(command_components)?;
+            command_type = CommandType::ListVertices(filter_commands);
+        }
+        // A single vertex followed by filters is a list, starting from that vertex
+        CommandType::GetVertex(id) if command_components.len() > 2 => {
+            let mut filter_commands = vec![VertexFilterCommandType::HasId(id)];
+            filter_commands.extend(parse_list_vertices_commands(command_components)?);
+            command_type = CommandType::ListVertices(filter_commands);
+        }
+        CommandType::AddVertex(label, _) => {
+            let mutation_commands = parse_entity_mutation_commmands(command_components)?;
+            command_type = CommandType::AddVertex(label, mutation_commands);
+        }
+        CommandType::EditVertex(id, _) => {
+            let mutation_commands = parse_entity_mutation_commmands(command_components)?;
+            command_type = CommandType::EditVertex(id, mutation_commands);
+        }
+        CommandType::AddEdge(_, ref mut mutation_commands) => {
+            let vertex_commands = parse_entity_mutation_commmands(command_components)?;
+            mutation_commands.push(EdgeMutationCommandType::VertexMutationCommandTypes(
+                vertex_commands,
+            ));
+        }
+        CommandType::EditEdge(id, _) => {
+            let mutation_commands = parse_edge_mutation_commands(command_components)?;
+            command_type = CommandType::EditEdge(id, mutation_commands);
+        }
+        CommandType::CreateTrigger(ref mut trigger) => {
+            let (target, actions) = parse_trigger_commands(command_components)?;
+            trigger.target = target;
+            trigger.actions = actions;
+        }
+
+        _ => (),
+    };
+
+    Ok(command_type)
+}
+
+/// The filters of a V() query wrapped in explain(...) or profile(...), e.g.
+/// graph.explain(V().hasLabel(person).out(knows))
+fn parse_wrapped_query(
+    key: &str,
+    command_components: &[&str],
+) -> Result<Option<Vec<VertexFilterCommandType>>, GraphDbError> {
+    let command = command_components[1].trim();
+    if !(command.starts_with(key) && command.ends_with(END_COMMAND_KEY)) {
+        return Ok(None);
+    }
+    if command_components.len() > 2 {
+        return Err(GraphDbError::parse(
+            format!("nothing after {key}<query>)"),
+            command_components[2].trim(),
+        )
+        .with_offset(component_offset(command_components, 2)));
+    }
+
+    let offset = component_offset(command_components, 1) + key.len();
+    let query = &command[key.len()..command.len() - END_COMMAND_KEY.len()];
+
+    // Parsed as though it followed a graph name. The empty name's dot moves offsets along by one
+    let mut components = vec![""];
+    components.extend(split_commands(query));
+    match get_command_type(&components).map_err(|err| err.with_offset(offset - 1))? {
+        CommandType::ListVertices(filters) => Ok(Some(filters)),
+        CommandType::GetVertex(id) => Ok(Some(vec![VertexFilterCommandType::HasId(id)])),
+        _ => Err(
+            GraphDbError::parse(format!("a V() query for {key}<query>)"), query.trim())
+                .with_offset(offset),
+        ),
     }
+}
 
+/// A V() or MATCH query run against the graph as it was at a time, e.g.
+/// graph.asOf(1700000000000).V().out()
+fn parse_as_of_query(command_components: &[&str]) -> Result<Option<CommandType>, GraphDbError> {
     let command = command_components[1].trim();
-    let mut command_type = match command {
+    if !(command.starts_with(AS_OF_KEY) && command.ends_with(END_COMMAND_KEY)) {
+        return Ok(None);
+    }
+
+    let offset = component_offset(command_components, 1);
+    let timestamp = extract_string(AS_OF_KEY, command)
+        .and_then(|timestamp| {
+            timestamp.parse::<i64>().map_err(|_| {
+                GraphDbError::parse(
+                    format!("milliseconds since January 1, 1970 for {AS_OF_KEY}<value>)"),
+                    timestamp,
+                )
+                .with_offset(AS_OF_KEY.len())
+            })
+        })
+        .map_err(|err| err.with_offset(offset))?;
+
+    let query_expected = || format!("a V() or MATCH query after {AS_OF_KEY}<timestamp>)");
+    if command_components.len() < 3 {
+        return Err(
+            GraphDbError::parse(query_expected(), "").with_offset(offset + command.len() + 1)
+        );
+    }
+
+    // The graph name and asOf(...) stand in for the graph name, so offsets are unchanged
+    let graph = format!("{}.{}", command_components[0], command_components[1]);
+    let mut components = vec![graph.as_str()];
+    components.extend(&command_components[2..]);
+    match get_command_type(&components)? {
+        CommandType::ListVertices(filters) => Ok(Some(CommandType::AsOf(timestamp, filters))),
+        CommandType::GetVertex(id) => Ok(Some(CommandType::AsOf(
+            timestamp,
+            vec![VertexFilterCommandType::HasId(id)],
+        ))),
+        CommandType::Match(query) => Ok(Some(CommandType::MatchAsOf(timestamp, query))),
+        _ => Err(
+            GraphDbError::parse(query_expected(), command_components[2].trim())
+                .with_offset(component_offset(command_components, 2)),
+        ),
+    }
+}
+
+fn parse_graph_command(command: &str) -> Result<CommandType, GraphDbError> {
+    match command {
         // Vertex selection
         LIST_VERTICES_KEY => Ok(CommandType::ListVertices(Vec::new())),
+
+        // Graph statistics
+        STATS_KEY => Ok(CommandType::GraphStats),
+
+        LIST_TRIGGERS_KEY => Ok(CommandType::ListTriggers),
+
+        // Versioning
+        ENABLE_VERSIONING_KEY => Ok(CommandType::EnableVersioning),
+
+        DISABLE_VERSIONING_KEY => Ok(CommandType::DisableVersioning),
+
+        _ if command.starts_with(HISTORY_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let vertex_id = extract_number(HISTORY_KEY, command)?;
+            Ok(CommandType::History(vertex_id))
+        }
+
         _ if command.starts_with(GET_VERTEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
             let vertex_id = extract_number(GET_VERTEX_KEY, command);
             match vertex_id {
@@ -199,17 +797,24 @@ fn get_command_type(command_components: &Vec<&str>) -> Result<CommandType, Strin
             let edge_input = extract_string(ADD_EDGE_KEY, command)?;
             let edge_parameters: Vec<&str> = edge_input.split(",").collect();
             if edge_parameters.len() != 3 {
-                return Err(format!("Must provide label, from_id and to_id parameters"));
+                return Err(GraphDbError::parse(
+                    "label, from_id and to_id parameters",
+                    edge_input.as_str(),
+                )
+                .with_offset(ADD_EDGE_KEY.len()));
             }
 
             let label = edge_parameters[0].trim();
             let from_id_str = edge_parameters[1].trim();
             let to_id_str = edge_parameters[2].trim();
 
-            fn parse(str: &str) -> Result<usize, String> {
+            fn parse(str: &str) -> Result<usize, GraphDbError> {
                 match str.parse::<usize>() {
                     Ok(num) => Ok(num),
-                    Err(_) => Err(format!("Failed to parse value: '{str}' as int",)),
+                    Err(_) => Err(GraphDbError::TypeMismatch {
+                        expected: "int".to_string(),
+                        found: str.to_string(),
+                    }),
                 }
             }
 
@@ -225,62 +830,132 @@ fn get_command_type(command_components: &Vec<&str>) -> Result<CommandType, Strin
             ))
         }
 
-        // Catch all
-        _ => Err(format!("Unrecognized command: {}", command)),
-    }?;
+        // Edge mutation
+        _ if command.starts_with(EDIT_EDGE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let edge_id = extract_number(EDIT_EDGE_KEY, command)?;
+            Ok(CommandType::EditEdge(edge_id, Vec::new()))
+        }
 
-    // Add follow up commands
-    match command_type {
-        CommandType::ListVertices(_) => {
-            let filter_commands = parse_list_vertices_commands(command_components)?;
-            command_type = CommandType::ListVertices(filter_commands);
+        // Edge deletion
+        _ if command.starts_with(DELETE_EDGE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let edge_id = extract_number(DELETE_EDGE_KEY, command)?;
+            Ok(CommandType::RemoveEdge(edge_id))
         }
-        CommandType::AddVertex(label, _) => {
-            let mutation_commands = parse_entity_mutation_commmands(command_components)?;
-            command_type = CommandType::AddVertex(label, mutation_commands);
+
+        // Text indexes
+        _ if command.starts_with(CREATE_TEXT_INDEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let property_name = extract_string(CREATE_TEXT_INDEX_KEY, command)?;
+            Ok(CommandType::CreateTextIndex(property_name))
         }
-        CommandType::EditVertex(id, _) => {
-            let mutation_commands = parse_entity_mutation_commmands(command_components)?;
-            command_type = CommandType::EditVertex(id, mutation_commands);
+
+        _ if command.starts_with(DROP_TEXT_INDEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let property_name = extract_string(DROP_TEXT_INDEX_KEY, command)?;
+            Ok(CommandType::DropTextIndex(property_name))
         }
-        CommandType::AddEdge(_, ref mut mutation_commands) => {
-            let vertex_commands = parse_entity_mutation_commmands(command_components)?;
-            mutation_commands.push(EdgeMutationCommandType::VertexMutationCommandTypes(
-                vertex_commands,
-            ));
+
+        _ if command.starts_with(SEARCH_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (property_name, query) = extract_name_and_raw_value(SEARCH_KEY, command)?;
+            Ok(CommandType::TextSearch(property_name, query.to_string()))
         }
 
-        _ => (),
-    };
+        // Spatial indexes
+        _ if command.starts_with(CREATE_SPATIAL_INDEX_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let property_name = extract_string(CREATE_SPATIAL_INDEX_KEY, command)?;
+            Ok(CommandType::CreateSpatialIndex(property_name))
+        }
 
-    Ok(command_type)
+        _ if command.starts_with(DROP_SPATIAL_INDEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let property_name = extract_string(DROP_SPATIAL_INDEX_KEY, command)?;
+            Ok(CommandType::DropSpatialIndex(property_name))
+        }
+
+        // Vector indexes
+        _ if command.starts_with(CREATE_VECTOR_INDEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (property_name, metric) =
+                extract_name_value_pair(CREATE_VECTOR_INDEX_KEY, command)?;
+            let metric = Metric::from_name(&metric).map_err(|err| {
+                err.with_offset(
+                    command
+                        .rfind(&metric)
+                        .unwrap_or(CREATE_VECTOR_INDEX_KEY.len()),
+                )
+            })?;
+            Ok(CommandType::CreateVectorIndex(property_name, metric))
+        }
+
+        _ if command.starts_with(DROP_VECTOR_INDEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let property_name = extract_string(DROP_VECTOR_INDEX_KEY, command)?;
+            Ok(CommandType::DropVectorIndex(property_name))
+        }
+
+        // Triggers
+        _ if command.starts_with(CREATE_TRIGGER_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let trigger = parse_create_trigger_command(command)?;
+            Ok(CommandType::CreateTrigger(trigger))
+        }
+
+        _ if command.starts_with(DROP_TRIGGER_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(DROP_TRIGGER_KEY, command)?;
+            Ok(CommandType::DropTrigger(name))
+        }
+
+        // Pattern matching
+        _ if is_match_query(command) => Ok(CommandType::Match(parse_match_query(command)?)),
+
+        // Catch all
+        _ => Err(GraphDbError::parse("a graph command", command)),
+    }
 }
 
-fn extract_number(key: &str, command: &str) -> Result<usize, String> {
+fn extract_number(key: &str, command: &str) -> Result<usize, GraphDbError> {
     let stripped_command = extract_string(key, command)?;
     match stripped_command.parse::<usize>() {
         Ok(num) => Ok(num),
-        Err(_) => Err(format!(
-            "Failed to parse value: '{stripped_command}' as int for {key}<value>)",
-        )),
+        Err(_) => Err(
+            GraphDbError::parse(format!("an int for {key}<value>)"), stripped_command)
+                .with_offset(key.len()),
+        ),
     }
 }
 
-fn extract_string(key: &str, command: &str) -> Result<String, String> {
+fn extract_string(key: &str, command: &str) -> Result<String, GraphDbError> {
     // TODO: guard against commas
 
     let binding = command.replace(key, "").replace(END_COMMAND_KEY, "");
     let value = binding.trim();
     match value.is_empty() {
-        true => Err(format!("Must provide a value for: {key}<value>)")),
+        true => Err(
+            GraphDbError::parse(format!("a value for {key}<value>)"), "").with_offset(key.len()),
+        ),
         false => Ok(value.to_string()),
     }
 }
 
-fn extract_name_value_pair(key: &str, command: &str) -> Result<(String, String), String> {
+/// Like extract_string, but an empty value is allowed, e.g. out(). A value must be a single name
+fn extract_optional_string(key: &str, command: &str) -> Result<Option<String>, GraphDbError> {
+    let arguments = &command[key.len()..command.len() - END_COMMAND_KEY.len()];
+    let value = arguments.trim();
+    match value.is_empty() {
+        true => Ok(None),
+        false if value.contains([',', '(', ')']) => Err(GraphDbError::parse(
+            format!("a single name for {key}<value>)"),
+            value,
+        )
+        .with_offset(key.len() + arguments.len() - arguments.trim_start().len())),
+        false => Ok(Some(value.to_string())),
+    }
+}
+
+fn extract_name_value_pair(key: &str, command: &str) -> Result<(String, String), GraphDbError> {
     let binding = command.replace(key, "").replace(END_COMMAND_KEY, "");
     let values: Vec<&str> = binding.trim().split(",").collect();
-    let msg = format!("Invalid format for: {key}<value>). Must provide a name and value pair separated by a comma");
+    let msg = GraphDbError::parse(
+        format!("a name and value pair separated by a comma for {key}<value>)"),
+        binding.trim(),
+    )
+    .with_offset(key.len());
     match values.len() {
         2 => {
             let name = values[0].trim();
@@ -295,14 +970,110 @@ fn extract_name_value_pair(key: &str, command: &str) -> Result<(String, String),
     }
 }
 
-fn extract_graph_name(create_graph_command: &str) -> Result<String, String> {
-    let binding = create_graph_command
-        .replace(CREATE_GRAPH_KEY, END_COMMAND_KEY)
+/// Splits on the first comma only, and keeps the value exactly as written, so it may hold commas,
+/// quotes and brackets (e.g. a regex or search query)
+fn extract_name_and_raw_value<'a>(
+    key: &str,
+    command: &'a str,
+) -> Result<(String, &'a str), GraphDbError> {
+    let parameters = &command[key.len()..command.len() - END_COMMAND_KEY.len()];
+    let msg = GraphDbError::parse(
+        format!("a name and value separated by a comma for {key}<value>)"),
+        parameters.trim(),
+    )
+    .with_offset(key.len());
+
+    match parameters.split_once(',') {
+        Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim()))
+        }
+        _ => Err(msg),
+    }
+}
+
+/// A name followed by the given number of numeric parameters, e.g. hasPropertyNearest(home, 51.5, -0.12, 3)
+fn extract_name_and_numbers(
+    key: &str,
+    command: &str,
+    count: usize,
+) -> Result<(String, Vec<f64>), GraphDbError> {
+    let parameters = &command[key.len()..command.len() - END_COMMAND_KEY.len()];
+    let values: Vec<&str> = parameters.split(',').map(|value| value.trim()).collect();
+    let msg = GraphDbError::parse(
+        format!("a name followed by {count} numbers, separated by commas, for {key}<value>)"),
+        parameters.trim(),
+    )
+    .with_offset(key.len());
+
+    let name = match values.first() {
+        Some(name) if !name.is_empty() && values.len() == count + 1 => name.to_string(),
+        _ => return Err(msg),
+    };
+
+    let numbers: Vec<f64> = values[1..]
+        .iter()
+        .filter_map(|value| value.parse::<f64>().ok())
+        .collect();
+    match numbers.len() == count {
+        true => Ok((name, numbers)),
+        false => Err(msg),
+    }
+}
+
+/// Parses a vector of numbers in square brackets, separated by commas or whitespace, e.g. [0.1, 0.2]
+pub fn parse_vector(value: &str) -> Result<Vec<f32>, GraphDbError> {
+    let mismatch = || GraphDbError::TypeMismatch {
+        expected: "numbers in square brackets, e.g. [0.1, 0.2]".to_string(),
+        found: value.to_string(),
+    };
+
+    let components = value
+        .trim()
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .ok_or_else(mismatch)?;
+
+    let vector = components
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|component| !component.is_empty())
+        .map(|component| component.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| mismatch())?;
+
+    match vector.is_empty() {
+        true => Err(mismatch()),
+        false => Ok(vector),
+    }
+}
+
+fn extract_graph_name(key: &str, graph_command: &str) -> Result<String, GraphDbError> {
+    let binding = graph_command
+        .replace(key, END_COMMAND_KEY)
         .replace(END_COMMAND_KEY, "");
     let name = binding.trim();
 
     match name.is_empty() {
-        true => Err(format!("Must provide a graph name")),
+        true => Err(GraphDbError::parse("a graph name", "").with_offset(key.len())),
         false => Ok(name.to_string()),
     }
 }
+
+fn extract_graph_name_pair(
+    key: &str,
+    graph_command: &str,
+) -> Result<(String, String), GraphDbError> {
+    let binding = graph_command.replace(key, "").replace(END_COMMAND_KEY, "");
+    let names: Vec<&str> = binding.split(",").map(|name| name.trim()).collect();
+    let msg = GraphDbError::parse(
+        format!("two graph names separated by a comma for {key}<value>)"),
+        binding.trim(),
+    )
+    .with_offset(key.len());
+
+    match names[..] {
+        [first, second] if !first.is_empty() && !second.is_empty() => {
+            Ok((first.to_string(), second.to_string()))
+        }
+        _ => Err(msg),
+    }
+}
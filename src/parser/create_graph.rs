@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+
+use super::{
+    component_offset, extract_string, DIRECTED_KEY, EDGE_POLICY_KEY, END_COMMAND_KEY, OPTION_KEY,
+    TYPE_KEY,
+};
+use crate::{
+    error::GraphDbError,
+    graph::{
+        options::{EdgePolicy, GraphOptions, OptionKey, SchemaMode},
+        vector::Metric,
+        GraphType,
+    },
+};
+
+/// Options following createGraph(name), e.g. createGraph(roads).edgePolicy(simple).directed(false)
+/// or createGraph(roads).type(persistent).option(path, roads.log). Those not given keep their defaults
+pub fn parse_graph_options(command_components: &[&str]) -> Result<GraphOptions, GraphDbError> {
+    let mut options = GraphOptions::default();
+    for (i, command) in command_components.iter().enumerate().skip(1) {
+        parse_graph_option(command, &mut options)
+            .map_err(|err| err.with_offset(component_offset(command_components, i)))?;
+    }
+
+    Ok(options)
+}
+
+fn parse_graph_option(command: &str, options: &mut GraphOptions) -> Result<(), GraphDbError> {
+    let command = command.trim();
+    match command {
+        _ if command.starts_with(TYPE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(TYPE_KEY, command)?;
+            options.graph_type =
+                GraphType::from_name(&name).map_err(|err| err.with_offset(TYPE_KEY.len()))?;
+        }
+
+        _ if command.starts_with(OPTION_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let arguments = &command[OPTION_KEY.len()..command.len() - END_COMMAND_KEY.len()];
+            let (key, value) = arguments.split_once(',').ok_or_else(|| {
+                GraphDbError::parse(
+                    format!("a key and value separated by a comma for {OPTION_KEY}<key>, <value>)"),
+                    arguments.trim(),
+                )
+                .with_offset(OPTION_KEY.len())
+            })?;
+
+            let key = OptionKey::from_name(key)
+                .map_err(|err| err.with_offset(OPTION_KEY.len() + leading_spaces(key)))?;
+            let value_offset = OPTION_KEY.len() + arguments.len() - value.trim_start().len();
+            set_option(options, key, value.trim()).map_err(|err| err.with_offset(value_offset))?;
+        }
+
+        _ if command.starts_with(EDGE_POLICY_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let value = extract_string(EDGE_POLICY_KEY, command)?;
+            set_option(options, OptionKey::EdgePolicy, &value)
+                .map_err(|err| err.with_offset(EDGE_POLICY_KEY.len()))?;
+        }
+
+        _ if command.starts_with(DIRECTED_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let value = extract_string(DIRECTED_KEY, command)?;
+            set_option(options, OptionKey::Directed, &value)
+                .map_err(|err| err.with_offset(DIRECTED_KEY.len()))?;
+        }
+
+        _ => {
+            return Err(GraphDbError::parse(
+                format!(
+                    "{TYPE_KEY}<type>), {OPTION_KEY}<key>, <value>), {EDGE_POLICY_KEY}<policy>) or {DIRECTED_KEY}<true|false>)"
+                ),
+                command,
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the option from its value. Errors are positioned relative to the value
+fn set_option(options: &mut GraphOptions, key: OptionKey, value: &str) -> Result<(), GraphDbError> {
+    if value.is_empty() {
+        return Err(GraphDbError::parse(
+            format!("a value for {}", key.as_str()),
+            "",
+        ));
+    }
+
+    match key {
+        OptionKey::Path => options.path = Some(PathBuf::from(value)),
+        OptionKey::Directed => {
+            options.directed = value.parse::<bool>().map_err(|_| {
+                GraphDbError::parse(format!("true or false for {}", key.as_str()), value)
+            })?
+        }
+        OptionKey::EdgePolicy => options.edge_policy = EdgePolicy::from_name(value)?,
+        OptionKey::Schema => options.schema = SchemaMode::from_name(value)?,
+        OptionKey::TextIndex => options.text_indexes.push(value.to_string()),
+        OptionKey::SpatialIndex => options.spatial_indexes.push(value.to_string()),
+        OptionKey::VectorIndex => {
+            let (property_name, metric) = value.split_once(',').ok_or_else(|| {
+                GraphDbError::parse(
+                    "a property name and metric separated by a comma, e.g. embedding, cosine",
+                    value,
+                )
+            })?;
+            let metric = Metric::from_name(metric)
+                .map_err(|err| err.with_offset(property_name.len() + 1 + leading_spaces(metric)))?;
+            options
+                .vector_indexes
+                .push((property_name.trim().to_string(), metric));
+        }
+    }
+
+    options.given.push(key);
+    Ok(())
+}
+
+fn leading_spaces(text: &str) -> usize {
+    text.len() - text.trim_start().len()
+}
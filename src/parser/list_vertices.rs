@@ -1,58 +1,432 @@
+use regex::Regex;
+
 use crate::{
-    executor::VertexFilterCommandType,
-    parser::{extract_name_value_pair, extract_string},
+    error::GraphDbError,
+    executor::{EdgeStep, EdgeStepEnd, PathFilter, Repeat, TextPredicate, VertexFilterCommandType},
+    graph::{
+        adjacency::Direction,
+        spatial::{BoundingBox, Point},
+        vector::Metric,
+    },
+    parser::{
+        component_offset, extract_name_and_numbers, extract_name_and_raw_value,
+        extract_name_value_pair, extract_number, extract_optional_string, extract_string,
+        parameter_name, parse_vector, split_commands,
+    },
 };
 
 use super::{
-    END_COMMAND_KEY, HAS_LABEL_KEY, HAS_PROPERTY_KEY, HAS_PROPERTY_LIKE_KEY,
-    HAS_PROPERTY_VALUE_KEY, VALUES_KEY,
+    CYCLIC_PATH_KEY, EMIT_KEY, END_COMMAND_KEY, HAS_EXTERNAL_ID_KEY, HAS_LABEL_KEY,
+    HAS_PROPERTY_ENDS_WITH_IGNORE_CASE_KEY, HAS_PROPERTY_ENDS_WITH_KEY, HAS_PROPERTY_KEY,
+    HAS_PROPERTY_LIKE_IGNORE_CASE_KEY, HAS_PROPERTY_LIKE_KEY, HAS_PROPERTY_NEAREST_KEY,
+    HAS_PROPERTY_REGEX_KEY, HAS_PROPERTY_STARTS_WITH_IGNORE_CASE_KEY, HAS_PROPERTY_STARTS_WITH_KEY,
+    HAS_PROPERTY_VALUE_IGNORE_CASE_KEY, HAS_PROPERTY_VALUE_KEY, HAS_PROPERTY_WITHIN_BOX_KEY,
+    HAS_PROPERTY_WITHIN_RADIUS_KEY, IN_EDGES_KEY, IN_KEY, IN_VERTEX_KEY, NEAREST_KEY,
+    OTHER_VERTEX_KEY, OUT_EDGES_KEY, OUT_KEY, OUT_VERTEX_KEY, REPEAT_KEY, SIMPLE_PATH_KEY,
+    TIMES_KEY, UNTIL_KEY, VALUES_KEY,
 };
 
 pub fn parse_list_vertices_commands(
-    commands: &Vec<&str>,
-) -> Result<Vec<VertexFilterCommandType>, String> {
+    commands: &[&str],
+) -> Result<Vec<VertexFilterCommandType>, GraphDbError> {
     // First command is graph, second is vertex. So, vertex filters occur from the third command
     if commands.len() < 3 {
         return Ok(Vec::new());
     }
 
+    let offsets: Vec<usize> = (2..commands.len())
+        .map(|i| component_offset(commands, i))
+        .collect();
+    parse_filter_chain(&commands[2..], &offsets)
+}
+
+/// Filters in the order written. Modifiers, e.g. times(3), change the repeat(...) before them
+fn parse_filter_chain(
+    commands: &[&str],
+    offsets: &[usize],
+) -> Result<Vec<VertexFilterCommandType>, GraphDbError> {
     let mut vertex_filter_commands = Vec::new();
-    for command in commands.iter().skip(2) {
-        match command {
-            _ if command.starts_with(HAS_LABEL_KEY) && command.ends_with(END_COMMAND_KEY) => {
-                let name = extract_string(HAS_LABEL_KEY, command)?;
-                vertex_filter_commands.push(VertexFilterCommandType::HasName(name));
-            }
+    let mut open_edge_step = None;
+    for (command, offset) in commands.iter().zip(offsets) {
+        parse_filter_or_modifier(&mut vertex_filter_commands, command.trim())
+            .map_err(|err| err.with_offset(*offset))?;
 
-            _ if command.starts_with(HAS_PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
-                let name = extract_string(HAS_PROPERTY_KEY, command)?;
-                vertex_filter_commands.push(VertexFilterCommandType::HasProperty(name));
+        open_edge_step = match vertex_filter_commands.last() {
+            Some(VertexFilterCommandType::Edges(EdgeStep { end: None, .. })) => {
+                open_edge_step.or(Some((command.trim(), *offset)))
             }
+            _ => None,
+        };
+    }
+
+    match open_edge_step {
+        Some((command, offset)) => Err(GraphDbError::parse(
+            "inV(), outV() or otherV() to end the edge step",
+            command,
+        )
+        .with_offset(offset)),
+        None => Ok(vertex_filter_commands),
+    }
+}
+
+fn parse_filter_or_modifier(
+    filters: &mut Vec<VertexFilterCommandType>,
+    command: &str,
+) -> Result<(), GraphDbError> {
+    if let Some(VertexFilterCommandType::Edges(step)) = filters.last_mut() {
+        if step.end.is_none() {
+            return parse_edge_step_command(step, command);
+        }
+    }
+
+    let repeat = match filters.last_mut() {
+        Some(VertexFilterCommandType::Repeat(repeat)) => Some(repeat),
+        _ => None,
+    };
+    let is_modifier = (command.starts_with(TIMES_KEY) || command.starts_with(UNTIL_KEY))
+        && command.ends_with(END_COMMAND_KEY)
+        || [EMIT_KEY, SIMPLE_PATH_KEY, CYCLIC_PATH_KEY].contains(&command);
+
+    let repeat = match (is_modifier, repeat) {
+        (false, _) => {
+            filters.push(parse_list_vertices_command(command)?);
+            return Ok(());
+        }
+        (true, Some(repeat)) => repeat,
+        (true, None) => return Err(GraphDbError::parse("repeat(...) before it", command)),
+    };
+
+    match command {
+        _ if command.starts_with(TIMES_KEY) => {
+            repeat.times = Some(extract_number(TIMES_KEY, command)?)
+        }
+        _ if command.starts_with(UNTIL_KEY) => {
+            repeat.until = Some(parse_nested_filters(UNTIL_KEY, command)?)
+        }
+        _ if command == EMIT_KEY => repeat.emit = true,
+        _ if command == SIMPLE_PATH_KEY => repeat.paths = PathFilter::Simple,
+        _ => repeat.paths = PathFilter::Cyclic,
+    }
+
+    Ok(())
+}
 
-            _ if command.starts_with(HAS_PROPERTY_VALUE_KEY)
-                && command.ends_with(END_COMMAND_KEY) =>
-            {
-                let (name, value) = extract_name_value_pair(HAS_PROPERTY_VALUE_KEY, command)?;
-                vertex_filter_commands.push(VertexFilterCommandType::HasPropertyValue(name, value));
+/// Filters on the edges of an edge step, or the vertex that ends it
+fn parse_edge_step_command(step: &mut EdgeStep, command: &str) -> Result<(), GraphDbError> {
+    match command {
+        _ if command == IN_VERTEX_KEY => step.end = Some(EdgeStepEnd::In),
+        _ if command == OUT_VERTEX_KEY => step.end = Some(EdgeStepEnd::Out),
+        _ if command == OTHER_VERTEX_KEY => step.end = Some(EdgeStepEnd::Other),
+        _ => match parse_list_vertices_command(command) {
+            Ok(
+                filter @ (VertexFilterCommandType::HasName(_)
+                | VertexFilterCommandType::HasProperty(_)
+                | VertexFilterCommandType::HasPropertyValue(..)
+                | VertexFilterCommandType::HasPropertyLike(..)
+                | VertexFilterCommandType::HasPropertyText(..)
+                | VertexFilterCommandType::HasPropertyRegex(..)
+                | VertexFilterCommandType::HasPropertyWithinRadius(..)
+                | VertexFilterCommandType::HasPropertyWithinBox(..)),
+            ) => step.filters.push(filter),
+            Ok(_) | Err(GraphDbError::Parse { .. }) => {
+                return Err(GraphDbError::parse(
+                    "an edge filter, or inV(), outV() or otherV()",
+                    command,
+                ))
             }
+            Err(err) => return Err(err),
+        },
+    }
+
+    Ok(())
+}
+
+/// The filters inside repeat(...) or until(...), e.g. out(reportsTo).hasLabel(manager). They're
+/// run from one vertex at a time, so can't select values
+fn parse_nested_filters(
+    key: &str,
+    command: &str,
+) -> Result<Vec<VertexFilterCommandType>, GraphDbError> {
+    let inner = &command[key.len()..command.len() - END_COMMAND_KEY.len()];
+    let commands = split_commands(inner);
+    let offsets: Vec<usize> = (0..commands.len())
+        .map(|i| key.len() + component_offset(&commands, i))
+        .collect();
+
+    let filters = parse_filter_chain(&commands, &offsets)?;
+    match filters
+        .iter()
+        .position(|filter| matches!(filter, VertexFilterCommandType::Values(_)))
+    {
+        Some(i) => Err(GraphDbError::parse(
+            format!("a vertex step inside {key}...)"),
+            commands[i].trim(),
+        )
+        .with_offset(offsets[i])),
+        None => Ok(filters),
+    }
+}
+
+fn parse_list_vertices_command(command: &str) -> Result<VertexFilterCommandType, GraphDbError> {
+    match command {
+        _ if command.starts_with(HAS_LABEL_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(HAS_LABEL_KEY, command)?;
+            Ok(VertexFilterCommandType::HasName(name))
+        }
+
+        _ if command.starts_with(HAS_EXTERNAL_ID_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let external_id = extract_string(HAS_EXTERNAL_ID_KEY, command)?;
+            Ok(VertexFilterCommandType::HasExternalId(external_id))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(HAS_PROPERTY_KEY, command)?;
+            Ok(VertexFilterCommandType::HasProperty(name))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_VALUE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (name, value) = extract_name_value_pair(HAS_PROPERTY_VALUE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyValue(name, value))
+        }
 
-            _ if command.starts_with(HAS_PROPERTY_LIKE_KEY)
-                && command.ends_with(END_COMMAND_KEY) =>
-            {
-                let (name, search_term) = extract_name_value_pair(HAS_PROPERTY_LIKE_KEY, command)?;
-                vertex_filter_commands
-                    .push(VertexFilterCommandType::HasPropertyLike(name, search_term));
+        _ if command.starts_with(HAS_PROPERTY_LIKE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (name, search_term) = extract_name_value_pair(HAS_PROPERTY_LIKE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyLike(name, search_term))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_STARTS_WITH_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, prefix) = extract_name_value_pair(HAS_PROPERTY_STARTS_WITH_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::StartsWith(prefix),
+                false,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_ENDS_WITH_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, suffix) = extract_name_value_pair(HAS_PROPERTY_ENDS_WITH_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::EndsWith(suffix),
+                false,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_VALUE_IGNORE_CASE_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, value) =
+                extract_name_value_pair(HAS_PROPERTY_VALUE_IGNORE_CASE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::Equals(fold_case(value)),
+                true,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_LIKE_IGNORE_CASE_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, search_term) =
+                extract_name_value_pair(HAS_PROPERTY_LIKE_IGNORE_CASE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::Contains(fold_case(search_term)),
+                true,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_STARTS_WITH_IGNORE_CASE_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, prefix) =
+                extract_name_value_pair(HAS_PROPERTY_STARTS_WITH_IGNORE_CASE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::StartsWith(fold_case(prefix)),
+                true,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_ENDS_WITH_IGNORE_CASE_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, suffix) =
+                extract_name_value_pair(HAS_PROPERTY_ENDS_WITH_IGNORE_CASE_KEY, command)?;
+            Ok(VertexFilterCommandType::HasPropertyText(
+                name,
+                TextPredicate::EndsWith(fold_case(suffix)),
+                true,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_REGEX_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (name, pattern) = extract_name_and_raw_value(HAS_PROPERTY_REGEX_KEY, command)?;
+            match Regex::new(pattern) {
+                Ok(regex) => Ok(VertexFilterCommandType::HasPropertyRegex(name, regex)),
+                Err(err) => Err(GraphDbError::parse(
+                    // The error's last line is its reason, e.g. "error: unclosed group"
+                    format!(
+                        "a valid regular expression ({})",
+                        err.to_string().lines().last().unwrap_or_default().trim()
+                    ),
+                    pattern,
+                )
+                .with_offset(
+                    command
+                        .find(pattern)
+                        .unwrap_or(HAS_PROPERTY_REGEX_KEY.len()),
+                )),
             }
+        }
 
-            _ if command.starts_with(VALUES_KEY) && command.ends_with(END_COMMAND_KEY) => {
-                // TODO: ability to select multiple properties
-                let name = extract_string(VALUES_KEY, command)?;
-                vertex_filter_commands.push(VertexFilterCommandType::Values(name));
+        _ if command.starts_with(HAS_PROPERTY_WITHIN_RADIUS_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, numbers) =
+                extract_name_and_numbers(HAS_PROPERTY_WITHIN_RADIUS_KEY, command, 3)?;
+            let center = Point::new(numbers[0], numbers[1])?;
+            Ok(VertexFilterCommandType::HasPropertyWithinRadius(
+                name, center, numbers[2],
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_WITHIN_BOX_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, numbers) =
+                extract_name_and_numbers(HAS_PROPERTY_WITHIN_BOX_KEY, command, 4)?;
+            let bounding_box = BoundingBox::new(
+                Point::new(numbers[0], numbers[1])?,
+                Point::new(numbers[2], numbers[3])?,
+            )?;
+            Ok(VertexFilterCommandType::HasPropertyWithinBox(
+                name,
+                bounding_box,
+            ))
+        }
+
+        _ if command.starts_with(HAS_PROPERTY_NEAREST_KEY)
+            && command.ends_with(END_COMMAND_KEY) =>
+        {
+            let (name, numbers) = extract_name_and_numbers(HAS_PROPERTY_NEAREST_KEY, command, 3)?;
+            let center = Point::new(numbers[0], numbers[1])?;
+            if numbers[2] < 0.0 || numbers[2].fract() != 0.0 {
+                return Err(GraphDbError::TypeMismatch {
+                    expected: "a whole number of vertices".to_string(),
+                    found: numbers[2].to_string(),
+                });
             }
 
-            _ => return Err(format!("Unrecognized vertex filter command: {}", command)),
+            Ok(VertexFilterCommandType::HasPropertyNearest(
+                name,
+                center,
+                numbers[2] as usize,
+            ))
+        }
+
+        _ if command.starts_with(NEAREST_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            parse_nearest_command(command)
+        }
+
+        _ if command.starts_with(OUT_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let label = extract_optional_string(OUT_KEY, command)?;
+            Ok(VertexFilterCommandType::Out(label))
+        }
+
+        _ if command.starts_with(IN_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let label = extract_optional_string(IN_KEY, command)?;
+            Ok(VertexFilterCommandType::In(label))
         }
+
+        _ if command.starts_with(VALUES_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            // TODO: ability to select multiple properties
+            let name = extract_string(VALUES_KEY, command)?;
+            Ok(VertexFilterCommandType::Values(name))
+        }
+
+        _ if command.starts_with(OUT_EDGES_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            Ok(VertexFilterCommandType::Edges(EdgeStep {
+                direction: Direction::Out,
+                label: extract_optional_string(OUT_EDGES_KEY, command)?,
+                filters: Vec::new(),
+                end: None,
+            }))
+        }
+
+        _ if command.starts_with(IN_EDGES_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            Ok(VertexFilterCommandType::Edges(EdgeStep {
+                direction: Direction::In,
+                label: extract_optional_string(IN_EDGES_KEY, command)?,
+                filters: Vec::new(),
+                end: None,
+            }))
+        }
+
+        _ if command.starts_with(REPEAT_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            Ok(VertexFilterCommandType::Repeat(Repeat {
+                steps: parse_nested_filters(REPEAT_KEY, command)?,
+                times: None,
+                until: None,
+                emit: false,
+                paths: PathFilter::Any,
+            }))
+        }
+
+        _ => Err(GraphDbError::parse("a vertex filter command", command)),
     }
+}
 
-    Ok(vertex_filter_commands)
+/// nearest(<name>, [<numbers>], <k>, <metric>). The vector holds commas, so is found by its brackets
+fn parse_nearest_command(command: &str) -> Result<VertexFilterCommandType, GraphDbError> {
+    let parameters = &command[NEAREST_KEY.len()..command.len() - END_COMMAND_KEY.len()];
+    let msg = GraphDbError::parse(
+        format!("a name, [vector], k and metric for {NEAREST_KEY}<value>)"),
+        parameters.trim(),
+    )
+    .with_offset(NEAREST_KEY.len());
+
+    let (start, end) = match (parameters.find('['), parameters.rfind(']')) {
+        (Some(start), Some(end)) if start < end => (start, end + 1),
+        _ => return Err(msg),
+    };
+
+    let name = parameters[..start].trim().trim_end_matches(',').trim();
+    let vector = parse_vector(&parameters[start..end])?;
+    let rest: Vec<&str> = parameters[end..]
+        .split(',')
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .collect();
+
+    match (name.is_empty(), &rest[..]) {
+        (false, [k, metric]) => {
+            let k = k.parse::<usize>().map_err(|_| GraphDbError::TypeMismatch {
+                expected: "a whole number of vertices".to_string(),
+                found: k.to_string(),
+            })?;
+            let metric = Metric::from_name(metric)?;
+
+            Ok(VertexFilterCommandType::Nearest(
+                name.to_string(),
+                vector,
+                k,
+                metric,
+            ))
+        }
+        _ => Err(msg),
+    }
+}
+
+/// Search terms are lower cased when ignoring case. Placeholders keep their name as written, and
+/// their bound values are lower cased instead
+fn fold_case(term: String) -> String {
+    match parameter_name(&term) {
+        Some(_) => term,
+        None => term.to_lowercase(),
+    }
 }
@@ -1,54 +1,145 @@
-use super::{extract_string, ValidTypes, END_COMMAND_KEY, PROPERTY_KEY, REMOVE_PROPERTY_KEY};
+use super::{
+    component_offset, extract_number, extract_string, parameter_name, parse_vector, ValidTypes,
+    END_COMMAND_KEY, EXTERNAL_ID_KEY, FROM_KEY, PROPERTY_KEY, REMOVE_PROPERTY_KEY, TO_KEY,
+};
 use crate::{
-    executor::VertexMutationCommandType,
-    graph::property::{Property, PropertyValue},
+    error::GraphDbError,
+    executor::{EdgeMutationCommandType, VertexMutationCommandType},
+    graph::{
+        property::{Property, PropertyValue},
+        spatial::Point,
+    },
 };
 
 pub fn parse_entity_mutation_commmands(
-    commands: &Vec<&str>,
-) -> Result<Vec<VertexMutationCommandType>, String> {
+    commands: &[&str],
+) -> Result<Vec<VertexMutationCommandType>, GraphDbError> {
     // First command is graph, second is entity. So, entity mutations occur from the third command
     if commands.len() < 3 {
         return Ok(Vec::new());
     }
 
     let mut vertex_mutation_commands = Vec::new();
-    for command in commands.iter().skip(2) {
-        match command {
-            _ if command.starts_with(PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
-                let property = parse_add_vertex_property_command(command)?;
-                let vertex_mutation_command = VertexMutationCommandType::Property(property);
-                vertex_mutation_commands.push(vertex_mutation_command);
+    for (i, command) in commands.iter().enumerate().skip(2) {
+        let vertex_mutation_command = parse_entity_mutation_command(command)
+            .map_err(|err| err.with_offset(component_offset(commands, i)))?;
+        vertex_mutation_commands.push(vertex_mutation_command);
+    }
+
+    Ok(vertex_mutation_commands)
+}
+
+/// Commands following editE(id). from(id) and to(id) move the edge, and the rest change its
+/// properties as they would a vertex's
+pub fn parse_edge_mutation_commands(
+    commands: &[&str],
+) -> Result<Vec<EdgeMutationCommandType>, GraphDbError> {
+    let mut edge_mutation_commands = Vec::new();
+    let mut vertex_mutation_commands = Vec::new();
+    for (i, command) in commands.iter().enumerate().skip(2) {
+        let offset = component_offset(commands, i);
+        match *command {
+            _ if command.starts_with(FROM_KEY) && command.ends_with(END_COMMAND_KEY) => {
+                let id =
+                    extract_number(FROM_KEY, command).map_err(|err| err.with_offset(offset))?;
+                edge_mutation_commands.push(EdgeMutationCommandType::FromVertex(id));
             }
 
-            _ if command.starts_with(REMOVE_PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
-                let property_name = extract_string(REMOVE_PROPERTY_KEY, command)?;
-                let vertex_mutation_command =
-                    VertexMutationCommandType::RemoveProperty(property_name);
+            _ if command.starts_with(TO_KEY) && command.ends_with(END_COMMAND_KEY) => {
+                let id = extract_number(TO_KEY, command).map_err(|err| err.with_offset(offset))?;
+                edge_mutation_commands.push(EdgeMutationCommandType::ToVertex(id));
+            }
+
+            _ => {
+                let vertex_mutation_command = parse_entity_mutation_command(command)
+                    .map_err(|err| err.with_offset(offset))?;
                 vertex_mutation_commands.push(vertex_mutation_command);
             }
+        }
+    }
+    edge_mutation_commands.push(EdgeMutationCommandType::VertexMutationCommandTypes(
+        vertex_mutation_commands,
+    ));
+
+    Ok(edge_mutation_commands)
+}
+
+pub fn parse_entity_mutation_command(
+    command: &str,
+) -> Result<VertexMutationCommandType, GraphDbError> {
+    match command {
+        _ if command.starts_with(PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            if let Some(mutation) = parse_property_parameter_command(command)? {
+                return Ok(mutation);
+            }
 
-            _ => return Err(format!("Unrecognized vertex mutation command: {}", command)),
+            let property = parse_add_vertex_property_command(command)?;
+            Ok(VertexMutationCommandType::Property(property))
         }
+
+        _ if command.starts_with(REMOVE_PROPERTY_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let property_name = extract_string(REMOVE_PROPERTY_KEY, command)?;
+            Ok(VertexMutationCommandType::RemoveProperty(property_name))
+        }
+
+        _ if command.starts_with(EXTERNAL_ID_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let external_id = extract_string(EXTERNAL_ID_KEY, command)?;
+            Ok(VertexMutationCommandType::ExternalId(external_id))
+        }
+
+        _ => Err(GraphDbError::parse("a vertex mutation command", command)),
     }
+}
 
-    Ok(vertex_mutation_commands)
+/// A property whose value is a placeholder, e.g. property(age, $age). The type comes from the
+/// bound value, and must match the type if one is given, e.g. property(age, $age, int32)
+fn parse_property_parameter_command(
+    command: &str,
+) -> Result<Option<VertexMutationCommandType>, GraphDbError> {
+    let arguments = &command[PROPERTY_KEY.len()..command.len() - END_COMMAND_KEY.len()];
+    let components: Vec<&str> = arguments.split(',').map(str::trim).collect();
+
+    let parameter = match components.get(1).and_then(|value| parameter_name(value)) {
+        Some(parameter) if components.len() <= 3 => parameter,
+        _ => return Ok(None),
+    };
+    if components[0].is_empty() {
+        return Err(GraphDbError::parse("a property name", "").with_offset(PROPERTY_KEY.len()));
+    }
+    let value_type = match components.get(2) {
+        Some(value_type) => Some(
+            ValidTypes::from_name(value_type).map_err(|err| err.with_offset(PROPERTY_KEY.len()))?,
+        ),
+        None => None,
+    };
+
+    Ok(Some(VertexMutationCommandType::PropertyParameter(
+        components[0].to_string(),
+        parameter.to_string(),
+        value_type,
+    )))
 }
 
-fn parse_add_vertex_property_command(command: &str) -> Result<Property, String> {
+fn parse_add_vertex_property_command(command: &str) -> Result<Property, GraphDbError> {
     let stripped_command = command
         .replace(PROPERTY_KEY, "")
         .replace(END_COMMAND_KEY, "");
     let stripped_command_components: Vec<&str> = stripped_command.split(",").collect();
 
-    // Property name, value and type
-    if stripped_command_components.len() != 3 {
-        return Err(format!("Parsed vertex command components as '{:?}'. This is invalid, must have a property name, value, and value type", stripped_command_components));
+    // Property name, value and type. The value may itself hold commas, e.g. a point
+    if stripped_command_components.len() < 3 {
+        return Err(GraphDbError::parse(
+            "a property name, value, and value type",
+            stripped_command.trim(),
+        )
+        .with_offset(PROPERTY_KEY.len()));
     }
 
     let property_name = stripped_command_components[0].trim();
-    let property_value_str = stripped_command_components[1].trim();
-    let property_type_str = stripped_command_components[2].trim();
+    let last = stripped_command_components.len() - 1;
+    let property_value_str = &stripped_command_components[1..last].join(",");
+    let property_value_str = property_value_str.trim();
+    let property_type_str = stripped_command_components[last].trim();
 
     // Validate value and type
     let property_value = match property_type_str {
@@ -56,11 +147,10 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<bool>() {
                 Ok(value) => PropertyValue::Boolean(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::Boolean.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::Boolean.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
@@ -69,11 +159,10 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<i32>() {
                 Ok(value) => PropertyValue::Int32(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::Int32.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::Int32.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
@@ -81,11 +170,10 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<i64>() {
                 Ok(value) => PropertyValue::Int64(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::Int64.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::Int64.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
@@ -93,11 +181,10 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<f32>() {
                 Ok(value) => PropertyValue::Float32(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::Float32.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::Float32.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
@@ -105,11 +192,10 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<f64>() {
                 Ok(value) => PropertyValue::Float64(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::Float64.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::Float64.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
@@ -121,15 +207,26 @@ fn parse_add_vertex_property_command(command: &str) -> Result<Property, String>
             match property_value_str.parse::<i64>() {
                 Ok(value) => PropertyValue::DateTime(value),
                 Err(_) => {
-                    return Err(format!(
-                        "Failed to parse value: {} as {}",
-                        property_value_str,
-                        ValidTypes::DateTime.as_str()
-                    ))
+                    return Err(GraphDbError::TypeMismatch {
+                        expected: ValidTypes::DateTime.as_str().to_string(),
+                        found: property_value_str.to_string(),
+                    })
                 }
             }
         }
-        _ => return Err(format!("Unrecognized type: {}", property_type_str)),
+        _ if property_type_str == ValidTypes::Point.as_str() => {
+            PropertyValue::Point(property_value_str.parse::<Point>()?)
+        }
+        _ if property_type_str == ValidTypes::Vector.as_str() => {
+            PropertyValue::Vector(parse_vector(property_value_str)?)
+        }
+        _ => {
+            let type_component = stripped_command_components[last];
+            let type_offset = stripped_command.len() - type_component.len()
+                + (type_component.len() - type_component.trim_start().len());
+            return Err(GraphDbError::parse("a property type", property_type_str)
+                .with_offset(PROPERTY_KEY.len() + type_offset));
+        }
     };
 
     Ok(Property {
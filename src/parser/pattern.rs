@@ -0,0 +1,538 @@
+use crate::{
+    error::GraphDbError,
+    executor::pattern::{
+        Comparison, ElementPattern, MatchClause, Operand, PathPattern, PatternDirection,
+        PatternQuery, Predicate, ReturnItem,
+    },
+    graph::property::PropertyValue,
+};
+
+const MATCH_KEYWORD: &str = "MATCH";
+const OPTIONAL_KEYWORD: &str = "OPTIONAL";
+const WHERE_KEYWORD: &str = "WHERE";
+const RETURN_KEYWORD: &str = "RETURN";
+const LIMIT_KEYWORD: &str = "LIMIT";
+const AS_KEYWORD: &str = "AS";
+const AND_KEYWORD: &str = "AND";
+const OR_KEYWORD: &str = "OR";
+const NOT_KEYWORD: &str = "NOT";
+const IS_KEYWORD: &str = "IS";
+const NULL_KEYWORD: &str = "NULL";
+const TRUE_KEYWORD: &str = "TRUE";
+const FALSE_KEYWORD: &str = "FALSE";
+
+/// Longer symbols first, so <= isn't read as < followed by =
+const SYMBOLS: [&str; 18] = [
+    "<>", "!=", "<=", ">=", "(", ")", "[", "]", "{", "}", ":", ",", ".", "-", "<", ">", "=", "*",
+];
+/// Keywords that can't be used as variable names
+const RESERVED_KEYWORDS: [&str; 10] = [
+    MATCH_KEYWORD,
+    OPTIONAL_KEYWORD,
+    WHERE_KEYWORD,
+    RETURN_KEYWORD,
+    LIMIT_KEYWORD,
+    AS_KEYWORD,
+    AND_KEYWORD,
+    OR_KEYWORD,
+    NOT_KEYWORD,
+    IS_KEYWORD,
+];
+const COMPARISONS: [&str; 7] = ["=", "<>", "!=", "<", "<=", ">", ">="];
+
+/// Whether the command is a MATCH query. Keywords may be upper or lower case
+pub fn is_match_query(command: &str) -> bool {
+    let first_word = command
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default();
+
+    first_word.eq_ignore_ascii_case(MATCH_KEYWORD)
+        || first_word.eq_ignore_ascii_case(OPTIONAL_KEYWORD)
+}
+
+/// MATCH <pattern>, ... [WHERE <predicate>] [OPTIONAL MATCH ...] RETURN <items> [LIMIT <n>]
+pub fn parse_match_query(query: &str) -> Result<PatternQuery, GraphDbError> {
+    let mut parser = PatternParser {
+        query,
+        tokens: tokenize(query)?,
+        position: 0,
+    };
+
+    let mut clauses = Vec::new();
+    loop {
+        let optional = parser.eat_keyword(OPTIONAL_KEYWORD);
+        if !parser.eat_keyword(MATCH_KEYWORD) {
+            if optional || clauses.is_empty() {
+                return Err(parser.error(MATCH_KEYWORD));
+            }
+            break;
+        }
+
+        let mut paths = vec![parser.path()?];
+        while parser.eat_symbol(",") {
+            paths.push(parser.path()?);
+        }
+
+        let predicate = match parser.eat_keyword(WHERE_KEYWORD) {
+            true => Some(parser.or()?),
+            false => None,
+        };
+
+        clauses.push(MatchClause {
+            optional,
+            paths,
+            predicate,
+        });
+    }
+
+    if !parser.eat_keyword(RETURN_KEYWORD) {
+        return Err(parser.error("MATCH, OPTIONAL MATCH, WHERE or RETURN"));
+    }
+
+    let mut returns = Vec::new();
+    if !parser.eat_symbol("*") {
+        returns.push(parser.return_item()?);
+        while parser.eat_symbol(",") {
+            returns.push(parser.return_item()?);
+        }
+    }
+
+    let limit = match parser.eat_keyword(LIMIT_KEYWORD) {
+        true => match parser.peek() {
+            Some(Token::Number(number)) => match number.parse::<usize>() {
+                Ok(limit) => {
+                    parser.position += 1;
+                    Some(limit)
+                }
+                Err(_) => return Err(parser.error("a whole number for LIMIT")),
+            },
+            _ => return Err(parser.error("a whole number for LIMIT")),
+        },
+        false => None,
+    };
+
+    if parser.peek().is_some() {
+        return Err(parser.error("the end of the query"));
+    }
+
+    Ok(PatternQuery {
+        clauses,
+        returns,
+        limit,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String), // a keyword or name
+    Text(String), // a quoted string, without its quotes
+    Number(String),
+    Symbol(&'static str),
+    Parameter(String), // a placeholder's name, without its $
+}
+
+/// Tokens, along with their offsets and lengths in the query
+fn tokenize(query: &str) -> Result<Vec<(Token, usize, usize)>, GraphDbError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => (),
+
+            // Quoted strings, where a backslash escapes the next character
+            '\'' | '"' => {
+                let mut text = String::new();
+                let mut end = None;
+                while let Some((i, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                text.push(escaped);
+                            }
+                        }
+                        _ if next == c => {
+                            end = Some(i + 1);
+                            break;
+                        }
+                        _ => text.push(next),
+                    }
+                }
+
+                match end {
+                    Some(end) => tokens.push((Token::Text(text), offset, end - offset)),
+                    None => {
+                        return Err(GraphDbError::parse("a closing quote", &query[offset..])
+                            .with_offset(offset))
+                    }
+                }
+            }
+
+            _ if c.is_ascii_digit() => {
+                let mut end = offset + 1;
+                while let Some((i, next)) = chars.peek().copied() {
+                    // A dot only continues the number when a digit follows it
+                    let continues = next.is_ascii_digit()
+                        || (next == '.'
+                            && query[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+                    if !continues {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                tokens.push((
+                    Token::Number(query[offset..end].to_string()),
+                    offset,
+                    end - offset,
+                ));
+            }
+
+            _ if c.is_alphanumeric() || c == '_' => {
+                let mut end = offset + c.len_utf8();
+                while let Some((i, next)) = chars.peek().copied() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                tokens.push((
+                    Token::Word(query[offset..end].to_string()),
+                    offset,
+                    end - offset,
+                ));
+            }
+
+            // A placeholder, e.g. $name, bound to a value before the query is run
+            '$' if chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_ascii_alphabetic() || *next == '_') =>
+            {
+                let mut end = offset + 1;
+                while let Some((i, next)) = chars.peek().copied() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                tokens.push((
+                    Token::Parameter(query[offset + 1..end].to_string()),
+                    offset,
+                    end - offset,
+                ));
+            }
+
+            _ => match SYMBOLS
+                .iter()
+                .find(|symbol| query[offset..].starts_with(*symbol))
+            {
+                Some(symbol) => {
+                    for _ in 1..symbol.len() {
+                        chars.next();
+                    }
+                    tokens.push((Token::Symbol(symbol), offset, symbol.len()));
+                }
+                None => {
+                    return Err(GraphDbError::parse(
+                        "a pattern, keyword, name or value",
+                        c.to_string(),
+                    )
+                    .with_offset(offset))
+                }
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct PatternParser<'a> {
+    query: &'a str,
+    tokens: Vec<(Token, usize, usize)>, // token, offset, length
+    position: usize,
+}
+
+impl PatternParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _, _)| token)
+    }
+
+    /// An error at the current token, or at the end of the query when there are none left
+    fn error(&self, expected: &str) -> GraphDbError {
+        match self.tokens.get(self.position) {
+            Some((_, offset, length)) => {
+                GraphDbError::parse(expected, &self.query[*offset..offset + length])
+                    .with_offset(*offset)
+            }
+            None => GraphDbError::parse(expected, "").with_offset(self.query.len()),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(found)) if *found == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), GraphDbError> {
+        match self.eat_symbol(symbol) {
+            true => Ok(()),
+            false => Err(self.error(&format!("'{}'", symbol))),
+        }
+    }
+
+    fn name(&mut self, expected: &str) -> Result<String, GraphDbError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.to_string();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    /// (a)-[:knows]->(b)<-[:works_at]-(c)
+    fn path(&mut self) -> Result<PathPattern, GraphDbError> {
+        let mut path = PathPattern {
+            nodes: vec![self.node()?],
+            relationships: Vec::new(),
+        };
+
+        while matches!(self.peek(), Some(Token::Symbol("-" | "<"))) {
+            path.relationships.push(self.relationship()?);
+            path.nodes.push(self.node()?);
+        }
+
+        Ok(path)
+    }
+
+    fn node(&mut self) -> Result<ElementPattern, GraphDbError> {
+        self.expect_symbol("(")?;
+        let node = self.element()?;
+        self.expect_symbol(")")?;
+
+        Ok(node)
+    }
+
+    /// -[r:label]->, <-[r:label]-, -[r:label]-, or without brackets, e.g. -->
+    fn relationship(&mut self) -> Result<(ElementPattern, PatternDirection), GraphDbError> {
+        let left = self.eat_symbol("<");
+        self.expect_symbol("-")?;
+
+        let mut relationship = ElementPattern::default();
+        if self.eat_symbol("[") {
+            relationship = self.element()?;
+            self.expect_symbol("]")?;
+        }
+
+        self.expect_symbol("-")?;
+        let right = self.peek() == Some(&Token::Symbol(">"));
+
+        let direction = match (left, right) {
+            (true, true) => return Err(self.error("a relationship pointing one way, or neither")),
+            (false, true) => PatternDirection::Right,
+            (true, false) => PatternDirection::Left,
+            (false, false) => PatternDirection::Either,
+        };
+        if right {
+            self.position += 1;
+        }
+
+        Ok((relationship, direction))
+    }
+
+    /// The inside of a node or relationship: an optional variable, label and properties
+    fn element(&mut self) -> Result<ElementPattern, GraphDbError> {
+        let mut element = ElementPattern::default();
+
+        if let Some(Token::Word(variable)) = self.peek() {
+            element.variable = Some(variable.to_string());
+            self.position += 1;
+        }
+
+        if self.eat_symbol(":") {
+            element.label = Some(self.name("a label")?);
+        }
+
+        if self.eat_symbol("{") {
+            loop {
+                let name = self.name("a property name")?;
+                self.expect_symbol(":")?;
+                if let Some(Token::Parameter(parameter)) = self.peek() {
+                    element.parameters.push((name, parameter.to_string()));
+                    self.position += 1;
+                } else {
+                    match self.literal()? {
+                        Some(value) => element.properties.push((name, value)),
+                        None => return Err(self.previous_error("a property value other than null")),
+                    }
+                }
+
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol("}")?;
+        }
+
+        Ok(element)
+    }
+
+    fn previous_error(&mut self, expected: &str) -> GraphDbError {
+        self.position -= 1;
+        let error = self.error(expected);
+        self.position += 1;
+        error
+    }
+
+    fn return_item(&mut self) -> Result<ReturnItem, GraphDbError> {
+        let operand = match self.operand()? {
+            Operand::Literal(_) | Operand::Parameter(_) => {
+                return Err(self.previous_error("a variable, or a variable's property"))
+            }
+            operand => operand,
+        };
+
+        let alias = match self.eat_keyword(AS_KEYWORD) {
+            true => Some(self.name("a column name")?),
+            false => None,
+        };
+
+        Ok(ReturnItem { operand, alias })
+    }
+
+    fn or(&mut self) -> Result<Predicate, GraphDbError> {
+        let mut predicate = self.and()?;
+        while self.eat_keyword(OR_KEYWORD) {
+            predicate = Predicate::Or(Box::new(predicate), Box::new(self.and()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn and(&mut self) -> Result<Predicate, GraphDbError> {
+        let mut predicate = self.not()?;
+        while self.eat_keyword(AND_KEYWORD) {
+            predicate = Predicate::And(Box::new(predicate), Box::new(self.not()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn not(&mut self) -> Result<Predicate, GraphDbError> {
+        match self.eat_keyword(NOT_KEYWORD) {
+            true => Ok(Predicate::Not(Box::new(self.not()?))),
+            false => self.comparison(),
+        }
+    }
+
+    /// a.age > 30, a.name IS NOT NULL, or a bracketed predicate
+    fn comparison(&mut self) -> Result<Predicate, GraphDbError> {
+        if self.eat_symbol("(") {
+            let predicate = self.or()?;
+            self.expect_symbol(")")?;
+            return Ok(predicate);
+        }
+
+        let left = self.operand()?;
+
+        if self.eat_keyword(IS_KEYWORD) {
+            let negated = self.eat_keyword(NOT_KEYWORD);
+            if !self.eat_keyword(NULL_KEYWORD) {
+                return Err(self.error(NULL_KEYWORD));
+            }
+
+            let predicate = Predicate::IsNull(left);
+            return Ok(match negated {
+                true => Predicate::Not(Box::new(predicate)),
+                false => predicate,
+            });
+        }
+
+        let comparison = match self.peek() {
+            Some(Token::Symbol(symbol)) if COMPARISONS.contains(symbol) => {
+                let comparison = Comparison::from_name(symbol)?;
+                self.position += 1;
+                comparison
+            }
+            _ => return Err(self.error("a comparison: =, <>, <, <=, >, >= or IS NULL")),
+        };
+
+        Ok(Predicate::Compare(left, comparison, self.operand()?))
+    }
+
+    /// A variable, a variable's property (e.g. a.name), a value or a placeholder
+    fn operand(&mut self) -> Result<Operand, GraphDbError> {
+        match self.peek() {
+            Some(Token::Parameter(parameter)) => {
+                let parameter = parameter.to_string();
+                self.position += 1;
+                Ok(Operand::Parameter(parameter))
+            }
+            Some(Token::Word(word))
+                if ![TRUE_KEYWORD, FALSE_KEYWORD, NULL_KEYWORD]
+                    .iter()
+                    .chain(RESERVED_KEYWORDS.iter())
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+            {
+                let variable = word.to_string();
+                self.position += 1;
+
+                match self.eat_symbol(".") {
+                    true => Ok(Operand::Property(variable, self.name("a property name")?)),
+                    false => Ok(Operand::Variable(variable)),
+                }
+            }
+            _ => Ok(Operand::Literal(self.literal()?)),
+        }
+    }
+
+    /// A quoted string, number, boolean or null. Whole numbers are int64, others float64
+    fn literal(&mut self) -> Result<Option<PropertyValue>, GraphDbError> {
+        let negative = self.eat_symbol("-");
+
+        let value = match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                let number = match negative {
+                    true => format!("-{}", number),
+                    false => number,
+                };
+                match number.parse::<i64>() {
+                    Ok(value) => Some(PropertyValue::Int64(value)),
+                    Err(_) => match number.parse::<f64>() {
+                        Ok(value) => Some(PropertyValue::Float64(value)),
+                        Err(_) => return Err(self.error("a number")),
+                    },
+                }
+            }
+            _ if negative => return Err(self.error("a number")),
+            Some(Token::Text(text)) => Some(PropertyValue::String(text)),
+            _ if self.is_keyword(TRUE_KEYWORD) => Some(PropertyValue::Boolean(true)),
+            _ if self.is_keyword(FALSE_KEYWORD) => Some(PropertyValue::Boolean(false)),
+            _ if self.is_keyword(NULL_KEYWORD) => None,
+            _ => return Err(self.error("a variable, value or placeholder")),
+        };
+
+        self.position += 1;
+        Ok(value)
+    }
+}
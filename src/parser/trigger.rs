@@ -0,0 +1,119 @@
+use super::{
+    component_offset, extract_name_value_pair, extract_string,
+    mutate_vertex::parse_entity_mutation_command, CREATE_TRIGGER_KEY, END_COMMAND_KEY,
+    INCREMENT_KEY, NOW_KEY, REJECT_KEY, REQUIRE_KEY, TARGET_KEY,
+};
+use crate::{
+    error::GraphDbError,
+    executor::{
+        trigger::{EdgeEnd, Trigger, TriggerAction, TriggerEvent, TriggerTiming},
+        VertexMutationCommandType,
+    },
+};
+
+/// createTrigger(<name>, <before|after>, <event>, <label>), without its actions
+pub fn parse_create_trigger_command(command: &str) -> Result<Trigger, GraphDbError> {
+    let parameters = &command[CREATE_TRIGGER_KEY.len()..command.len() - END_COMMAND_KEY.len()];
+    let values: Vec<&str> = parameters.split(',').map(|value| value.trim()).collect();
+
+    match values[..] {
+        [name, timing, event, label] if !name.is_empty() && !label.is_empty() => {
+            let offset =
+                |value: &str| CREATE_TRIGGER_KEY.len() + parameters.find(value).unwrap_or(0);
+
+            Ok(Trigger {
+                name: name.to_string(),
+                timing: TriggerTiming::from_name(timing)
+                    .map_err(|err| err.with_offset(offset(timing)))?,
+                event: TriggerEvent::from_name(event)
+                    .map_err(|err| err.with_offset(offset(event)))?,
+                label: label.to_string(),
+                target: None,
+                actions: Vec::new(),
+            })
+        }
+        _ => Err(GraphDbError::parse(
+            format!("a name, timing, event and label for {CREATE_TRIGGER_KEY}<value>)"),
+            parameters.trim(),
+        )
+        .with_offset(CREATE_TRIGGER_KEY.len())),
+    }
+}
+
+/// The target and actions following createTrigger(...)
+pub fn parse_trigger_commands(
+    commands: &[&str],
+) -> Result<(Option<EdgeEnd>, Vec<TriggerAction>), GraphDbError> {
+    let mut target = None;
+    let mut actions = Vec::new();
+
+    // First command is graph, second is the trigger. So, actions occur from the third command
+    for (i, command) in commands.iter().enumerate().skip(2) {
+        let command = command.trim();
+        let offset = component_offset(commands, i);
+
+        if command.starts_with(TARGET_KEY) && command.ends_with(END_COMMAND_KEY) {
+            let end = extract_string(TARGET_KEY, command)
+                .and_then(|end| EdgeEnd::from_name(&end))
+                .map_err(|err| err.with_offset(offset))?;
+            match target {
+                Some(_) => {
+                    return Err(GraphDbError::parse("a single target(from|to)", command)
+                        .with_offset(offset))
+                }
+                None => target = Some(end),
+            }
+            continue;
+        }
+
+        let action = parse_trigger_action(command).map_err(|err| err.with_offset(offset))?;
+        actions.push(action);
+    }
+
+    Ok((target, actions))
+}
+
+fn parse_trigger_action(command: &str) -> Result<TriggerAction, GraphDbError> {
+    match command {
+        _ if command.starts_with(NOW_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(NOW_KEY, command)?;
+            Ok(TriggerAction::Now(name))
+        }
+
+        _ if command.starts_with(INCREMENT_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let (name, amount) = extract_name_value_pair(INCREMENT_KEY, command)?;
+            match amount.parse::<i64>() {
+                Ok(amount) => Ok(TriggerAction::Increment(name, amount)),
+                Err(_) => Err(GraphDbError::TypeMismatch {
+                    expected: "an integer amount".to_string(),
+                    found: amount,
+                }),
+            }
+        }
+
+        _ if command.starts_with(REQUIRE_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let name = extract_string(REQUIRE_KEY, command)?;
+            Ok(TriggerAction::Require(name))
+        }
+
+        // The message is kept as written, so it may hold commas
+        _ if command.starts_with(REJECT_KEY) && command.ends_with(END_COMMAND_KEY) => {
+            let message = command[REJECT_KEY.len()..command.len() - END_COMMAND_KEY.len()].trim();
+            match message.is_empty() {
+                true => Err(GraphDbError::parse("a message for reject(<message>)", "")
+                    .with_offset(REJECT_KEY.len())),
+                false => Ok(TriggerAction::Reject(message.to_string())),
+            }
+        }
+
+        _ => match parse_entity_mutation_command(command) {
+            Ok(VertexMutationCommandType::ExternalId(_))
+            | Ok(VertexMutationCommandType::PropertyParameter(..))
+            | Err(GraphDbError::Parse { .. }) => Err(GraphDbError::parse(
+                "a trigger action: property, removeProperty, now, increment, require or reject",
+                command,
+            )),
+            mutation => mutation.map(TriggerAction::Mutation),
+        },
+    }
+}
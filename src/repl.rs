@@ -0,0 +1,334 @@
+mod format;
+
+use std::{collections::HashMap, path::PathBuf, time::Instant};
+
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::{ValidationContext, ValidationResult, Validator},
+    Context, Editor, Helper,
+};
+
+use rust_graph_db::{
+    graph::DataResult,
+    parser::{
+        CREATE_GRAPH_COMMAND_KEYS, CREATE_GRAPH_KEY, GRAPH_COMMAND_KEYS, GRAPH_NAME_COMMAND_KEYS,
+        STANDALONE_COMMAND_KEYS,
+    },
+    Database, GraphDbError, Journal,
+};
+
+use self::format::{render, OutputFormat};
+
+const PROMPT: &str = "\nrustgdb> ";
+const HISTORY_FILE_NAME: &str = ".rustgdb_history";
+const LINE_CONTINUATION_KEY: char = '\\';
+const FORMAT_KEY: &str = ":format";
+const UNDO_KEY: &str = "undo()";
+const REDO_KEY: &str = "redo()";
+
+/// Commands handled by the REPL itself, rather than the database
+const SESSION_COMMAND_KEYS: [&str; 3] = [FORMAT_KEY, UNDO_KEY, REDO_KEY];
+
+pub fn run(database: Database) {
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+        Editor::new().expect("Error whilst attempting to initialise the line editor");
+    editor.set_helper(Some(ReplHelper::new()));
+
+    let history_path = history_path();
+    if let Some(path) = &history_path {
+        // A missing history file just means this is the first session
+        _ = editor.load_history(path);
+    }
+
+    let mut output_format = OutputFormat::Table;
+    let mut journal = Journal::new();
+
+    loop {
+        let input = match editor.readline(PROMPT) {
+            Ok(input) => input,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                println!("Error whilst attempting to read input: {}", err);
+                break;
+            }
+        };
+
+        if input.trim().is_empty() {
+            continue;
+        }
+
+        _ = editor.add_history_entry(input.as_str());
+
+        if let Some(format_name) = input.trim().strip_prefix(FORMAT_KEY) {
+            match format_name.trim().is_empty() {
+                true => println!("Output format: {}", output_format.as_str()),
+                false => match OutputFormat::from_name(format_name) {
+                    Ok(format) => output_format = format,
+                    Err(err) => println!("{}", err),
+                },
+            }
+            continue;
+        }
+
+        let start = Instant::now();
+        let command = join_lines(&input);
+        let result = match command.trim() {
+            UNDO_KEY => journal.undo(&database),
+            REDO_KEY => journal.redo(&database),
+            _ => journal.query(&database, &command),
+        };
+
+        match result {
+            Ok(result) => println!("{}", render(&result, output_format, start.elapsed())),
+            Err(err) => print_error(&err),
+        }
+
+        if let Some(helper) = editor.helper_mut() {
+            helper.refresh(&database);
+        }
+    }
+
+    if let Some(path) = &history_path {
+        if let Err(err) = editor.save_history(path) {
+            println!("Failed to save history to {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn print_error(err: &GraphDbError) {
+    println!("Error {}: {}", err.code(), err);
+}
+
+/// Collapses a multi-line command into the single line the parser expects
+fn join_lines(input: &str) -> String {
+    input
+        .lines()
+        .map(|line| line.trim().trim_end_matches(LINE_CONTINUATION_KEY).trim())
+        .collect::<String>()
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(HISTORY_FILE_NAME))
+}
+
+struct ReplHelper {
+    graph_names: Vec<String>,
+    property_names: HashMap<String, Vec<String>>, // graph name, property names
+}
+
+impl ReplHelper {
+    fn new() -> Self {
+        ReplHelper {
+            graph_names: Vec::new(),
+            property_names: HashMap::new(),
+        }
+    }
+
+    /// Re-reads the graph and property names used for completion
+    fn refresh(&mut self, database: &Database) {
+        let graph_factory = database.graph_factory();
+
+        self.graph_names = match graph_factory.list_graphs() {
+            Ok(DataResult::StringVector(names)) => names,
+            _ => Vec::new(),
+        };
+
+        self.property_names.clear();
+        for graph in graph_factory.graphs().unwrap_or_default() {
+            let graph = match graph.read() {
+                Ok(graph) => graph,
+                Err(_) => continue,
+            };
+            let names = graph
+                .property_names()
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            self.property_names.insert(graph.name().to_string(), names);
+        }
+    }
+
+    fn candidates(&self, line: &str, word_start: usize) -> Vec<&str> {
+        let preceding = line[..word_start].trim_end();
+
+        match preceding.chars().last() {
+            // Start of the line: either a standalone command or a graph name
+            None => STANDALONE_COMMAND_KEYS
+                .iter()
+                .chain(SESSION_COMMAND_KEYS.iter())
+                .copied()
+                .chain(self.graph_names.iter().map(|s| s.as_str()))
+                .collect(),
+
+            Some('.') if line.trim_start().starts_with(CREATE_GRAPH_KEY) => {
+                CREATE_GRAPH_COMMAND_KEYS.to_vec()
+            }
+
+            Some('.') => GRAPH_COMMAND_KEYS.to_vec(),
+
+            // Graph management parameters: offer existing graph names
+            Some('(') | Some(',')
+                if GRAPH_NAME_COMMAND_KEYS
+                    .iter()
+                    .any(|key| line.trim_start().starts_with(key)) =>
+            {
+                self.graph_names.iter().map(|s| s.as_str()).collect()
+            }
+
+            // Command parameters: offer property names from the graph the command targets
+            Some('(') | Some(',') => {
+                let graph_name = line.split('.').next().unwrap_or_default().trim();
+                match self.property_names.get(graph_name) {
+                    Some(names) => names.iter().map(|s| s.as_str()).collect(),
+                    None => Vec::new(),
+                }
+            }
+
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let word_start = line[..pos]
+            .rfind(['.', '(', ',', ' ', '\n'])
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &line[word_start..pos];
+
+        let matches = self
+            .candidates(line, word_start)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect();
+
+        Ok((word_start, matches))
+    }
+}
+
+impl Validator for ReplHelper {
+    /// Keeps reading lines whilst brackets are unbalanced, or the line ends in a '.' or '\'
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input().trim_end();
+
+        let mut depth = 0;
+        for c in input.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => (),
+            }
+        }
+
+        if depth > 0 || input.ends_with('.') || input.ends_with(LINE_CONTINUATION_KEY) {
+            return Ok(ValidationResult::Incomplete);
+        }
+
+        Ok(ValidationResult::Valid(None))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn helper() -> ReplHelper {
+        let mut helper = ReplHelper::new();
+        helper.graph_names = vec!["people".to_string(), "places".to_string()];
+        helper.property_names.insert(
+            "people".to_string(),
+            vec![
+                "name".to_string(),
+                "nickname".to_string(),
+                "age".to_string(),
+            ],
+        );
+        helper
+    }
+
+    /// The start of the completed word, and what it could be replaced with
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        let (start, pairs) = helper()
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap();
+        (
+            start,
+            pairs.into_iter().map(|pair| pair.replacement).collect(),
+        )
+    }
+
+    #[test]
+    fn continued_lines_are_joined_into_one_command() {
+        assert_eq!(join_lines("people.V()"), "people.V()");
+        assert_eq!(
+            join_lines("people.V(). \\\n    hasLabel(person)"),
+            "people.V().hasLabel(person)"
+        );
+        assert_eq!(
+            join_lines("people.addV(person)\n  .property(name, alice, string)\\\n"),
+            "people.addV(person).property(name, alice, string)"
+        );
+    }
+
+    #[test]
+    fn completes_commands_and_graph_names_at_the_start_of_a_line() {
+        assert_eq!(complete("cre"), (0, vec!["createGraph(".to_string()]));
+        assert_eq!(complete("pe"), (0, vec!["people".to_string()]));
+        assert_eq!(complete("x"), (0, Vec::new()));
+    }
+
+    #[test]
+    fn completes_graph_names_for_graph_management_commands() {
+        assert_eq!(complete("dropGraph(pl"), (10, vec!["places".to_string()]));
+        assert_eq!(
+            complete("cloneGraph(people, p"),
+            (19, vec!["people".to_string(), "places".to_string()])
+        );
+    }
+
+    #[test]
+    fn completes_graph_commands_and_property_names() {
+        assert_eq!(
+            complete("people.V().hasL"),
+            (11, vec!["hasLabel(".to_string()])
+        );
+        assert_eq!(
+            complete("people.V().hasProperty(n"),
+            (23, vec!["name".to_string(), "nickname".to_string()])
+        );
+        assert_eq!(
+            complete("people.V().hasPropertyValue(age, a"),
+            (33, vec!["age".to_string()])
+        );
+
+        // Properties only come from the graph the command is run on
+        assert_eq!(complete("places.V().hasProperty(n"), (23, Vec::new()));
+    }
+}
@@ -0,0 +1,523 @@
+use std::time::Duration;
+
+use json::{object as JsonObject, JsonValue as Json};
+
+use rust_graph_db::{
+    executor::pattern::Binding,
+    graph::{stats::GraphStats, vertex::Vertex, DataResult},
+};
+
+const MAX_CELL_WIDTH: usize = 40;
+const TRUNCATION_MARKER: &str = "...";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        match name {
+            _ if name == OutputFormat::Table.as_str() => Ok(OutputFormat::Table),
+            _ if name == OutputFormat::Json.as_str() => Ok(OutputFormat::Json),
+            _ if name == OutputFormat::Csv.as_str() => Ok(OutputFormat::Csv),
+            other => Err(format!(
+                "Unrecognized format: '{}'. Must be one of table, json or csv",
+                other
+            )),
+        }
+    }
+}
+
+/// A result flattened into named columns, so every output format can be rendered the same way
+struct ResultSet {
+    columns: Vec<String>,
+    rows: Vec<Vec<Json>>,
+    /// What each column after the leading ones holds, in column order
+    vertex_columns: Vec<VertexColumn>,
+}
+
+#[derive(PartialEq)]
+enum VertexColumn {
+    ExternalId,
+    Property(String),
+}
+
+impl ResultSet {
+    fn new(columns: Vec<&str>) -> Self {
+        ResultSet {
+            columns: columns.into_iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+            vertex_columns: Vec::new(),
+        }
+    }
+
+    /// Finds or adds the column for part of a vertex, never reusing a leading column such as id or
+    /// label. A property whose name is already taken gets a prefixed column name instead.
+    fn column_index(&mut self, column: VertexColumn) -> usize {
+        let leading_count = self.columns.len() - self.vertex_columns.len();
+        if let Some(index) = self.vertex_columns.iter().position(|c| *c == column) {
+            return leading_count + index;
+        }
+
+        let mut name = match &column {
+            VertexColumn::ExternalId => "external_id".to_string(),
+            VertexColumn::Property(name) => name.clone(),
+        };
+        while self.columns.contains(&name) {
+            name = format!("property.{}", name);
+        }
+
+        self.columns.push(name);
+        self.vertex_columns.push(column);
+        self.columns.len() - 1
+    }
+
+    /// Adds a row with the given leading cells, followed by one cell per vertex property
+    fn push_vertex_row(&mut self, mut leading: Vec<Json>, vertex: &Vertex) {
+        let mut property_cells = Vec::new();
+        if let Some(external_id) = &vertex.external_id {
+            let index = self.column_index(VertexColumn::ExternalId);
+            property_cells.push((index, Json::from(external_id.as_str())));
+        }
+
+        for property in &vertex.properties {
+            let index = self.column_index(VertexColumn::Property(property.name.clone()));
+            property_cells.push((index, property.value.to_json()));
+        }
+
+        leading.resize(self.columns.len(), Json::Null);
+        for (index, value) in property_cells {
+            leading[index] = value;
+        }
+
+        self.rows.push(leading);
+    }
+}
+
+pub fn render(result: &DataResult, format: OutputFormat, elapsed: Duration) -> String {
+    if let DataResult::Text(text) = result {
+        return text.to_string();
+    }
+
+    let result_set = to_result_set(result);
+
+    let body = match (format, result) {
+        // Statistics are nested, so keep their structure rather than flattening them
+        (OutputFormat::Json, DataResult::GraphStats(stats)) => stats.to_json().pretty(2),
+        (OutputFormat::Table, _) => render_table(&result_set),
+        (OutputFormat::Json, _) => render_json(&result_set),
+        (OutputFormat::Csv, _) => render_csv(&result_set),
+    };
+
+    let row_count = result_set.rows.len();
+    format!(
+        "{}\n{} row{} ({:.2} ms)",
+        body,
+        row_count,
+        if row_count == 1 { "" } else { "s" },
+        elapsed.as_secs_f64() * 1000.0
+    )
+}
+
+fn to_result_set(result: &DataResult) -> ResultSet {
+    match result {
+        DataResult::UnsignedInt(value) => {
+            let mut result_set = ResultSet::new(vec!["result"]);
+            result_set.rows.push(vec![Json::from(*value)]);
+            result_set
+        }
+
+        DataResult::StringVector(values) => {
+            let mut result_set = ResultSet::new(vec!["name"]);
+            for value in values {
+                result_set.rows.push(vec![Json::from(value.as_str())]);
+            }
+            result_set
+        }
+
+        DataResult::VertexVector(vertices) => {
+            let mut vertices = vertices.clone();
+            vertices.sort_by_key(|(id, _)| *id);
+
+            let mut result_set = ResultSet::new(vec!["id", "label"]);
+            for (id, vertex) in vertices {
+                let leading = vec![Json::from(id), Json::from(vertex.label.as_str())];
+                result_set.push_vertex_row(leading, &vertex);
+            }
+            result_set
+        }
+
+        // Already in order of relevance, so not sorted by id
+        DataResult::ScoredVertexVector(vertices) => {
+            let mut result_set = ResultSet::new(vec!["id", "score", "label"]);
+            for (id, score, vertex) in vertices {
+                let leading = vec![
+                    Json::from(*id),
+                    Json::from((score * 1000.0).round() / 1000.0),
+                    Json::from(vertex.label.as_str()),
+                ];
+                result_set.push_vertex_row(leading, vertex);
+            }
+            result_set
+        }
+
+        DataResult::Vertex(vertex) => vertex_result_set(vertex),
+
+        DataResult::Edge(edge) => {
+            let mut result_set = ResultSet::new(vec!["from", "to", "label"]);
+            let leading = vec![
+                Json::from(edge.from_vertex_id),
+                Json::from(edge.to_vertex_id),
+                Json::from(edge.edge_vertex.label.as_str()),
+            ];
+            result_set.push_vertex_row(leading, &edge.edge_vertex);
+            result_set
+        }
+
+        DataResult::VertexValueVector(values) => {
+            let mut result_set = ResultSet::new(vec!["value"]);
+            for value in values {
+                let cell = match value {
+                    Some(value) => value.to_json(),
+                    None => Json::Null,
+                };
+                result_set.rows.push(vec![cell]);
+            }
+            result_set
+        }
+
+        DataResult::Table(columns, rows) => {
+            let mut result_set = ResultSet::new(columns.iter().map(|c| c.as_str()).collect());
+            for row in rows {
+                result_set
+                    .rows
+                    .push(row.iter().map(binding_to_json).collect());
+            }
+            result_set
+        }
+
+        DataResult::GraphStats(stats) => stats_result_set(stats),
+
+        DataResult::Text(text) => {
+            let mut result_set = ResultSet::new(vec!["text"]);
+            result_set.rows.push(vec![Json::from(text.as_str())]);
+            result_set
+        }
+    }
+}
+
+fn stats_result_set(stats: &GraphStats) -> ResultSet {
+    let mut result_set = ResultSet::new(vec!["statistic", "value"]);
+    let mut push =
+        |statistic: String, value: Json| result_set.rows.push(vec![statistic.into(), value]);
+
+    push("vertices".to_string(), stats.vertex_count.into());
+    push("edges".to_string(), stats.edge_count.into());
+    for (label, count) in &stats.vertex_label_counts {
+        push(format!("vertex label: {}", label), (*count).into());
+    }
+    for (label, count) in &stats.edge_label_counts {
+        push(format!("edge label: {}", label), (*count).into());
+    }
+    for property in &stats.property_stats {
+        push(
+            format!("property: {}", property.name),
+            format!("{} ({})", property.count, property.types.join(", ")).into(),
+        );
+    }
+    push("degree min".to_string(), stats.degree.min.into());
+    push("degree max".to_string(), stats.degree.max.into());
+    push(
+        "degree mean".to_string(),
+        format!("{:.2}", stats.degree.mean).into(),
+    );
+    push(
+        "approximate memory (bytes)".to_string(),
+        stats.approximate_memory_bytes.into(),
+    );
+
+    result_set
+}
+
+fn vertex_result_set(vertex: &Vertex) -> ResultSet {
+    let mut result_set = ResultSet::new(vec!["label"]);
+    result_set.push_vertex_row(vec![Json::from(vertex.label.as_str())], vertex);
+    result_set
+}
+
+/// Vertices and edges in a MATCH result are nested objects, with their ids and labels alongside
+/// their properties
+fn binding_to_json(binding: &Binding) -> Json {
+    match binding {
+        Binding::Vertex(id, vertex) => {
+            let mut object = JsonObject! { id: *id };
+            push_vertex_fields(&mut object, vertex);
+            object
+        }
+        Binding::Edge(id, edge) => {
+            let mut object = JsonObject! {
+                id: *id,
+                from: edge.from_vertex_id,
+                to: edge.to_vertex_id
+            };
+            push_vertex_fields(&mut object, &edge.edge_vertex);
+            object
+        }
+        Binding::Value(value) => value.to_json(),
+        Binding::Null => Json::Null,
+    }
+}
+
+fn push_vertex_fields(object: &mut Json, vertex: &Vertex) {
+    object["label"] = vertex.label.as_str().into();
+    if let Some(external_id) = &vertex.external_id {
+        object["external_id"] = external_id.as_str().into();
+    }
+    for property in &vertex.properties {
+        object[property.name.as_str()] = property.value.to_json();
+    }
+}
+
+fn to_cell_string(value: &Json) -> String {
+    match value {
+        Json::Null => String::new(),
+        _ => match value.as_str() {
+            Some(text) => text.to_string(),
+            None => value.dump(),
+        },
+    }
+}
+
+fn truncate(value: String) -> String {
+    if value.chars().count() <= MAX_CELL_WIDTH {
+        return value;
+    }
+
+    let kept: String = value
+        .chars()
+        .take(MAX_CELL_WIDTH - TRUNCATION_MARKER.len())
+        .collect();
+    format!("{}{}", kept, TRUNCATION_MARKER)
+}
+
+fn render_table(result_set: &ResultSet) -> String {
+    let rows: Vec<Vec<String>> = result_set
+        .rows
+        .iter()
+        .map(|row| {
+            (0..result_set.columns.len())
+                .map(|i| truncate(row.get(i).map(to_cell_string).unwrap_or_default()))
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = result_set
+        .columns
+        .iter()
+        .map(|column| column.chars().count())
+        .collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let separator = widths
+        .iter()
+        .map(|width| "-".repeat(width + 2))
+        .collect::<Vec<_>>()
+        .join("+");
+    let separator = format!("+{}+", separator);
+
+    let format_row = |cells: &Vec<String>| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!(" {:<width$} ", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("|");
+        format!("|{}|", cells)
+    };
+
+    let mut lines = vec![
+        separator.clone(),
+        format_row(&result_set.columns),
+        separator.clone(),
+    ];
+    for row in &rows {
+        lines.push(format_row(row));
+    }
+    lines.push(separator);
+
+    lines.join("\n")
+}
+
+fn render_json(result_set: &ResultSet) -> String {
+    let mut array = Json::new_array();
+    for row in &result_set.rows {
+        let mut object = Json::new_object();
+        for (column, value) in result_set.columns.iter().zip(row) {
+            if !value.is_null() {
+                object[column.as_str()] = value.clone();
+            }
+        }
+
+        // Pushing onto an array value cannot fail
+        _ = array.push(object);
+    }
+
+    array.pretty(2)
+}
+
+fn render_csv(result_set: &ResultSet) -> String {
+    let mut lines = vec![result_set
+        .columns
+        .iter()
+        .map(|column| escape_csv(column))
+        .collect::<Vec<_>>()
+        .join(",")];
+
+    for row in &result_set.rows {
+        let cells = (0..result_set.columns.len())
+            .map(|i| escape_csv(&row.get(i).map(to_cell_string).unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(",");
+        lines.push(cells);
+    }
+
+    lines.join("\n")
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_graph_db::graph::property::{Property, PropertyValue};
+
+    use super::*;
+
+    fn vertex(label: &str, properties: Vec<(&str, PropertyValue)>) -> Vertex {
+        let properties = properties
+            .into_iter()
+            .map(|(name, value)| Property {
+                name: name.to_string(),
+                value,
+                flagged_for_removal: false,
+            })
+            .collect();
+        Vertex::new(label.to_string(), properties)
+    }
+
+    /// alice has a name and age, and bob only a nickname
+    fn people() -> ResultSet {
+        let mut result_set = ResultSet::new(vec!["id", "label"]);
+        let alice = vertex(
+            "person",
+            vec![
+                ("name", PropertyValue::String("alice".to_string())),
+                ("age", PropertyValue::Int32(30)),
+            ],
+        );
+        let bob = vertex(
+            "person",
+            vec![(
+                "nickname",
+                PropertyValue::String("bob, \"bobby\"".to_string()),
+            )],
+        );
+        result_set.push_vertex_row(vec![Json::from(0), Json::from("person")], &alice);
+        result_set.push_vertex_row(vec![Json::from(1), Json::from("person")], &bob);
+        result_set
+    }
+
+    #[test]
+    fn properties_become_columns_in_order_of_appearance() {
+        let result_set = people();
+        assert_eq!(
+            result_set.columns,
+            ["id", "label", "name", "age", "nickname"]
+        );
+        assert_eq!(
+            render_csv(&result_set),
+            "id,label,name,age,nickname\n\
+             0,person,alice,30,\n\
+             1,person,,,\"bob, \"\"bobby\"\"\""
+        );
+    }
+
+    #[test]
+    fn json_leaves_out_missing_properties() {
+        let json = json::parse(&render_json(&people())).unwrap();
+        assert_eq!(json[0]["name"], "alice");
+        assert_eq!(json[0]["age"], 30);
+        assert!(!json[0].has_key("nickname"));
+        assert_eq!(json[1]["id"], 1);
+        assert!(!json[1].has_key("name"));
+    }
+
+    #[test]
+    fn properties_never_overwrite_leading_columns() {
+        let mut result_set = ResultSet::new(vec!["from", "to", "label"]);
+        let mut edge = vertex(
+            "knows",
+            vec![
+                ("label", PropertyValue::String("friend".to_string())),
+                ("to", PropertyValue::Int32(7)),
+            ],
+        );
+        edge.external_id = Some("k1".to_string());
+        let leading = vec![Json::from(0), Json::from(1), Json::from("knows")];
+        result_set.push_vertex_row(leading, &edge);
+
+        assert_eq!(
+            render_csv(&result_set),
+            "from,to,label,external_id,property.label,property.to\n\
+             0,1,knows,k1,friend,7"
+        );
+        let json = json::parse(&render_json(&result_set)).unwrap();
+        assert_eq!(json[0]["label"], "knows");
+        assert_eq!(json[0]["property.label"], "friend");
+        assert_eq!(json[0]["to"], 1);
+    }
+
+    #[test]
+    fn tables_pad_columns_and_truncate_long_cells() {
+        let long_name = "a".repeat(MAX_CELL_WIDTH + 10);
+        let result_set = vertex_result_set(&vertex(
+            "person",
+            vec![("name", PropertyValue::String(long_name))],
+        ));
+
+        let truncated = format!("{}...", "a".repeat(MAX_CELL_WIDTH - 3));
+        let separator = format!("+--------+{}+", "-".repeat(MAX_CELL_WIDTH + 2));
+        assert_eq!(
+            render_table(&result_set),
+            [
+                separator.clone(),
+                format!("| label  | {:<1$} |", "name", MAX_CELL_WIDTH),
+                separator.clone(),
+                format!("| person | {} |", truncated),
+                separator,
+            ]
+            .join("\n")
+        );
+    }
+}
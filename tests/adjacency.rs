@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use rust_graph_db::graph::adjacency::{Adjacency, AdjacentEdge};
+
+/// Enough edges to cross the compaction threshold several times
+const EDGES: usize = 5000;
+
+/// The adjacency lists as plain vectors, to check the compacted rows against
+#[derive(Default)]
+struct Model {
+    rows: BTreeMap<usize, Vec<AdjacentEdge>>,
+}
+
+impl Model {
+    fn insert(&mut self, adjacency: &mut Adjacency, vertex_id: usize, edge: AdjacentEdge) {
+        adjacency.insert(vertex_id, edge);
+        self.rows.entry(vertex_id).or_default().push(edge);
+    }
+
+    fn remove(&mut self, adjacency: &mut Adjacency, vertex_id: usize, edge_id: usize) {
+        assert!(adjacency.remove(vertex_id, edge_id));
+        let row = self.rows.get_mut(&vertex_id).unwrap();
+        row.retain(|edge| edge.edge_id != edge_id);
+    }
+
+    fn assert_matches(&self, adjacency: &Adjacency, vertex_count: usize) {
+        for vertex_id in 0..vertex_count {
+            let expected = self.rows.get(&vertex_id).cloned().unwrap_or_default();
+            let edges: Vec<AdjacentEdge> = adjacency.edges(vertex_id).collect();
+            assert_eq!(edges, expected, "vertex {}", vertex_id);
+            assert_eq!(adjacency.degree(vertex_id), expected.len());
+        }
+    }
+}
+
+fn edge(edge_id: usize, vertex_id: usize) -> AdjacentEdge {
+    AdjacentEdge { edge_id, vertex_id }
+}
+
+#[test]
+fn rows_keep_their_order_across_compactions() {
+    let mut adjacency = Adjacency::new();
+    let mut model = Model::default();
+
+    // Each vertex's edges arrive spread out, so every row spans several compactions
+    for edge_id in 0..EDGES {
+        model.insert(&mut adjacency, edge_id % 50, edge(edge_id, edge_id % 7));
+        if edge_id % 1000 == 999 {
+            model.assert_matches(&adjacency, 60);
+        }
+    }
+
+    model.assert_matches(&adjacency, 60);
+    assert_eq!(adjacency.degree(0), EDGES / 50);
+    assert!(!adjacency.remove(0, 1));
+    assert!(!adjacency.remove(99, 0));
+}
+
+#[test]
+fn removed_edges_stay_gone_once_compacted_away() {
+    let mut adjacency = Adjacency::new();
+    let mut model = Model::default();
+    for edge_id in 0..EDGES {
+        model.insert(&mut adjacency, edge_id % 10, edge(edge_id, 0));
+    }
+
+    // Tombstones past a quarter of the entries trigger a compaction part way through
+    for edge_id in (0..EDGES).filter(|edge_id| edge_id % 3 != 0) {
+        model.remove(&mut adjacency, edge_id % 10, edge_id);
+    }
+    model.assert_matches(&adjacency, 10);
+    assert!(!adjacency.remove(1, 1));
+
+    // Edges added after the compaction sit in the overflow behind the compacted row
+    for edge_id in EDGES..EDGES + 10 {
+        model.insert(&mut adjacency, edge_id % 10, edge(edge_id, 0));
+    }
+    model.assert_matches(&adjacency, 10);
+
+    let removed = adjacency.remove_vertex(3);
+    assert_eq!(removed, model.rows.remove(&3).unwrap());
+    model.assert_matches(&adjacency, 10);
+}
+
+#[test]
+fn rows_outside_the_compacted_range_are_folded_in() {
+    let mut adjacency = Adjacency::new();
+    let mut model = Model::default();
+    for edge_id in 0..2000 {
+        model.insert(&mut adjacency, 100 + edge_id % 20, edge(edge_id, 1));
+    }
+
+    // Vertices before and after the compacted rows start out in the overflow
+    for edge_id in 2000..3000 {
+        let vertex_id = match edge_id % 2 {
+            0 => edge_id % 5,
+            _ => 200 + edge_id % 5,
+        };
+        model.insert(&mut adjacency, vertex_id, edge(edge_id, 2));
+    }
+    model.assert_matches(&adjacency, 210);
+
+    for vertex_id in 100..120 {
+        let removed = adjacency.remove_vertex(vertex_id);
+        assert_eq!(removed, model.rows.remove(&vertex_id).unwrap());
+    }
+    model.assert_matches(&adjacency, 210);
+
+    adjacency.clear();
+    assert_eq!(adjacency.edges(0).count(), 0);
+    assert_eq!(adjacency.degree(200), 0);
+}
@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    net::TcpListener,
+    sync::mpsc::Receiver,
+    thread,
+};
+
+use rust_graph_db::{Change, ChangeEvent, Database, GraphDbError, SessionId};
+
+fn received(receiver: &Receiver<ChangeEvent>) -> Vec<ChangeEvent> {
+    receiver.try_iter().collect()
+}
+
+#[test]
+fn subscribers_see_every_change_in_order() {
+    let database = Database::new();
+    let changes = database.changes().subscribe(None).unwrap();
+    let graph = database.create_graph("g").unwrap();
+
+    let alice = graph
+        .add_vertex("person")
+        .property("age", 30i32)
+        .insert()
+        .unwrap();
+    let bob = graph.add_vertex("person").insert().unwrap();
+    let knows = graph.add_edge("knows", alice, bob).insert().unwrap();
+    database
+        .query("g.editV(0).property(age, 31, int32)")
+        .unwrap();
+    graph.remove_vertex(alice).unwrap();
+
+    let events = received(&changes);
+    let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+    assert_eq!(sequences, [1, 2, 3, 4, 5, 6]);
+    assert!(events.iter().all(|event| event.graph == "g"));
+
+    let types: Vec<&str> = events.iter().map(|event| event.change.as_str()).collect();
+    assert_eq!(
+        types,
+        [
+            "vertexAdded",
+            "vertexAdded",
+            "edgeAdded",
+            "vertexUpdated",
+            "edgeRemoved",
+            "vertexRemoved"
+        ]
+    );
+
+    match &events[3].change {
+        Change::VertexUpdated { id, before, after } => {
+            assert_eq!(*id, alice);
+            assert_eq!(before.get_property_value("age"), Some(&30i32.into()));
+            assert_eq!(after.get_property_value("age"), Some(&31i32.into()));
+        }
+        other => panic!("Unexpected change: {:?}", other),
+    }
+    match &events[4].change {
+        Change::EdgeRemoved { id, before } => {
+            assert_eq!(*id, knows);
+            assert_eq!((before.from_vertex_id, before.to_vertex_id), (alice, bob));
+        }
+        other => panic!("Unexpected change: {:?}", other),
+    }
+}
+
+#[test]
+fn subscribers_resume_from_a_sequence_number() {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+
+    // Until something follows the feed, changes aren't published or kept
+    graph.add_vertex("robot").insert().unwrap();
+    assert_eq!(database.changes().last_sequence().unwrap(), 0);
+
+    database.changes().set_retention(100).unwrap();
+    for _ in 0..5 {
+        graph.add_vertex("person").insert().unwrap();
+    }
+
+    let resumed = database.changes().subscribe(Some(3)).unwrap();
+    graph.add_vertex("person").insert().unwrap();
+    let sequences: Vec<u64> = received(&resumed)
+        .iter()
+        .map(|event| event.sequence)
+        .collect();
+    assert_eq!(sequences, [4, 5, 6]);
+    assert_eq!(
+        database.graph("g").unwrap().vertex(0).unwrap().label,
+        "robot"
+    );
+
+    database.changes().set_retention(2).unwrap();
+    assert!(database.changes().subscribe(Some(4)).is_ok());
+    assert!(matches!(
+        database.changes().subscribe(Some(3)),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        database.changes().subscribe(Some(7)),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn captures_collect_only_their_sessions_changes() {
+    let database = Database::new();
+    database.create_graph("g").unwrap();
+    let (ann, bob) = (SessionId::new(), SessionId::new());
+    let added = |events: &[ChangeEvent]| -> Vec<String> {
+        events
+            .iter()
+            .map(|event| match &event.change {
+                Change::VertexAdded { after, .. } => after.label.clone(),
+                Change::EdgeAdded { after, .. } => after.edge_vertex.label.clone(),
+                other => panic!("Unexpected change: {:?}", other),
+            })
+            .collect()
+    };
+
+    // Sessions are told apart by the commands run for them, not by the thread running them
+    let (bobs, anns) = database
+        .changes()
+        .capture(ann, || {
+            database.query_in_session("g.addV(person)", ann).unwrap();
+            database.query("g.addV(city)").unwrap();
+            let (_, bobs) = database
+                .changes()
+                .capture(bob, || database.query_in_session("g.addV(robot)", bob))
+                .unwrap();
+            database
+                .query_in_session("g.addE(knows, 0, 2)", ann)
+                .unwrap();
+            bobs
+        })
+        .unwrap();
+    assert_eq!(added(&anns), ["person", "knows"]);
+    assert_eq!(added(&bobs), ["robot"]);
+    assert!(!database.changes().is_followed());
+}
+
+#[test]
+fn sinks_receive_newline_delimited_json() {
+    let database = Database::new();
+    database.changes().set_retention(10).unwrap();
+    let graph = database.create_graph("g").unwrap();
+    graph
+        .add_vertex("person")
+        .property("name", "alice")
+        .insert()
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let reader = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(|line| line.unwrap())
+            .collect::<Vec<String>>()
+    });
+
+    let path = std::env::temp_dir().join(format!("changes-{}.ndjson", std::process::id()));
+    let _ = fs::remove_file(&path);
+    database.changes().add_file_sink(&path, Some(0)).unwrap();
+    database.changes().add_socket_sink(address, None).unwrap();
+    database
+        .query("g.editV(0).property(name, bob, string)")
+        .unwrap();
+    database.query("g.deleteV(0)").unwrap();
+
+    let file_lines: Vec<String> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    fs::remove_file(&path).unwrap();
+    let socket_lines = reader.join().unwrap();
+
+    // The file sink started from the beginning, the socket from when it connected
+    assert_eq!(file_lines.len(), 3);
+    assert_eq!(socket_lines, file_lines[1..]);
+
+    let update = json::parse(&file_lines[1]).unwrap();
+    assert_eq!(update["sequence"], 2);
+    assert_eq!(update["type"], "vertexUpdated");
+    assert_eq!(update["label"], "person");
+    assert_eq!(update["before"]["name"], "alice");
+    assert_eq!(update["after"]["name"], "bob");
+
+    let removal = json::parse(&file_lines[2]).unwrap();
+    assert_eq!(removal["type"], "vertexRemoved");
+    assert!(removal["after"].is_null());
+}
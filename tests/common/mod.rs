@@ -0,0 +1,70 @@
+//! Helpers shared by the integration tests. Each test file uses only some of them
+#![allow(dead_code)]
+
+use rust_graph_db::{Binding, DataResult, Database, GraphDbError, PropertyValue};
+
+/// A database holding one empty graph
+pub fn database_with_graph(graph_name: &str) -> Database {
+    let database = Database::new();
+    database.create_graph(graph_name).unwrap();
+    database
+}
+
+/// Ids of the vertices a query returns, in the order it returns them
+pub fn ids(database: &Database, query: &str) -> Vec<usize> {
+    match vertex_ids(database.query(query)) {
+        Some(ids) => ids,
+        None => panic!(
+            "Unexpected result for {}: {:?}",
+            query,
+            database.query(query)
+        ),
+    }
+}
+
+/// Ids of the vertices a query returns, in ascending order
+pub fn sorted_ids(database: &Database, query: &str) -> Vec<usize> {
+    let mut ids = ids(database, query);
+    ids.sort_unstable();
+    ids
+}
+
+/// Ids of the vertices in a result, e.g. of a query run with parameters
+pub fn ids_of(result: Result<DataResult, GraphDbError>) -> Vec<usize> {
+    match vertex_ids(result.clone()) {
+        Some(ids) => ids,
+        None => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+pub fn table(database: &Database, query: &str) -> (Vec<String>, Vec<Vec<Binding>>) {
+    match database.query(query) {
+        Ok(DataResult::Table(columns, rows)) => (columns, rows),
+        other => panic!("Unexpected result for {}: {:?}", query, other),
+    }
+}
+
+pub fn is_constraint_violation(database: &Database, query: &str) -> bool {
+    matches!(
+        database.query(query),
+        Err(GraphDbError::ConstraintViolation(_))
+    )
+}
+
+/// The value of a vertex's property in graph g
+pub fn property(database: &Database, id: usize, name: &str) -> Option<PropertyValue> {
+    let vertex = database.graph("g").unwrap().vertex(id).unwrap();
+    vertex.get_property_value(name).cloned()
+}
+
+fn vertex_ids(result: Result<DataResult, GraphDbError>) -> Option<Vec<usize>> {
+    match result {
+        Ok(DataResult::VertexVector(vertices)) => {
+            Some(vertices.into_iter().map(|(id, _)| id).collect())
+        }
+        Ok(DataResult::ScoredVertexVector(vertices)) => {
+            Some(vertices.into_iter().map(|(id, _, _)| id).collect())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use std::{collections::HashSet, sync::Arc, thread};
+
+use rust_graph_db::{graph::adjacency::Direction, DataResult, Database, GraphDbError};
+
+const THREADS: usize = 8;
+const OPERATIONS_PER_THREAD: usize = 200;
+
+#[test]
+fn parallel_inserts_are_not_lost() {
+    let database = Arc::new(Database::new());
+    database.create_graph("g").unwrap();
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let database = Arc::clone(&database);
+            thread::spawn(move || {
+                let mut ids = Vec::new();
+                for i in 0..OPERATIONS_PER_THREAD {
+                    let query = match i % 2 {
+                        0 => format!("g.addV(person).property(thread, {}, int32)", thread),
+                        _ => "g.addV(person)".to_string(),
+                    };
+                    match database.query(&query).unwrap() {
+                        DataResult::UnsignedInt(id) => ids.push(id),
+                        other => panic!("Unexpected result: {:?}", other),
+                    }
+                }
+                ids
+            })
+        })
+        .collect();
+
+    let mut ids = HashSet::new();
+    for handle in handles {
+        for id in handle.join().unwrap() {
+            assert!(ids.insert(id), "Vertex ID {} was handed out twice", id);
+        }
+    }
+
+    assert_eq!(ids.len(), THREADS * OPERATIONS_PER_THREAD);
+    assert_eq!(
+        common::sorted_ids(&database, "g.V()").len(),
+        THREADS * OPERATIONS_PER_THREAD
+    );
+}
+
+#[test]
+fn parallel_edge_inserts_and_vertex_deletes_keep_adjacency_consistent() {
+    let database = Arc::new(Database::new());
+    let graph = database.create_graph("g").unwrap();
+
+    let vertex_count = 64;
+    let mut ids = Vec::new();
+    for _ in 0..vertex_count {
+        ids.push(graph.add_vertex("node").insert().unwrap());
+    }
+    let ids = Arc::new(ids);
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let database = Arc::clone(&database);
+            let ids = Arc::clone(&ids);
+            thread::spawn(move || {
+                for i in 0..OPERATIONS_PER_THREAD {
+                    let from = ids[(thread * 7 + i) % ids.len()];
+                    let to = ids[(thread * 13 + i * 3) % ids.len()];
+
+                    // Every few operations, a writer deletes a vertex instead. Commands that
+                    // lose the race to a delete must fail cleanly rather than corrupt the graph
+                    let result = match (thread + i) % 10 {
+                        0 => database.query(&format!("g.deleteV({})", from)),
+                        1 => database.query("g.V()"),
+                        _ => database.query(&format!("g.addE(link, {}, {})", from, to)),
+                    };
+
+                    match result {
+                        Ok(_) | Err(GraphDbError::NotFound { .. }) => (),
+                        Err(err) => panic!("Unexpected error: {}", err),
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let remaining = common::sorted_ids(&database, "g.V()");
+    let graph = graph.read().unwrap();
+    let mut edge_count = 0;
+
+    for id in &remaining {
+        for adjacent in graph.adjacent_edges(id, Direction::Out).unwrap() {
+            let edge = match graph.get_edge(&adjacent.edge_id).unwrap() {
+                DataResult::Edge(edge) => edge,
+                other => panic!("Unexpected result: {:?}", other),
+            };
+            assert_eq!(edge.from_vertex_id, *id);
+            assert_eq!(edge.to_vertex_id, adjacent.vertex_id);
+
+            // The other end must still exist and list the edge as incoming
+            let is_linked = graph
+                .adjacent_edges(&edge.to_vertex_id, Direction::In)
+                .unwrap()
+                .any(|incoming| incoming.edge_id == adjacent.edge_id && incoming.vertex_id == *id);
+            assert!(is_linked);
+            edge_count += 1;
+        }
+
+        for adjacent in graph.adjacent_edges(id, Direction::In).unwrap() {
+            match graph.get_edge(&adjacent.edge_id).unwrap() {
+                DataResult::Edge(edge) => assert_eq!(edge.to_vertex_id, *id),
+                other => panic!("Unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    match graph.stats().unwrap() {
+        DataResult::GraphStats(stats) => {
+            assert_eq!(stats.vertex_count, remaining.len());
+            assert_eq!(stats.edge_count, edge_count);
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn readers_see_whole_writes() {
+    let database = Arc::new(Database::new());
+    database.create_graph("g").unwrap();
+
+    let writer = {
+        let database = Arc::clone(&database);
+        thread::spawn(move || {
+            for i in 0..OPERATIONS_PER_THREAD {
+                database
+                    .query(&format!(
+                        "g.addV(pair).property(a, {}, int32).property(b, {}, int32)",
+                        i, i
+                    ))
+                    .unwrap();
+            }
+        })
+    };
+
+    let readers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let database = Arc::clone(&database);
+            thread::spawn(move || {
+                for _ in 0..OPERATIONS_PER_THREAD {
+                    let vertices = match database.query("g.V()").unwrap() {
+                        DataResult::VertexVector(vertices) => vertices,
+                        other => panic!("Unexpected result: {:?}", other),
+                    };
+
+                    for (_, vertex) in vertices {
+                        assert_eq!(
+                            vertex.get_property_value("a"),
+                            vertex.get_property_value("b")
+                        );
+                    }
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
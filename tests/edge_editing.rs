@@ -0,0 +1,141 @@
+mod common;
+
+use rust_graph_db::{DataResult, Database, GraphDbError, Journal, PropertyValue};
+
+use common::ids;
+
+/// ann, bob and cat, with edge 0 from ann to bob
+fn database_with_edge() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    for _ in 0..3 {
+        graph.add_vertex("person").insert().unwrap();
+    }
+    graph
+        .add_edge("knows", 0, 1)
+        .property("since", 2020i32)
+        .insert()
+        .unwrap();
+
+    database
+}
+
+/// Where edge 0 goes, as seen by traversing from each vertex
+fn neighbours(database: &Database) -> Vec<(Vec<usize>, Vec<usize>)> {
+    (0..3)
+        .map(|id| {
+            (
+                ids(database, &format!("g.V({}).out(knows)", id)),
+                ids(database, &format!("g.V({}).in(knows)", id)),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn moved_edges_are_followed_from_their_new_vertices() {
+    let database = database_with_edge();
+
+    database.query("g.editE(0).to(2)").unwrap();
+    assert_eq!(
+        neighbours(&database),
+        [(vec![2], vec![]), (vec![], vec![]), (vec![], vec![0])]
+    );
+
+    // A self loop is both an out and an in edge of its vertex
+    database.query("g.editE(0).from(2)").unwrap();
+    assert_eq!(
+        neighbours(&database),
+        [(vec![], vec![]), (vec![], vec![]), (vec![2], vec![2])]
+    );
+
+    database.query("g.editE(0).from(1)").unwrap();
+    assert_eq!(
+        neighbours(&database),
+        [(vec![], vec![]), (vec![2], vec![]), (vec![], vec![1])]
+    );
+
+    // Deleting its new vertex deletes the edge, without leaving it behind at the old ones
+    database.query("g.deleteV(2)").unwrap();
+    assert!(ids(&database, "g.V(1).out()").is_empty());
+    assert!(matches!(
+        database.graph("g").unwrap().edge(0),
+        Err(GraphDbError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn failed_edits_leave_the_edge_as_it_was() {
+    let database = database_with_edge();
+
+    for query in [
+        "g.editE(0).to(9).property(since, 2021, int32)",
+        "g.editE(0).from(1).to(9)",
+        "g.editE(7).to(2)",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::NotFound { .. })),
+            "{}",
+            query
+        );
+    }
+    assert!(matches!(
+        database.query("g.editE(0).externalId(e1)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        database.query("g.editE(0).from(ann)"),
+        Err(GraphDbError::Parse { position: 16, .. })
+    ));
+
+    let edge = database.graph("g").unwrap().edge(0).unwrap();
+    assert_eq!((edge.from_vertex_id, edge.to_vertex_id), (0, 1));
+    assert_eq!(
+        edge.get_property_value("since"),
+        Some(&PropertyValue::Int32(2020))
+    );
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [1]);
+}
+
+#[test]
+fn edits_change_properties_and_can_be_undone() {
+    let database = database_with_edge();
+    let mut journal = Journal::new();
+
+    let edge = match journal
+        .query(
+            &database,
+            "g.editE(0).to(2).property(weight, 0.5, float64).removeProperty(since)",
+        )
+        .unwrap()
+    {
+        DataResult::Edge(edge) => edge,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    assert_eq!(edge.to_vertex_id, 2);
+    assert_eq!(edge.get_property_value("since"), None);
+    assert_eq!(
+        edge.get_property_value("weight"),
+        Some(&PropertyValue::Float64(0.5))
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "g.V().outE(knows).hasPropertyValue(weight, 0.5).inV()"
+        ),
+        [2]
+    );
+
+    journal.undo(&database).unwrap();
+    let edge = database.graph("g").unwrap().edge(0).unwrap();
+    assert_eq!((edge.from_vertex_id, edge.to_vertex_id), (0, 1));
+    assert_eq!(
+        edge.get_property_value("since"),
+        Some(&PropertyValue::Int32(2020))
+    );
+    assert_eq!(edge.get_property_value("weight"), None);
+    assert_eq!(
+        neighbours(&database),
+        [(vec![1], vec![]), (vec![], vec![0]), (vec![], vec![])]
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use rust_graph_db::{Database, GraphDbError};
+
+use common::ids;
+
+/// ann and bob rate films 2 and 3, and ann follows herself
+fn database_with_ratings() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("films").unwrap();
+
+    let ann = graph.add_vertex("person").insert().unwrap();
+    let bob = graph.add_vertex("person").insert().unwrap();
+    let heat = graph.add_vertex("film").insert().unwrap();
+    let jaws = graph.add_vertex("film").insert().unwrap();
+    for (from, to, stars, review) in [
+        (ann, heat, 5, "a classic"),
+        (ann, jaws, 3, "too long"),
+        (bob, jaws, 5, "a classic shark film"),
+    ] {
+        graph
+            .add_edge("rated", from, to)
+            .property("stars", stars)
+            .property("review", review)
+            .insert()
+            .unwrap();
+    }
+    graph.add_edge("follows", ann, ann).insert().unwrap();
+
+    database
+}
+
+#[test]
+fn edges_are_filtered_by_label_and_properties() {
+    let database = database_with_ratings();
+
+    for (query, expected) in [
+        ("films.V(0).outE(rated).inV()", vec![2, 3]),
+        (
+            "films.V(0).outE(rated).hasPropertyValue(stars, 5).inV()",
+            vec![2],
+        ),
+        (
+            "films.V().outE().hasLabel(rated).hasPropertyValue(stars, 5).inV()",
+            vec![2, 3],
+        ),
+        (
+            "films.V().outE(rated).hasPropertyLike(review, classic).inV()",
+            vec![2, 3],
+        ),
+        (
+            "films.V().outE(rated).hasPropertyRegex(review, ^too).inV()",
+            vec![3],
+        ),
+        (
+            "films.V(0).outE(rated).hasProperty(stars).hasPropertyValue(stars, 4).inV()",
+            vec![],
+        ),
+        ("films.V(0).outE(rated).hasProperty(rating).inV()", vec![]),
+        ("films.V(0).outE(likes).inV()", vec![]),
+        // Vertex filters after the step apply to the vertices it ends at
+        (
+            "films.V(3).inE(rated).hasPropertyValue(stars, 5).outV().out(follows)",
+            vec![],
+        ),
+        ("films.V(3).inE(rated).outV().out(follows)", vec![0]),
+    ] {
+        assert_eq!(ids(&database, query), expected, "{}", query);
+    }
+}
+
+#[test]
+fn steps_end_at_either_vertex_of_each_edge() {
+    let database = database_with_ratings();
+
+    assert_eq!(ids(&database, "films.V(3).inE(rated).outV()"), [0, 1]);
+    assert_eq!(ids(&database, "films.V(3).inE(rated).inV()"), [3, 3]);
+    assert_eq!(ids(&database, "films.V(3).inE(rated).otherV()"), [0, 1]);
+    assert_eq!(ids(&database, "films.V(0).outE(rated).otherV()"), [2, 3]);
+
+    // The other end of a self-loop is the vertex itself
+    assert_eq!(ids(&database, "films.V(0).outE(follows).otherV()"), [0]);
+    assert_eq!(ids(&database, "films.V(0).inE(follows).otherV()"), [0]);
+
+    // Edge steps can be repeated like any other step
+    assert_eq!(
+        ids(
+            &database,
+            "films.V(1).repeat(outE(rated).hasPropertyValue(stars, 5).inV().inE(rated).hasPropertyValue(stars, 5).outV()).emit()"
+        ),
+        [1]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "films.V(1).repeat(outE(rated).inV().inE(rated).outV()).times(2)"
+        ),
+        [0, 1]
+    );
+}
+
+#[test]
+fn edge_steps_only_take_edge_filters_and_must_end() {
+    let database = database_with_ratings();
+
+    for (query, position) in [
+        ("films.V(0).outE(rated)", 11),
+        ("films.V(0).outE(rated).hasPropertyValue(stars, 5)", 11),
+        ("films.V(0).outE(rated).out(follows).inV()", 23),
+        ("films.V(0).outE(rated).values(stars)", 23),
+        ("films.V(0).outE(rated).hasLable(rated).inV()", 23),
+        ("films.V(0).inV()", 11),
+        ("films.V(0).out(rated, follows)", 15),
+        ("films.V(0).in( rated,follows)", 15),
+        ("films.V(0).inE(rated, follows).outV()", 15),
+    ] {
+        match database.query(query) {
+            Err(GraphDbError::Parse {
+                position: found, ..
+            }) => {
+                assert_eq!(found, position, "{}", query)
+            }
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+}
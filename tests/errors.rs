@@ -0,0 +1,98 @@
+mod common;
+
+use rust_graph_db::{error::EntityKind, Database, GraphDbError};
+
+use common::database_with_graph;
+
+fn error(database: &Database, query: &str) -> GraphDbError {
+    match database.query(query) {
+        Err(err) => err,
+        Ok(result) => panic!("Unexpected result for {}: {:?}", query, result),
+    }
+}
+
+#[test]
+fn each_kind_of_error_has_its_own_code() {
+    let database = database_with_graph("g");
+    database.query("g.addV(person)").unwrap();
+
+    let codes: Vec<u16> = [
+        "g.V().hasLabel(person).bogus()",
+        "g.addV(person).property(age, abc, int32)",
+        "g.addE(knows, 0, 0).externalId(a1b2)",
+        "g.V(7)",
+        "createGraph(g)",
+    ]
+    .iter()
+    .map(|query| error(&database, query).code())
+    .collect();
+    assert_eq!(codes, [100, 101, 102, 200, 201]);
+
+    database.query("g.addV(person).externalId(a1b2)").unwrap();
+    assert_eq!(
+        error(&database, "g.addV(person).externalId(a1b2)").code(),
+        202
+    );
+    assert_eq!(GraphDbError::Internal(String::new()).code(), 500);
+}
+
+#[test]
+fn parse_errors_point_at_the_offending_text() {
+    let database = database_with_graph("g");
+
+    let position = |query: &str| match error(&database, query) {
+        GraphDbError::Parse {
+            position, found, ..
+        } => (position, found),
+        other => panic!("Unexpected error for {}: {:?}", query, other),
+    };
+    assert_eq!(position("frob()"), (0, "frob()".to_string()));
+    assert_eq!(position("g.V(x)"), (4, "x".to_string()));
+    assert_eq!(
+        position("g.V().hasLabel(person).bogus()"),
+        (23, "bogus()".to_string())
+    );
+    assert_eq!(
+        position("g.addV(person).property(age, 1, int33)"),
+        (32, "int33".to_string())
+    );
+    assert_eq!(
+        position("g.addV(person).property(age,  1,   int33)"),
+        (35, "int33".to_string())
+    );
+    assert_eq!(
+        position("g.V().out(knows, likes)"),
+        (10, "knows, likes".to_string())
+    );
+    assert_eq!(
+        position("g.V().in( knows,likes)"),
+        (10, "knows,likes".to_string())
+    );
+}
+
+#[test]
+fn messages_name_what_went_wrong() {
+    let database = database_with_graph("g");
+    database.query("g.addV(person)").unwrap();
+
+    assert_eq!(
+        error(&database, "nosuch.V()"),
+        GraphDbError::not_found(EntityKind::Graph, "nosuch")
+    );
+    assert_eq!(
+        error(&database, "nosuch.V()").to_string(),
+        "Unknown graph: nosuch"
+    );
+    assert_eq!(
+        error(&database, "g.addE(knows, 0, 9)").to_string(),
+        "Vertex ID: 9 does not exist"
+    );
+    assert_eq!(
+        error(&database, "g.addV(person).property(age, abc, int32)").to_string(),
+        "Failed to parse value: abc as int32"
+    );
+    assert_eq!(
+        error(&database, "g.V(x)").to_string(),
+        "Parse error at position 4: expected an int for V(<value>), found 'x'"
+    );
+}
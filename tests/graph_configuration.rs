@@ -0,0 +1,341 @@
+mod common;
+
+use std::fs;
+
+use rust_graph_db::{Binding, DataResult, Database, GraphDbError, PropertyValue};
+
+use common::ids;
+
+/// describeGraph's rows, as setting and displayed value
+fn describe(database: &Database, graph_name: &str) -> Vec<(String, String)> {
+    match database.query(&format!("describeGraph({})", graph_name)) {
+        Ok(DataResult::Table(_, rows)) => rows
+            .into_iter()
+            .map(|row| match row.as_slice() {
+                [Binding::Value(setting), Binding::Value(value)] => {
+                    (setting.to_string(), value.to_string())
+                }
+                [Binding::Value(setting), Binding::Null] => (setting.to_string(), String::new()),
+                other => panic!("Unexpected row: {:?}", other),
+            })
+            .collect(),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+fn setting(rows: &[(String, String)], name: &str) -> String {
+    match rows.iter().find(|(setting, _)| setting == name) {
+        Some((_, value)) => value.clone(),
+        None => panic!("No {} setting in {:?}", name, rows),
+    }
+}
+
+#[test]
+fn persistent_graphs_are_read_back_from_their_file() {
+    let path = std::env::temp_dir().join(format!("graph-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let create = format!(
+        "createGraph(g).type(persistent).option(path, {}).option(textIndex, name)",
+        path.display()
+    );
+
+    let database = Database::new();
+    database.query(&create).unwrap();
+    for query in [
+        "g.addV(person).property(name, ann, string).property(age, 30, int32).externalId(a1)",
+        "g.addV(person).property(name, bob, string)",
+        "g.addV(person).property(name, cat, string)",
+        "g.addE(knows, 0, 1).property(since, 2020, int64)",
+        "g.addE(knows, 0, 2)",
+        "g.editV(0).property(age, 31.5, float32).removeProperty(name)",
+        "g.editE(0).to(2)",
+        "g.deleteV(1)",
+    ] {
+        database.query(query).unwrap();
+    }
+
+    // Another graph can't share the file, and a copy is kept in memory only
+    assert!(matches!(
+        database.query(&create.replace("createGraph(g)", "createGraph(h)")),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    database.query("cloneGraph(g, h)").unwrap();
+    database.query("h.addV(person)").unwrap();
+    assert_eq!(setting(&describe(&database, "h"), "type"), "inMemory");
+
+    let database = Database::new();
+    database.query(&create).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [0, 2]);
+    assert_eq!(ids(&database, "g.V().hasExternalId(a1)"), [0]);
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [2, 2]);
+    let rows = describe(&database, "g");
+    assert_eq!(setting(&rows, "path"), path.display().to_string());
+    assert_eq!(setting(&rows, "textIndex"), "name");
+    assert!(matches!(
+        database.query("g.search(name, cat)"),
+        Ok(DataResult::ScoredVertexVector(vertices)) if vertices[0].0 == 2
+    ));
+
+    let graph = database.graph("g").unwrap();
+    let ann = graph.vertex(0).unwrap();
+    assert_eq!(
+        ann.get_property_value("age"),
+        Some(&PropertyValue::Float32(31.5))
+    );
+    assert_eq!(ann.get_property_value("name"), None);
+    let edge = graph.edge(0).unwrap();
+    assert_eq!(edge.to_vertex_id, 2);
+    assert_eq!(
+        edge.get_property_value("since"),
+        Some(&PropertyValue::Int64(2020))
+    );
+
+    // New ids carry on from those in the file
+    assert!(matches!(
+        database.query("g.addV(person)"),
+        Ok(DataResult::UnsignedInt(3))
+    ));
+    database.query("clearGraph(g)").unwrap();
+
+    // Clearing rewrites the file, keeping only the options and where ids carry on from
+    let text = fs::read_to_string(&path).unwrap();
+    assert_eq!(text.lines().count(), 2);
+    assert_eq!(
+        text.lines().nth(1),
+        Some(r#"{"cleared":true,"nextVertex":4,"nextEdge":2}"#)
+    );
+
+    let database = Database::new();
+    database.query(&create).unwrap();
+    assert!(ids(&database, "g.V()").is_empty());
+    assert!(matches!(
+        database.query("g.addV(place)"),
+        Ok(DataResult::UnsignedInt(4))
+    ));
+
+    let database = Database::new();
+    database.query(&create).unwrap();
+    assert_eq!(ids(&database, "g.V().hasLabel(place)"), [4]);
+    assert_eq!(ids(&database, "g.V()"), [4]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn persistent_graphs_keep_the_options_they_were_created_with() {
+    let path = std::env::temp_dir().join(format!("options-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let create = |options: &str| {
+        Database::new().query(&format!(
+            "createGraph(g).type(persistent).option(path, {}){}",
+            path.display(),
+            options
+        ))
+    };
+
+    let database = Database::new();
+    database
+        .query(&format!(
+            "createGraph(g).type(persistent).option(path, {}).option(directed, false)\
+             .option(textIndex, name)",
+            path.display()
+        ))
+        .unwrap();
+    database
+        .query("g.addV(person).property(name, ann, string)")
+        .unwrap();
+    database.query("g.addV(person)").unwrap();
+    database.query("g.addE(knows, 0, 1)").unwrap();
+    database.query("g.addE(knows, 0, 1)").unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with(r#"{"options":{"directed":false,"edgePolicy":"multigraph""#));
+
+    // The file decides how its lines are read back, so conflicting options are turned away
+    for (options, conflict) in [
+        (
+            ".option(directed, true)",
+            "option(directed, false), so can't be opened with option(directed, true)",
+        ),
+        (
+            ".option(directed, false).option(edgePolicy, simple)",
+            "option(edgePolicy, multigraph), so can't be opened with option(edgePolicy, simple)",
+        ),
+    ] {
+        match create(options) {
+            Err(GraphDbError::InvalidArgument(message)) => {
+                assert!(message.contains(conflict), "{}", message)
+            }
+            other => panic!("Unexpected result for {}: {:?}", options, other),
+        }
+    }
+
+    // Options not given are taken from the file, and indexes named in it are built along with
+    // any given
+    let database = Database::new();
+    database
+        .query(&format!(
+            "createGraph(g).type(persistent).option(path, {}).option(spatialIndex, location)",
+            path.display()
+        ))
+        .unwrap();
+    assert_eq!(ids(&database, "g.V(1).out(knows)"), [0, 0]);
+    let rows = describe(&database, "g");
+    assert_eq!(setting(&rows, "directed"), "false");
+    assert_eq!(setting(&rows, "edgePolicy"), "multigraph");
+    assert_eq!(setting(&rows, "textIndex"), "name");
+    assert_eq!(setting(&rows, "spatialIndex"), "location");
+    assert!(matches!(
+        database.query("g.search(name, ann)"),
+        Ok(DataResult::ScoredVertexVector(vertices)) if vertices[0].0 == 0
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn dropping_a_persistent_graph_deletes_its_file() {
+    let path = std::env::temp_dir().join(format!("dropped-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let create = format!(
+        "createGraph(g).type(persistent).option(path, {})",
+        path.display()
+    );
+
+    let database = Database::new();
+    database.query(&create).unwrap();
+    database.query("g.addV(person)").unwrap();
+    assert!(path.exists());
+
+    database.query("dropGraph(g)").unwrap();
+    assert!(!path.exists());
+
+    // A graph created with the same path afterwards starts out empty
+    database.query(&create).unwrap();
+    assert!(ids(&database, "g.V()").is_empty());
+    database.query("dropGraph(g)").unwrap();
+}
+
+#[test]
+fn strict_graphs_keep_properties_to_one_type() {
+    let database = Database::new();
+    database
+        .query("createGraph(g).option(schema, strict)")
+        .unwrap();
+    database
+        .query("g.addV(person).property(age, 30, int32)")
+        .unwrap();
+    database.query("g.addV(person)").unwrap();
+
+    for query in [
+        "g.addV(person).property(age, 30, int64)",
+        "g.editV(1).property(age, thirty, string)",
+        "g.addE(knows, 0, 1).property(age, 2, float64)",
+    ] {
+        assert!(
+            matches!(
+                database.query(query),
+                Err(GraphDbError::ConstraintViolation(_))
+            ),
+            "{}",
+            query
+        );
+    }
+    assert_eq!(ids(&database, "g.V()"), [0, 1]);
+
+    // A rejected write sets no types or vector lengths, even for the properties it got right
+    database
+        .query("g.addV(item).property(embedding, [0.1, 0.2, 0.3], vector)")
+        .unwrap();
+    for query in [
+        "g.addV(person).property(height, 180, int32).property(age, old, string)",
+        "g.addV(item).property(code, 7, int32).property(embedding, [0.1, 0.2], vector)",
+        "g.addV(item).property(features, [0.5], vector).property(age, 3, int64)",
+        "g.editV(1).property(weight, 80, int32).property(age, 1.5, float32)",
+    ] {
+        assert!(database.query(query).is_err(), "{}", query);
+    }
+    database
+        .query(
+            "g.addV(item).property(height, tall, string).property(code, x7, string)\
+             .property(weight, 80.5, float64).property(features, [0.5, 0.5], vector)",
+        )
+        .unwrap();
+    database.query("g.deleteV(2)").unwrap();
+    database.query("g.deleteV(3)").unwrap();
+
+    // Types are fixed per property, and forgotten when the graph is cleared
+    database
+        .query("g.addE(knows, 0, 1).property(since, 2020, int32).property(age, 3, int32)")
+        .unwrap();
+    database.query("clearGraph(g)").unwrap();
+    database
+        .query("g.addV(person).property(age, 30, int64)")
+        .unwrap();
+
+    // A flexible graph takes any type
+    database.query("createGraph(h)").unwrap();
+    database
+        .query("h.addV(person).property(age, 30, int32)")
+        .unwrap();
+    database
+        .query("h.addV(person).property(age, thirty, string)")
+        .unwrap();
+}
+
+#[test]
+fn describe_graph_shows_how_the_graph_was_created() {
+    let database = Database::new();
+    database
+        .query(
+            "createGraph(g).type(inMemory).option(directed, false).option(edgePolicy, simple)\
+             .option(vectorIndex, embedding, cosine).option(spatialIndex, location)",
+        )
+        .unwrap();
+    database.query("g.createTextIndex(name)").unwrap();
+
+    let rows = describe(&database, "g");
+    assert_eq!(
+        rows,
+        [
+            ("type", "inMemory"),
+            ("directed", "false"),
+            ("edgePolicy", "simple"),
+            ("schema", "flexible"),
+            ("textIndex", "name"),
+            ("spatialIndex", "location"),
+            ("vectorIndex", "embedding (cosine)"),
+            ("versioning", "false"),
+        ]
+        .map(|(setting, value)| (setting.to_string(), value.to_string()))
+    );
+    assert!(matches!(
+        database.query("describeGraph(x)"),
+        Err(GraphDbError::NotFound { .. })
+    ));
+
+    for (query, position) in [
+        ("createGraph(x).type(disk)", 20),
+        ("createGraph(x).option(colour, red)", 22),
+        ("createGraph(x).option(schema,  loose)", 31),
+        ("createGraph(x).option(vectorIndex, embedding, far)", 46),
+        ("createGraph(x).option(path)", 22),
+    ] {
+        match database.query(query) {
+            Err(GraphDbError::Parse {
+                position: found, ..
+            }) => assert_eq!(found, position, "{}", query),
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+
+    // A file is only for persistent graphs, and they need one
+    for query in [
+        "createGraph(x).type(persistent)",
+        "createGraph(x).option(path, x.log)",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::InvalidArgument(_))),
+            "{}",
+            query
+        );
+    }
+    assert!(database.graph("x").is_err());
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use rust_graph_db::{error::EntityKind, DataResult, Database, GraphDbError};
+
+use common::{database_with_graph, sorted_ids};
+
+const PEOPLE: [&str; 3] = ["g.addV(person)", "g.addV(person)", "g.addE(knows, 0, 1)"];
+
+fn graph_names(database: &Database) -> Vec<String> {
+    let mut names: Vec<String> = match database.query("listGraph()") {
+        Ok(DataResult::StringVector(names)) => names,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+    names.sort();
+    names
+}
+
+fn is_graph_not_found(result: Result<DataResult, GraphDbError>, graph_name: &str) -> bool {
+    result.err() == Some(GraphDbError::not_found(EntityKind::Graph, graph_name))
+}
+
+#[test]
+fn dropped_graphs_are_gone_and_their_names_free() {
+    let database = database_with_graph("g");
+    for query in PEOPLE {
+        database.query(query).unwrap();
+    }
+    database.query("createGraph(h)").unwrap();
+
+    database.query("dropGraph(g)").unwrap();
+    assert_eq!(graph_names(&database), ["h"]);
+    assert!(is_graph_not_found(database.query("g.V()"), "g"));
+    assert!(is_graph_not_found(database.query("dropGraph(g)"), "g"));
+
+    database.query("createGraph(g)").unwrap();
+    assert_eq!(sorted_ids(&database, "g.V()"), Vec::<usize>::new());
+}
+
+#[test]
+fn renamed_graphs_keep_their_contents() {
+    let database = database_with_graph("g");
+    for query in PEOPLE {
+        database.query(query).unwrap();
+    }
+    database.query("createGraph(h)").unwrap();
+
+    database.query("renameGraph(g, people)").unwrap();
+    assert_eq!(graph_names(&database), ["h", "people"]);
+    assert_eq!(sorted_ids(&database, "people.V()"), [0, 1]);
+    assert!(is_graph_not_found(database.query("g.V()"), "g"));
+
+    assert_eq!(
+        database.query("renameGraph(people, h)").err(),
+        Some(GraphDbError::GraphExists("h".to_string()))
+    );
+    assert!(is_graph_not_found(
+        database.query("renameGraph(missing, other)"),
+        "missing"
+    ));
+    assert_eq!(sorted_ids(&database, "people.V()"), [0, 1]);
+}
+
+#[test]
+fn clones_are_independent_of_their_source() {
+    let database = database_with_graph("g");
+    for query in PEOPLE {
+        database.query(query).unwrap();
+    }
+
+    database.query("cloneGraph(g, copy)").unwrap();
+    assert_eq!(graph_names(&database), ["copy", "g"]);
+    assert_eq!(sorted_ids(&database, "copy.V()"), [0, 1]);
+    assert_eq!(
+        database.query("cloneGraph(g, copy)").err(),
+        Some(GraphDbError::GraphExists("copy".to_string()))
+    );
+
+    database.query("copy.deleteV(1)").unwrap();
+    database.query("copy.addV(person)").unwrap();
+    assert_eq!(sorted_ids(&database, "copy.V()"), [0, 2]);
+    assert_eq!(sorted_ids(&database, "g.V()"), [0, 1]);
+
+    database.query("clearGraph(g)").unwrap();
+    assert_eq!(sorted_ids(&database, "g.V()"), Vec::<usize>::new());
+    assert_eq!(sorted_ids(&database, "copy.V()"), [0, 2]);
+}
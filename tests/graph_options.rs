@@ -0,0 +1,139 @@
+mod common;
+
+use rust_graph_db::{Binding, DataResult, Database, EdgePolicy, GraphDbError, GraphOptions};
+
+use common::{is_constraint_violation, sorted_ids};
+
+/// A graph of three vertices with the given edge policy
+fn database_with_policy(policy: &str, directed: bool) -> Database {
+    let database = Database::new();
+    database
+        .query(&format!(
+            "createGraph(g).edgePolicy({}).directed({})",
+            policy, directed
+        ))
+        .unwrap();
+    for i in 0..3 {
+        database
+            .query(&format!("g.addV(place).property(name, p{}, string)", i))
+            .unwrap();
+    }
+
+    database
+}
+
+#[test]
+fn edge_policies_limit_parallel_edges() {
+    let database = database_with_policy("multigraph", true);
+    for _ in 0..2 {
+        database.query("g.addE(road, 0, 1)").unwrap();
+    }
+    database.query("g.addE(road, 0, 0)").unwrap();
+    assert_eq!(sorted_ids(&database, "g.V(0).out(road)"), [0, 1, 1]);
+
+    // One edge between two vertices each way, and none from a vertex to itself
+    let database = database_with_policy("simple", true);
+    database.query("g.addE(road, 0, 1)").unwrap();
+    database.query("g.addE(road, 1, 0)").unwrap();
+    for query in [
+        "g.addE(road, 0, 1)",
+        "g.addE(rail, 0, 1)",
+        "g.addE(road, 2, 2)",
+    ] {
+        assert!(is_constraint_violation(&database, query), "{}", query);
+    }
+
+    // Moving an edge onto another's vertices is held to the same policy, but not against itself
+    database.query("g.addE(road, 1, 2)").unwrap();
+    assert!(is_constraint_violation(
+        &database,
+        "g.editE(2).from(0).to(1)"
+    ));
+    assert!(is_constraint_violation(&database, "g.editE(2).to(1)"));
+    database.query("g.editE(2).property(km, 4, int32)").unwrap();
+    database.query("g.editE(2).from(0).to(2)").unwrap();
+
+    let database = database_with_policy("uniquePerLabel", true);
+    database.query("g.addE(road, 0, 1)").unwrap();
+    database.query("g.addE(rail, 0, 1)").unwrap();
+    database.query("g.addE(road, 0, 0)").unwrap();
+    assert!(is_constraint_violation(&database, "g.addE(rail, 0, 1)"));
+    assert_eq!(sorted_ids(&database, "g.V(0).out()"), [0, 1, 1]);
+}
+
+#[test]
+fn undirected_graphs_follow_edges_either_way() {
+    let database = database_with_policy("simple", false);
+    database.query("g.addE(road, 0, 1)").unwrap();
+    database.query("g.addE(road, 2, 0)").unwrap();
+
+    // Joined either way round is the same edge
+    assert!(is_constraint_violation(&database, "g.addE(road, 1, 0)"));
+
+    for query in ["g.V(0).out(road)", "g.V(0).in(road)", "g.V(0).outE().inV()"] {
+        assert_eq!(sorted_ids(&database, query), [1, 2], "{}", query);
+    }
+    assert_eq!(sorted_ids(&database, "g.V(1).out().out()"), [1, 2]);
+    assert_eq!(sorted_ids(&database, "g.V(0).outE().outV()"), [0, 0]);
+    assert_eq!(sorted_ids(&database, "g.V(0).inE().outV()"), [1, 2]);
+
+    // Each edge matches once, whichever way the pattern is written
+    for query in [
+        "g.MATCH (a {name: 'p0'})-[:road]->(b) RETURN b",
+        "g.MATCH (a {name: 'p0'})-[:road]-(b) RETURN b",
+        "g.MATCH (b)<-[:road]-(a {name: 'p0'}) RETURN b",
+    ] {
+        match database.query(query) {
+            Ok(DataResult::Table(_, rows)) => {
+                let mut ids: Vec<usize> = rows
+                    .iter()
+                    .map(|row| match &row[0] {
+                        Binding::Vertex(id, _) => *id,
+                        other => panic!("Unexpected binding: {:?}", other),
+                    })
+                    .collect();
+                ids.sort_unstable();
+                assert_eq!(ids, [1, 2], "{}", query);
+            }
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+}
+
+#[test]
+fn options_are_chosen_when_the_graph_is_created() {
+    let database = Database::new();
+    let graph = database
+        .create_graph_with_options(
+            "g",
+            GraphOptions {
+                edge_policy: EdgePolicy::UniquePerLabel,
+                directed: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let a = graph.add_vertex("place").insert().unwrap();
+    let b = graph.add_vertex("place").insert().unwrap();
+    graph.add_edge("road", a, b).insert().unwrap();
+    assert!(graph.add_edge("road", b, a).insert().is_err());
+
+    // Cloning keeps them
+    database.query("cloneGraph(g, h)").unwrap();
+    assert!(is_constraint_violation(&database, "h.addE(road, 1, 0)"));
+    assert_eq!(sorted_ids(&database, "h.V(1).out(road)"), [0]);
+
+    for (query, position) in [
+        ("createGraph(x).edgePolicy(tree)", 26),
+        ("createGraph(x).directed(yes)", 24),
+        ("createGraph(x).indexed(true)", 15),
+    ] {
+        match database.query(query) {
+            Err(GraphDbError::Parse {
+                position: found, ..
+            }) => assert_eq!(found, position, "{}", query),
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+    assert!(database.graph("x").is_err());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use rust_graph_db::{DataResult, Database, GraphDbError};
+
+use common::{database_with_graph, sorted_ids};
+
+fn added_id(database: &Database, query: &str) -> usize {
+    match database.query(query) {
+        Ok(DataResult::UnsignedInt(id)) => id,
+        other => panic!("Unexpected result for {}: {:?}", query, other),
+    }
+}
+
+#[test]
+fn deleted_ids_are_never_handed_out_again() {
+    let database = database_with_graph("g");
+    for expected in 0..3 {
+        assert_eq!(added_id(&database, "g.addV(person)"), expected);
+    }
+    assert_eq!(added_id(&database, "g.addE(knows, 0, 2)"), 0);
+
+    // Deleting the highest id mustn't let it be handed out again
+    database.query("g.deleteV(2)").unwrap();
+    assert_eq!(added_id(&database, "g.addV(person)"), 3);
+    assert_eq!(added_id(&database, "g.addE(knows, 0, 1)"), 1);
+    assert_eq!(sorted_ids(&database, "g.V()"), [0, 1, 3]);
+
+    database.query("clearGraph(g)").unwrap();
+    assert_eq!(added_id(&database, "g.addV(person)"), 4);
+    assert_eq!(added_id(&database, "g.addE(knows, 4, 4)"), 2);
+}
+
+#[test]
+fn vertices_are_found_by_external_id() {
+    let database = database_with_graph("g");
+    database
+        .query("g.addV(person).externalId(a1b2).property(name, alice, string)")
+        .unwrap();
+    database.query("g.addV(person).externalId(c3d4)").unwrap();
+    database.query("g.addV(person)").unwrap();
+
+    assert_eq!(sorted_ids(&database, "g.V().hasExternalId(c3d4)"), [1]);
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasLabel(person).hasExternalId(a1b2)"),
+        [0]
+    );
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasExternalId(missing)"),
+        Vec::<usize>::new()
+    );
+    assert_eq!(
+        database
+            .graph("g")
+            .unwrap()
+            .vertex(0)
+            .unwrap()
+            .external_id
+            .as_deref(),
+        Some("a1b2")
+    );
+
+    // Deleting a vertex frees its external id for another
+    database.query("g.deleteV(1)").unwrap();
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasExternalId(c3d4)"),
+        Vec::<usize>::new()
+    );
+    database.query("g.addV(robot).externalId(c3d4)").unwrap();
+    assert_eq!(sorted_ids(&database, "g.V().hasExternalId(c3d4)"), [3]);
+}
+
+#[test]
+fn external_ids_are_unique_and_only_set_on_new_vertices() {
+    let database = database_with_graph("g");
+    database.query("g.addV(person).externalId(a1b2)").unwrap();
+
+    assert!(matches!(
+        database.query("g.addV(person).externalId(a1b2)"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    assert!(matches!(
+        database.query("g.editV(0).externalId(e5f6)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        database.query("g.addE(knows, 0, 0).externalId(e5f6)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+
+    assert_eq!(sorted_ids(&database, "g.V()"), [0]);
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasExternalId(e5f6)"),
+        Vec::<usize>::new()
+    );
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use std::{fs, thread};
+
+use rust_graph_db::{DataResult, Database, GraphDbError, Journal, PropertyValue, Vertex};
+
+use common::{ids, property};
+
+fn run(database: &Database, journal: &mut Journal, query: &str) -> DataResult {
+    journal.query(database, query).unwrap()
+}
+
+#[test]
+fn deleted_vertices_come_back_with_their_ids_and_edges() {
+    let database = Database::new();
+    let mut journal = Journal::new();
+    run(&database, &mut journal, "createGraph(g)");
+    for query in [
+        "g.addV(person).property(name, alice, string).externalId(a1)",
+        "g.addV(person).property(name, bob, string)",
+        "g.addE(knows, 0, 1)",
+        "g.addE(knows, 1, 0)",
+        "g.addE(likes, 0, 0)",
+    ] {
+        run(&database, &mut journal, query);
+    }
+
+    run(&database, &mut journal, "g.deleteV(0)");
+    assert_eq!(ids(&database, "g.V()"), [1]);
+
+    journal.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [1]);
+    assert_eq!(ids(&database, "g.V(0).in(knows)"), [1]);
+    assert_eq!(ids(&database, "g.V(0).out(likes)"), [0]);
+    assert_eq!(ids(&database, "g.V().hasExternalId(a1)"), [0]);
+    assert_eq!(
+        property(&database, 0, "name"),
+        Some(PropertyValue::String("alice".to_string()))
+    );
+    let graph = database.graph("g").unwrap();
+    assert_eq!(graph.edge(2).unwrap().to_vertex_id, 0);
+
+    journal.redo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [1]);
+    assert!(ids(&database, "g.V(1).out(knows)").is_empty());
+}
+
+#[test]
+fn edits_are_undone_to_the_exact_previous_values() {
+    let database = Database::new();
+    let mut journal = Journal::new();
+    database.create_graph("g").unwrap();
+    run(
+        &database,
+        &mut journal,
+        "g.addV(person).property(age, 30, int32).property(city, Leeds, string)",
+    );
+
+    run(
+        &database,
+        &mut journal,
+        "g.editV(0).property(age, 31, int64).property(email, a@b.c, string).removeProperty(city)",
+    );
+    journal.undo(&database).unwrap();
+    assert_eq!(
+        property(&database, 0, "age"),
+        Some(PropertyValue::Int32(30))
+    );
+    assert_eq!(
+        property(&database, 0, "city"),
+        Some(PropertyValue::String("Leeds".to_string()))
+    );
+    assert_eq!(property(&database, 0, "email"), None);
+
+    journal.redo(&database).unwrap();
+    assert_eq!(
+        property(&database, 0, "age"),
+        Some(PropertyValue::Int64(31))
+    );
+    assert_eq!(property(&database, 0, "city"), None);
+
+    // Undoing the add removes the vertex, and reads don't count as steps
+    ids(&database, "g.V()");
+    journal.undo(&database).unwrap();
+    journal.undo(&database).unwrap();
+    assert!(ids(&database, "g.V()").is_empty());
+    assert!(matches!(
+        journal.undo(&database),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn changes_that_cant_be_undone_yet_are_kept_for_later() {
+    let database = Database::new();
+    let mut journal = Journal::new();
+    database.create_graph("g").unwrap();
+    for query in ["g.addV(person)", "g.addV(person)", "g.addE(knows, 0, 1)"] {
+        run(&database, &mut journal, query);
+    }
+    run(&database, &mut journal, "g.deleteV(1)");
+
+    // Someone else deletes the edge's other end, so the vertex comes back but the edge can't
+    database.query("g.deleteV(0)").unwrap();
+    assert!(matches!(
+        journal.undo(&database),
+        Err(GraphDbError::NotFound { .. })
+    ));
+    assert_eq!(ids(&database, "g.V()"), [1]);
+
+    // Once it's back, undoing carries on where it stopped
+    let graph = database.graph("g").unwrap();
+    let person = Vertex::new("person".to_string(), Vec::new());
+    graph.write().unwrap().restore_vertex(0, person).unwrap();
+    journal.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [1]);
+
+    // Both parts are redone, in the order they were undone
+    journal.redo(&database).unwrap();
+    journal.redo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [0]);
+    journal.undo(&database).unwrap();
+    journal.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [1]);
+}
+
+#[test]
+fn the_journal_is_bounded_and_forgets_what_cant_be_undone() {
+    let database = Database::new();
+    let mut journal = Journal::with_capacity(2);
+    database.create_graph("g").unwrap();
+    for _ in 0..3 {
+        run(&database, &mut journal, "g.addV(person)");
+    }
+
+    journal.undo(&database).unwrap();
+    journal.undo(&database).unwrap();
+    assert!(journal.undo(&database).is_err());
+    assert_eq!(ids(&database, "g.V()"), [0]);
+
+    // A new change can't be followed by redoing older ones
+    journal.redo(&database).unwrap();
+    run(&database, &mut journal, "g.addE(knows, 0, 1)");
+    assert!(journal.redo(&database).is_err());
+    journal.undo(&database).unwrap();
+    assert!(ids(&database, "g.V(0).out()").is_empty());
+
+    run(&database, &mut journal, "clearGraph(g)");
+    assert!(journal.undo(&database).is_err());
+}
+
+#[test]
+fn loading_a_persistent_graph_is_not_undone() {
+    let path = std::env::temp_dir().join(format!("journal-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let create = format!(
+        "createGraph(g).type(persistent).option(path, {})",
+        path.display()
+    );
+    let database = Database::new();
+    database.query(&create).unwrap();
+    database.query("g.addV(person)").unwrap();
+    database.query("g.addE(knows, 0, 0)").unwrap();
+
+    let database = Database::new();
+    let mut journal = Journal::new();
+    let events = database.changes().subscribe(None).unwrap();
+    run(&database, &mut journal, &create);
+    let changes: Vec<&str> = events
+        .try_iter()
+        .map(|event| event.change.as_str())
+        .collect();
+    assert_eq!(changes, ["graphLoaded"]);
+
+    // The graph's contents were there before, so aren't removed
+    assert!(journal.undo(&database).is_err());
+    assert_eq!(ids(&database, "g.V(0).out(knows)"), [0]);
+    run(&database, &mut journal, "g.addV(person)");
+    journal.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [0]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn only_the_sessions_own_changes_are_undone() {
+    let database = Database::new();
+    database.create_graph("g").unwrap();
+    let mut journal = Journal::new();
+    run(&database, &mut journal, "g.addV(person)");
+
+    // Another session's changes, and changes made outside any session, aren't journaled here
+    let mut other = Journal::new();
+    thread::scope(|scope| {
+        scope.spawn(|| other.query(&database, "g.addV(robot)").unwrap());
+    });
+    database.query("g.addV(city)").unwrap();
+
+    journal.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [1, 2]);
+    assert!(journal.undo(&database).is_err());
+    other.undo(&database).unwrap();
+    assert_eq!(ids(&database, "g.V()"), [2]);
+
+    // Journaling doesn't keep events for anyone else
+    assert!(!database.changes().is_followed());
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use rust_graph_db::{Database, PropertyValue, Vertex};
+
+use common::{database_with_graph, ids};
+
+fn label_count(database: &Database, label: &str) -> usize {
+    database
+        .graph("g")
+        .unwrap()
+        .read()
+        .unwrap()
+        .vertex_count(Some(label))
+}
+
+#[test]
+fn label_index_stays_sorted_through_removes_and_restores() {
+    let database = database_with_graph("g");
+    for label in ["person", "city", "person", "person", "city", "person"] {
+        database.query(&format!("g.addV({})", label)).unwrap();
+    }
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [0, 2, 3, 5]);
+
+    database.query("g.deleteV(2)").unwrap();
+    database.query("g.deleteV(0)").unwrap();
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [3, 5]);
+    assert_eq!(label_count(&database, "person"), 2);
+
+    // Restored vertices go back into the middle of the index, not onto its end
+    let graph = database.graph("g").unwrap();
+    for id in [2, 0] {
+        let vertex = Vertex::new("person".to_string(), Vec::new());
+        graph.write().unwrap().restore_vertex(id, vertex).unwrap();
+    }
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [0, 2, 3, 5]);
+    assert_eq!(ids(&database, "g.V().hasLabel(city)"), [1, 4]);
+    assert_eq!(label_count(&database, "person"), 4);
+
+    database.query("g.addV(person)").unwrap();
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [0, 2, 3, 5, 6]);
+}
+
+#[test]
+fn clearing_forgets_labels_and_keys() {
+    let database = database_with_graph("g");
+    database
+        .query("g.addV(person).property(name, alice, string)")
+        .unwrap();
+    database
+        .query("g.addV(city).property(size, 3, int32)")
+        .unwrap();
+    database.query("g.addE(lives_in, 0, 1)").unwrap();
+
+    database.query("clearGraph(g)").unwrap();
+    assert!(ids(&database, "g.V().hasLabel(person)").is_empty());
+    assert_eq!(label_count(&database, "person"), 0);
+    assert!(database
+        .graph("g")
+        .unwrap()
+        .read()
+        .unwrap()
+        .property_names()
+        .is_empty());
+
+    // New entities intern their names afresh, and still read back as they were written
+    database
+        .query("g.addV(city).property(name, leeds, string)")
+        .unwrap();
+    database.query("g.addV(person)").unwrap();
+    database.query("g.addE(lives_in, 3, 2)").unwrap();
+    assert_eq!(ids(&database, "g.V().hasLabel(city)"), [2]);
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [3]);
+    assert_eq!(ids(&database, "g.V(3).out(lives_in)"), [2]);
+    assert_eq!(
+        ids(&database, "g.V().hasProperty(size)"),
+        Vec::<usize>::new()
+    );
+    let graph = database.graph("g").unwrap();
+    assert_eq!(graph.vertex(2).unwrap().label, "city");
+    assert_eq!(graph.edge(1).unwrap().edge_vertex.label, "lives_in");
+}
+
+#[test]
+fn names_are_matched_exactly() {
+    let database = database_with_graph("g");
+    database
+        .query("g.addV(Person).property(Name, alice, string)")
+        .unwrap();
+    database
+        .query("g.addV(person).property(name, bob, string)")
+        .unwrap();
+    database
+        .query("g.addV(person).property(name, carol, string).property(Name, c, string)")
+        .unwrap();
+
+    assert_eq!(ids(&database, "g.V().hasLabel(Person)"), [0]);
+    assert_eq!(ids(&database, "g.V().hasLabel(person)"), [1, 2]);
+    assert!(ids(&database, "g.V().hasLabel(PERSON)").is_empty());
+    assert_eq!(ids(&database, "g.V().hasProperty(Name)"), [0, 2]);
+
+    let mut names = database
+        .graph("g")
+        .unwrap()
+        .read()
+        .unwrap()
+        .property_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    names.sort();
+    assert_eq!(names, ["Name", "name"]);
+    assert_eq!(
+        database
+            .graph("g")
+            .unwrap()
+            .vertex(2)
+            .unwrap()
+            .get_property_value("Name"),
+        Some(&PropertyValue::String("c".to_string()))
+    );
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::thread;
+
+use rust_graph_db::{error::EntityKind, Database, GraphDbError, PropertyValue};
+
+use common::sorted_ids;
+
+#[test]
+fn builders_add_typed_vertices_and_edges() {
+    let database = Database::new();
+    let graph = database.create_graph("social").unwrap();
+
+    let alice = graph
+        .add_vertex("person")
+        .property("name", "alice")
+        .property("age", 30i32)
+        .property("score", 0.5f64)
+        .external_id("a1b2")
+        .insert()
+        .unwrap();
+    let bob = graph.add_vertex("person").insert().unwrap();
+    let knows = graph
+        .add_edge("knows", alice, bob)
+        .property("since", 2020i64)
+        .insert()
+        .unwrap();
+
+    let vertex = graph.vertex(alice).unwrap();
+    assert_eq!(vertex.label, "person");
+    assert_eq!(vertex.external_id.as_deref(), Some("a1b2"));
+    assert_eq!(
+        vertex.get_property_value("name"),
+        Some(&PropertyValue::String("alice".to_string()))
+    );
+    assert_eq!(vertex.get_property_value("age"), Some(&30i32.into()));
+    assert_eq!(vertex.get_property_value("score"), Some(&0.5f64.into()));
+
+    let edge = graph.edge(knows).unwrap();
+    assert_eq!((edge.from_vertex_id, edge.to_vertex_id), (alice, bob));
+    assert_eq!(
+        edge.edge_vertex.get_property_value("since"),
+        Some(&2020i64.into())
+    );
+}
+
+#[test]
+fn handles_and_queries_see_the_same_graph() {
+    let database = Database::new();
+    let graph = database.create_graph("social").unwrap();
+    let alice = graph.add_vertex("person").insert().unwrap();
+    database.query("social.addV(person)").unwrap();
+
+    assert_eq!(
+        database.graph("social").unwrap().vertex(1).unwrap().label,
+        "person"
+    );
+    graph.remove_vertex(alice).unwrap();
+    assert!(matches!(
+        database.query("social.V(0)"),
+        Err(GraphDbError::NotFound {
+            kind: EntityKind::Vertex,
+            ..
+        })
+    ));
+
+    assert!(matches!(
+        graph.add_edge("knows", alice, 1).insert(),
+        Err(GraphDbError::NotFound { .. })
+    ));
+    assert!(matches!(
+        database.graph("missing"),
+        Err(GraphDbError::NotFound {
+            kind: EntityKind::Graph,
+            ..
+        })
+    ));
+    assert!(matches!(
+        database.create_graph("social"),
+        Err(GraphDbError::GraphExists(_))
+    ));
+}
+
+#[test]
+fn handles_can_be_shared_between_threads() {
+    let database = Database::new();
+    let graph = database.create_graph("social").unwrap();
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let graph = graph.clone();
+            thread::spawn(move || {
+                for _ in 0..25 {
+                    graph.add_vertex("person").insert().unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(
+        sorted_ids(&database, "social.V()"),
+        (0..100).collect::<Vec<_>>()
+    );
+}
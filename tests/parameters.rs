@@ -0,0 +1,204 @@
+mod common;
+
+use std::sync::Arc;
+
+use rust_graph_db::{Binding, DataResult, Database, GraphDbError, Parameters, PropertyValue};
+
+use common::ids_of;
+
+fn database_with_graph() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    for name in ["alice", "Smith, J. (jr)", "bob.v2"] {
+        graph
+            .add_vertex("person")
+            .property("name", name)
+            .insert()
+            .unwrap();
+    }
+    graph.add_edge("knows", 0, 1).insert().unwrap();
+
+    database
+}
+
+#[test]
+fn bound_values_are_never_split_or_parsed() {
+    let database = database_with_graph();
+    let query = "g.V().hasLabel($label).hasPropertyValue(name, $name)";
+
+    for (name, expected) in [
+        ("Smith, J. (jr)", vec![1]),
+        ("bob.v2", vec![2]),
+        ("$name", vec![]),
+    ] {
+        let parameters = Parameters::new().set("label", "person").set("$name", name);
+        assert_eq!(
+            ids_of(database.query_with(query, &parameters)),
+            expected,
+            "{}",
+            name
+        );
+    }
+
+    let parameters = Parameters::new().set("name", "x).out(knows");
+    assert_eq!(
+        ids_of(database.query_with("g.V().hasPropertyLike(name, $name)", &parameters)),
+        Vec::<usize>::new()
+    );
+
+    // Ignoring case applies to bound values, and regexes are compiled from them as given
+    let parameters = Parameters::new().set("Name", "SMITH, J. (JR)");
+    assert_eq!(
+        ids_of(database.query_with("g.V().hasPropertyValueIgnoreCase(name, $Name)", &parameters)),
+        [1]
+    );
+    let parameters = Parameters::new().set("pattern", r"^\w+, J\. \(");
+    assert_eq!(
+        ids_of(database.query_with("g.V().hasPropertyRegex(name, $pattern)", &parameters)),
+        [1]
+    );
+    let parameters = Parameters::new().set("label", "knows");
+    assert_eq!(
+        ids_of(database.query_with("g.V(0).out($label)", &parameters)),
+        [1]
+    );
+}
+
+#[test]
+fn property_values_take_the_type_they_were_bound_with() {
+    let database = database_with_graph();
+
+    let parameters = Parameters::new()
+        .set("label", "city")
+        .set("name", "St. Albans, Herts")
+        .set("population", 147_000i64);
+    let id = match database.query_with(
+        "g.addV($label).property(name, $name).property(population, $population, int64)",
+        &parameters,
+    ) {
+        Ok(DataResult::UnsignedInt(id)) => id,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+
+    let city = database.graph("g").unwrap().vertex(id).unwrap();
+    assert_eq!(city.label, "city");
+    assert_eq!(
+        city.get_property_value("name"),
+        Some(&PropertyValue::String("St. Albans, Herts".to_string()))
+    );
+    assert_eq!(
+        city.get_property_value("population"),
+        Some(&PropertyValue::Int64(147_000))
+    );
+
+    // Declared types must match, and names must be strings
+    let parameters = Parameters::new().set("population", 147_000i32);
+    assert!(matches!(
+        database.query_with(
+            "g.editV(0).property(population, $population, int64)",
+            &parameters
+        ),
+        Err(GraphDbError::TypeMismatch { .. })
+    ));
+    let parameters = Parameters::new().set("label", 3i32);
+    assert!(matches!(
+        database.query_with("g.V().hasLabel($label)", &parameters),
+        Err(GraphDbError::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn prepared_queries_are_parsed_once_and_run_with_any_values() {
+    let database = database_with_graph();
+    let text = "g.V().hasPropertyValue(name, $name).out($edge)";
+
+    let prepared = database.prepare(text).unwrap();
+    assert_eq!(prepared.parameters(), ["name", "edge"]);
+    assert!(Arc::ptr_eq(&prepared, &database.prepare(text).unwrap()));
+
+    for (name, expected) in [("alice", vec![1]), ("bob.v2", vec![])] {
+        let parameters = Parameters::new().set("name", name).set("edge", "knows");
+        assert_eq!(
+            ids_of(database.execute_prepared(&prepared, &parameters)),
+            expected
+        );
+    }
+
+    // Queries without placeholders can be prepared too
+    let prepared = database.prepare("g.V().hasLabel(person)").unwrap();
+    assert!(prepared.parameters().is_empty());
+    assert_eq!(
+        ids_of(database.execute_prepared(&prepared, &Parameters::new())),
+        [0, 1, 2]
+    );
+}
+
+#[test]
+fn match_queries_take_placeholders_in_where_and_property_maps() {
+    let database = database_with_graph();
+    let names = |query: &str, parameters: &Parameters| match database.query_with(query, parameters)
+    {
+        Ok(DataResult::Table(_, rows)) => rows
+            .into_iter()
+            .map(|row| match &row[..] {
+                [Binding::Value(value)] => value.to_string(),
+                other => panic!("Unexpected row: {:?}", other),
+            })
+            .collect::<Vec<String>>(),
+        other => panic!("Unexpected result for {}: {:?}", query, other),
+    };
+
+    let parameters = Parameters::new().set("n", "Smith, J. (jr)");
+    assert_eq!(
+        names("g.MATCH (a) WHERE a.name = $n RETURN a.name", &parameters),
+        ["Smith, J. (jr)"]
+    );
+    assert_eq!(
+        names(
+            "g.MATCH (a:person {name: $n})<-[:knows]-(b) RETURN b.name",
+            &parameters
+        ),
+        ["alice"]
+    );
+
+    // Prepared once, the same query is run with each value in turn
+    let prepared = database
+        .prepare("g.MATCH (a {name: $from})-[:knows]->(b) WHERE b.name <> $not RETURN b")
+        .unwrap();
+    assert_eq!(prepared.parameters(), ["from", "not"]);
+    for (not, expected) in [("bob.v2", 1), ("Smith, J. (jr)", 0)] {
+        let parameters = Parameters::new().set("from", "alice").set("not", not);
+        match database.execute_prepared(&prepared, &parameters) {
+            Ok(DataResult::Table(_, rows)) => assert_eq!(rows.len(), expected, "{}", not),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn every_placeholder_needs_a_value() {
+    let database = database_with_graph();
+
+    for result in [
+        database.query("g.V().hasPropertyValue(name, $name)"),
+        database.query_with(
+            "g.V().hasPropertyValue(name, $name).hasLabel($label)",
+            &Parameters::new().set("name", "alice"),
+        ),
+        database.query("g.addV(person).property(age, $age)"),
+        database.query("g.MATCH (a {name: $name}) RETURN a"),
+    ] {
+        match result {
+            Err(GraphDbError::InvalidArgument(message)) => {
+                assert!(message.starts_with("No value bound for $"), "{}", message)
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    // Triggers are kept, so can't hold placeholders
+    assert!(matches!(
+        database.query("g.createTrigger(t, before, insert, person).property(age, $age)"),
+        Err(GraphDbError::Parse { .. })
+    ));
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use rust_graph_db::{Binding, Database, GraphDbError};
+
+use common::table;
+
+/// alice knows bob and carol, bob works at acme, and carol knows alice back
+fn database_with_graph() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+
+    let alice = graph
+        .add_vertex("person")
+        .property("name", "alice")
+        .property("age", 30i32)
+        .insert()
+        .unwrap();
+    let bob = graph
+        .add_vertex("person")
+        .property("name", "bob")
+        .property("age", 25i32)
+        .insert()
+        .unwrap();
+    let carol = graph
+        .add_vertex("person")
+        .property("name", "carol")
+        .insert()
+        .unwrap();
+    let acme = graph
+        .add_vertex("company")
+        .property("name", "acme")
+        .insert()
+        .unwrap();
+
+    graph.add_edge("knows", alice, bob).insert().unwrap();
+    graph.add_edge("knows", alice, carol).insert().unwrap();
+    graph.add_edge("knows", carol, alice).insert().unwrap();
+    graph
+        .add_edge("works_at", bob, acme)
+        .property("since", 2020i64)
+        .insert()
+        .unwrap();
+    database
+}
+
+/// Each row as text: vertex and edge ids, property values, or null
+fn rows(database: &Database, query: &str) -> Vec<Vec<String>> {
+    let (_, rows) = table(database, query);
+    let mut rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|binding| match binding {
+                    Binding::Vertex(id, _) => format!("v{}", id),
+                    Binding::Edge(id, _) => format!("e{}", id),
+                    Binding::Value(value) => value.to_string(),
+                    Binding::Null => "null".to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    rows.sort();
+    rows
+}
+
+#[test]
+fn multi_hop_patterns_bind_variables_across_the_path() {
+    let database = database_with_graph();
+
+    let (columns, bindings) = table(
+        &database,
+        "g.MATCH (a:person)-[:knows]->(b)-[:works_at]->(c) WHERE a.name = 'alice' RETURN b, c",
+    );
+    assert_eq!(columns, ["b", "c"]);
+    assert_eq!(bindings.len(), 1);
+    match &bindings[0][..] {
+        [Binding::Vertex(1, bob), Binding::Vertex(3, acme)] => {
+            assert_eq!(bob.get_property_value("name"), Some(&"bob".into()));
+            assert_eq!(acme.label, "company");
+        }
+        other => panic!("Unexpected row: {:?}", other),
+    }
+
+    // Written from the other end, or starting from the rarer label, the matches are the same
+    assert_eq!(
+        rows(
+            &database,
+            "g.MATCH (c:company)<-[:works_at]-(b)<-[:knows]-(a:person {name: 'alice'}) RETURN b, c"
+        ),
+        [["v1", "v3"]]
+    );
+
+    // A variable used twice must be the same vertex each time
+    assert_eq!(
+        rows(
+            &database,
+            "g.MATCH (a)-[:knows]->(b)-[:knows]->(a) RETURN a.name, b.name"
+        ),
+        [["alice", "carol"], ["carol", "alice"]]
+    );
+
+    // Relationship properties, and comparisons across integer types
+    assert_eq!(
+        rows(&database, "g.match (p)-[w:works_at {since: 2020}]->(c) where p.age < 30 and not p.age IS NULL return p.name, w.since"),
+        [["bob", "2020"]]
+    );
+}
+
+#[test]
+fn optional_matches_keep_rows_with_nulls() {
+    let database = database_with_graph();
+
+    let query = "g.MATCH (a:person {name: 'alice'})-[:knows]->(b) \
+                 OPTIONAL MATCH (b)-[:works_at]->(c) \
+                 RETURN b.name AS friend, c.name AS employer";
+    let (columns, _) = table(&database, query);
+    assert_eq!(columns, ["friend", "employer"]);
+    assert_eq!(rows(&database, query), [["bob", "acme"], ["carol", "null"]]);
+
+    // The optional part's WHERE only decides whether it matched
+    assert_eq!(
+        rows(&database, "g.MATCH (a:person) OPTIONAL MATCH (a)-[:works_at]->(c) WHERE c.name = 'other' RETURN a.name, c"),
+        [["alice", "null"], ["bob", "null"], ["carol", "null"]]
+    );
+
+    // Null variables don't match later patterns, and fail comparisons
+    assert_eq!(
+        rows(&database, "g.MATCH (a:person {name: 'carol'}) OPTIONAL MATCH (a)-[:works_at]->(c) MATCH (c)--(d) RETURN d"),
+        Vec::<Vec<String>>::new()
+    );
+    assert_eq!(
+        rows(&database, "g.MATCH (a:person) OPTIONAL MATCH (a)-[:works_at]->(c) MATCH (a) WHERE c.name <> 'acme' RETURN a"),
+        Vec::<Vec<String>>::new()
+    );
+    assert_eq!(
+        rows(&database, "g.MATCH (a:person) OPTIONAL MATCH (a)-[:works_at]->(c) MATCH (a) WHERE c IS NULL RETURN a.name"),
+        [["alice"], ["carol"]]
+    );
+}
+
+#[test]
+fn undirected_relationships_are_each_matched_once_per_path() {
+    let database = database_with_graph();
+
+    // alice and carol know each other both ways, so two distinct edges join them
+    assert_eq!(
+        rows(
+            &database,
+            "g.MATCH (a {name: 'carol'})-[r:knows]-(b) RETURN b.name, r"
+        ),
+        [["alice", "e1"], ["alice", "e2"]]
+    );
+    assert_eq!(
+        rows(
+            &database,
+            "g.MATCH (a {name: 'carol'})-[r1]-(b)-[r2]-(c) WHERE c.name = 'carol' RETURN r1, r2"
+        ),
+        [["e1", "e2"], ["e2", "e1"]]
+    );
+
+    let (columns, all) = table(&database, "g.MATCH (a)-->(b) RETURN * LIMIT 2");
+    assert_eq!(columns, ["a", "b"]);
+    assert_eq!(all.len(), 2);
+}
+
+#[test]
+fn malformed_queries_are_refused() {
+    let database = database_with_graph();
+
+    match database.query("g.MATCH (a)-[:knows]-> RETURN a") {
+        Err(GraphDbError::Parse {
+            position, found, ..
+        }) => {
+            assert_eq!((position, found.as_str()), (23, "RETURN"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    for query in [
+        "g.MATCH (a)<-[:knows]->(b) RETURN a",
+        "g.MATCH (a) WHERE a.name = 'unterminated RETURN a",
+        "g.MATCH (a) RETURN a LIMIT many",
+        "g.OPTIONAL (a) RETURN a",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::Parse { .. })),
+            "{}",
+            query
+        );
+    }
+
+    for query in [
+        "g.MATCH (a) RETURN b",
+        "g.MATCH (a) WHERE b.name = 'x' RETURN a",
+        "g.MATCH (a)-[a]->(b) RETURN a",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::InvalidArgument(_))),
+            "{}",
+            query
+        );
+    }
+
+    assert!(matches!(
+        database.query("missing.MATCH (a) RETURN a"),
+        Err(GraphDbError::NotFound { .. })
+    ));
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use rust_graph_db::{graph::spatial::Point, Binding, Database, GraphDbError, PropertyValue};
+
+use common::{ids, table};
+
+/// Twenty people who each know the next, and a handful of companies and cities
+fn database_with_graph() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+
+    let mut people = Vec::new();
+    for i in 0..20i32 {
+        let person = graph
+            .add_vertex("person")
+            .property("name", format!("person{}", i))
+            .property("age", 20 + i)
+            .insert()
+            .unwrap();
+        people.push(person);
+    }
+    for pair in people.windows(2) {
+        graph.add_edge("knows", pair[0], pair[1]).insert().unwrap();
+    }
+    for name in ["acme", "initech"] {
+        graph
+            .add_vertex("company")
+            .property("name", name)
+            .insert()
+            .unwrap();
+    }
+    for (name, latitude, longitude) in [("London", 51.5074, -0.1278), ("Paris", 48.8566, 2.3522)] {
+        graph
+            .add_vertex("city")
+            .property("name", name)
+            .property("location", Point::new(latitude, longitude).unwrap())
+            .insert()
+            .unwrap();
+    }
+
+    database
+}
+
+/// The operation column of a plan, in order
+fn operations(database: &Database, query: &str) -> Vec<String> {
+    let (_, rows) = table(database, query);
+    rows.into_iter()
+        .map(|row| match &row[1] {
+            Binding::Value(PropertyValue::String(operation)) => operation.clone(),
+            other => panic!("Unexpected operation: {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn selective_filters_run_first_and_indexes_find_the_first_vertices() {
+    let database = database_with_graph();
+
+    assert_eq!(
+        operations(
+            &database,
+            "g.explain(V().hasProperty(name).hasPropertyRegex(name, ^a).hasPropertyLike(name, a%).hasLabel(company))"
+        ),
+        [
+            "hasLabel(company) using label index",
+            "hasPropertyLike(name, a%)",
+            "hasPropertyRegex(name, ^a)",
+            "hasProperty(name)",
+        ]
+    );
+
+    // Equality beats a common label, and filters after a traversal are ordered on their own
+    assert_eq!(
+        operations(
+            &database,
+            "g.explain(V().hasLabel(person).hasPropertyValue(name, person3).out(knows).hasPropertyRegex(name, 4$).hasPropertyValue(age, 24))"
+        ),
+        [
+            "hasLabel(person) using label index",
+            "hasPropertyValue(name, person3)",
+            "out(knows)",
+            "hasPropertyValue(age, 24)",
+            "hasPropertyRegex(name, 4$)",
+        ]
+    );
+
+    assert_eq!(
+        operations(&database, "g.explain(V(3).hasLabel(person))"),
+        ["V(3) using vertex id", "hasLabel(person)"]
+    );
+    assert_eq!(
+        operations(&database, "g.explain(V().hasProperty(age))"),
+        ["scan every vertex", "hasProperty(age)"]
+    );
+}
+
+#[test]
+fn reordering_doesnt_change_results() {
+    let database = database_with_graph();
+
+    for (query, expected) in [
+        (
+            "g.V().hasPropertyRegex(name, ^person1).hasLabel(person).hasPropertyValue(age, 31)",
+            vec![11],
+        ),
+        (
+            "g.V().hasProperty(age).hasPropertyValue(name, person3).out(knows).hasLabel(person)",
+            vec![4],
+        ),
+        (
+            "g.V().hasPropertyRegex(name, e$).hasLabel(company)",
+            vec![20],
+        ),
+        ("g.V().hasLabel(city).out()", vec![]),
+    ] {
+        assert_eq!(ids(&database, query), expected, "{}", query);
+    }
+}
+
+#[test]
+fn spatial_indexes_are_used_once_created() {
+    let database = database_with_graph();
+    let query = "g.V().hasProperty(location).hasPropertyWithinRadius(location, 51.5, -0.12, 10)";
+    let explain = format!("g.explain({})", &query[2..]);
+
+    assert_eq!(
+        operations(&database, &explain),
+        [
+            "scan every vertex",
+            "hasPropertyWithinRadius(location, 51.5, -0.12, 10)",
+            "hasProperty(location)",
+        ]
+    );
+    assert_eq!(ids(&database, query), [22]);
+
+    database.query("g.createSpatialIndex(location)").unwrap();
+    assert_eq!(
+        operations(&database, &explain),
+        [
+            "hasPropertyWithinRadius(location, 51.5, -0.12, 10) using spatial index",
+            "hasProperty(location)",
+        ]
+    );
+    assert_eq!(ids(&database, query), [22]);
+}
+
+#[test]
+fn profiles_count_the_rows_each_step_leaves() {
+    let database = database_with_graph();
+
+    let (columns, rows) = table(
+        &database,
+        "g.profile(V().hasLabel(person).hasPropertyValue(name, person3).out(knows))",
+    );
+    assert_eq!(
+        columns,
+        ["step", "operation", "estimated rows", "rows", "ms"]
+    );
+
+    let counts: Vec<i64> = rows
+        .iter()
+        .map(|row| match row[3] {
+            Binding::Value(PropertyValue::Int64(count)) => count,
+            ref other => panic!("Unexpected count: {:?}", other),
+        })
+        .collect();
+    assert_eq!(counts, [20, 1, 1]);
+    assert!(rows
+        .iter()
+        .all(|row| matches!(row[4], Binding::Value(PropertyValue::Float64(ms)) if ms >= 0.0)));
+}
+
+#[test]
+fn only_vertex_queries_can_be_explained() {
+    let database = database_with_graph();
+
+    for query in [
+        "g.explain(addV(person))",
+        "g.profile(V().hasLabel(person)).count()",
+        "g.explain(V().hasLable(person))",
+        "g.explain()",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::Parse { .. })),
+            "{}",
+            query
+        );
+    }
+
+    match database.query("g.explain(V().hasLable(person))") {
+        Err(GraphDbError::Parse { position, .. }) => assert_eq!(position, 14),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert!(matches!(
+        database.query("missing.explain(V())"),
+        Err(GraphDbError::NotFound { .. })
+    ));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use rust_graph_db::{Database, GraphDbError};
+
+use common::ids;
+
+/// ed and eve report to max, who reports to zoe. zoe, the ceo, reports to herself
+fn database_with_org_chart() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("org").unwrap();
+
+    let zoe = graph.add_vertex("ceo").insert().unwrap();
+    let max = graph.add_vertex("manager").insert().unwrap();
+    let eve = graph.add_vertex("employee").insert().unwrap();
+    let ed = graph.add_vertex("employee").insert().unwrap();
+    for (from, to) in [(max, zoe), (eve, max), (ed, max), (zoe, zoe)] {
+        graph.add_edge("reportsTo", from, to).insert().unwrap();
+    }
+
+    database
+}
+
+#[test]
+fn walks_stop_after_times_or_until() {
+    let database = database_with_org_chart();
+
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).times(1)"),
+        [1]
+    );
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).times(2)"),
+        [0]
+    );
+    // zoe's self-loop keeps her walk going
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).times(7)"),
+        [0]
+    );
+
+    assert_eq!(
+        ids(
+            &database,
+            "org.V().hasLabel(employee).repeat(out(reportsTo)).until(hasLabel(ceo))"
+        ),
+        [0]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(0).repeat(in(reportsTo)).until(hasLabel(employee))"
+        ),
+        [2, 3]
+    );
+
+    // Emitting keeps everyone on the way, in the order first reached, and only once
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).emit()"),
+        [1, 0]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(0).repeat(in(reportsTo)).emit().hasLabel(employee)"
+        ),
+        [2, 3]
+    );
+}
+
+#[test]
+fn cycles_and_self_loops_end_walks() {
+    let database = database_with_org_chart();
+    let graph = database.graph("org").unwrap();
+    graph.add_edge("reportsTo", 0, 3).insert().unwrap();
+
+    // Nobody reports to a board, so without a cycle guard these would never end
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(3).repeat(out(reportsTo)).until(hasLabel(board))"
+        ),
+        Vec::<usize>::new()
+    );
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).emit()"),
+        [1, 0, 3]
+    );
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo))"),
+        Vec::<usize>::new()
+    );
+
+    // eve is a dead end, so where walks down from zoe end
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(0).repeat(in(reportsTo)).hasLabel(employee)"
+        ),
+        [2]
+    );
+}
+
+#[test]
+fn walks_can_be_kept_by_whether_they_revisit_a_vertex() {
+    let database = database_with_org_chart();
+
+    assert_eq!(
+        ids(&database, "org.V(3).repeat(out(reportsTo)).times(3)"),
+        [0]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(3).repeat(out(reportsTo)).times(3).simplePath()"
+        ),
+        Vec::<usize>::new()
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(3).repeat(out(reportsTo)).emit().simplePath()"
+        ),
+        [1, 0]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(3).repeat(out(reportsTo)).emit().cyclicPath()"
+        ),
+        [0]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "org.V(3).repeat(out(reportsTo)).times(2).cyclicPath()"
+        ),
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn modifiers_need_a_repeat() {
+    let database = database_with_org_chart();
+
+    for (query, position) in [
+        ("org.V().times(2)", 8),
+        ("org.V().out(reportsTo).emit()", 23),
+        ("org.V().repeat(values(name))", 15),
+        ("org.V().repeat(out(reportsTo)).until(hasLable(ceo))", 37),
+        ("org.V().repeat(out(reportsTo)).times(many)", 37),
+    ] {
+        match database.query(query) {
+            Err(GraphDbError::Parse {
+                position: found, ..
+            }) => {
+                assert_eq!(found, position, "{}", query)
+            }
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+}
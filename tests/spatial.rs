@@ -0,0 +1,116 @@
+mod common;
+
+use rust_graph_db::{graph::spatial::Point, Database, GraphDbError};
+
+use common::ids;
+
+const CITIES: [(&str, f64, f64); 6] = [
+    ("London", 51.5074, -0.1278),
+    ("Paris", 48.8566, 2.3522),
+    ("Brussels", 50.8503, 4.3517),
+    ("New York", 40.7128, -74.0060),
+    ("Suva", -18.1248, 178.4501),
+    ("Apia", -13.8507, -171.7514),
+];
+
+fn database_with_cities() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    for (name, latitude, longitude) in CITIES {
+        graph
+            .add_vertex("city")
+            .property("name", name)
+            .property("location", Point::new(latitude, longitude).unwrap())
+            .insert()
+            .unwrap();
+    }
+
+    database
+}
+
+/// Runs each query without and then with a spatial index, expecting the same result both times
+fn assert_indexed_and_unindexed(queries: &[(&str, Vec<usize>)]) {
+    let database = database_with_cities();
+    for (query, expected) in queries {
+        assert_eq!(&ids(&database, query), expected, "{}", query);
+    }
+
+    database.query("g.createSpatialIndex(location)").unwrap();
+    for (query, expected) in queries {
+        assert_eq!(&ids(&database, query), expected, "indexed {}", query);
+    }
+}
+
+#[test]
+fn geo_filters_match_with_and_without_an_index() {
+    assert_indexed_and_unindexed(&[
+        // London to Paris is about 344 km, and to Brussels about 320 km
+        (
+            "g.V().hasPropertyWithinRadius(location, 51.5, -0.12, 330)",
+            vec![0, 2],
+        ),
+        (
+            "g.V().hasPropertyWithinRadius(location, 51.5, -0.12, 350)",
+            vec![0, 1, 2],
+        ),
+        (
+            "g.V().hasPropertyWithinBox(location, 48, -1, 52, 3)",
+            vec![0, 1],
+        ),
+        // Across the antimeridian
+        (
+            "g.V().hasPropertyWithinBox(location, -20, 170, -10, -170)",
+            vec![4, 5],
+        ),
+        (
+            "g.V().hasPropertyWithinRadius(location, -16, 180, 1000)",
+            vec![4, 5],
+        ),
+        // Nearest first
+        (
+            "g.V().hasPropertyNearest(location, 50.9, 4.4, 2)",
+            vec![2, 1],
+        ),
+        (
+            "g.V().hasPropertyNearest(location, 0, 0, 10)",
+            vec![1, 2, 0, 3, 4, 5],
+        ),
+    ]);
+}
+
+#[test]
+fn index_follows_updates_and_deletes() {
+    let database = database_with_cities();
+    database.query("g.createSpatialIndex(location)").unwrap();
+
+    database
+        .query("g.editV(3).property(location, 51.4545, -2.5879, point)")
+        .unwrap();
+    database.query("g.deleteV(2)").unwrap();
+
+    assert_eq!(
+        ids(
+            &database,
+            "g.V().hasPropertyNearest(location, 51.5, -0.12, 2)"
+        ),
+        [0, 3]
+    );
+}
+
+#[test]
+fn invalid_points_are_rejected() {
+    let database = database_with_cities();
+
+    assert!(matches!(
+        database.query("g.addV(city).property(location, 91, 0, point)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        database.query("g.addV(city).property(location, north, point)"),
+        Err(GraphDbError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        database.query("g.V().hasPropertyWithinRadius(location, 51.5, 200)"),
+        Err(GraphDbError::Parse { .. })
+    ));
+}
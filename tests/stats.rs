@@ -0,0 +1,124 @@
+mod common;
+
+use rust_graph_db::{
+    graph::stats::{DegreeStats, GraphStats, PropertyStats},
+    DataResult, Database,
+};
+
+use common::database_with_graph;
+
+fn graph_stats(database: &Database) -> GraphStats {
+    match database.query("g.stats()") {
+        Ok(DataResult::GraphStats(stats)) => stats,
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn counts_labels_properties_and_degrees() {
+    let database = database_with_graph("g");
+    for query in [
+        "g.addV(person).property(name, alice, string).property(age, 30, int32)",
+        "g.addV(person).property(name, bob, string).property(age, 31, int64)",
+        "g.addV(city).property(name, paris, string)",
+        "g.addV(city)",
+        "g.addE(knows, 0, 1)",
+        "g.addE(lives_in, 0, 2)",
+        "g.addE(lives_in, 1, 2)",
+    ] {
+        database.query(query).unwrap();
+    }
+
+    let mut stats = graph_stats(&database);
+    assert_eq!((stats.vertex_count, stats.edge_count), (4, 3));
+    assert_eq!(
+        stats.vertex_label_counts,
+        [("city".to_string(), 2), ("person".to_string(), 2)]
+    );
+    assert_eq!(
+        stats.edge_label_counts,
+        [("knows".to_string(), 1), ("lives_in".to_string(), 2)]
+    );
+
+    // Vertices aren't visited in id order, so neither are the types they hold
+    for property in &mut stats.property_stats {
+        property.types.sort();
+    }
+    assert_eq!(
+        stats.property_stats,
+        [
+            PropertyStats {
+                name: "age".to_string(),
+                count: 2,
+                types: vec!["int32", "int64"],
+            },
+            PropertyStats {
+                name: "name".to_string(),
+                count: 3,
+                types: vec!["string"],
+            },
+        ]
+    );
+    assert_eq!(
+        stats.degree,
+        DegreeStats {
+            min: 0,
+            max: 2,
+            mean: 1.5,
+        }
+    );
+    assert!(stats.approximate_memory_bytes > 0);
+}
+
+#[test]
+fn follows_removals_and_clears() {
+    let database = database_with_graph("g");
+    for query in [
+        "g.addV(person)",
+        "g.addV(robot)",
+        "g.addV(robot)",
+        "g.addE(builds, 0, 1)",
+        "g.deleteV(2)",
+    ] {
+        database.query(query).unwrap();
+    }
+
+    let stats = graph_stats(&database);
+    assert_eq!((stats.vertex_count, stats.edge_count), (2, 1));
+    assert_eq!(
+        stats.vertex_label_counts,
+        [("person".to_string(), 1), ("robot".to_string(), 1)]
+    );
+    assert_eq!(stats.edge_label_counts, [("builds".to_string(), 1)]);
+    assert_eq!((stats.degree.min, stats.degree.max), (1, 1));
+
+    database.query("g.deleteV(1)").unwrap();
+    let stats = graph_stats(&database);
+    assert_eq!((stats.vertex_count, stats.edge_count), (1, 0));
+    assert_eq!(stats.vertex_label_counts, [("person".to_string(), 1)]);
+    assert!(stats.edge_label_counts.is_empty());
+    assert_eq!((stats.degree.min, stats.degree.max), (0, 0));
+
+    database.query("clearGraph(g)").unwrap();
+    let stats = graph_stats(&database);
+    assert_eq!((stats.vertex_count, stats.edge_count), (0, 0));
+    assert!(stats.vertex_label_counts.is_empty());
+    assert_eq!(stats.degree.mean, 0.0);
+}
+
+#[test]
+fn json_output_is_keyed_by_name() {
+    let database = database_with_graph("g");
+    database
+        .query("g.addV(person).property(age, 30, int32)")
+        .unwrap();
+    database.query("g.addE(self, 0, 0)").unwrap();
+
+    let json = graph_stats(&database).to_json();
+    assert_eq!(json["vertex_count"], 1);
+    assert_eq!(json["vertex_labels"]["person"], 1);
+    assert_eq!(json["edge_labels"]["self"], 1);
+    assert_eq!(json["properties"]["age"]["count"], 1);
+    assert_eq!(json["properties"]["age"]["types"][0], "int32");
+    assert_eq!(json["degree"]["max"], 2);
+}
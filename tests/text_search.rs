@@ -0,0 +1,95 @@
+mod common;
+
+use rust_graph_db::{Database, GraphDbError};
+
+use common::{ids, sorted_ids};
+
+fn database_with_bios() -> Database {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    for (name, bio) in [
+        ("Alice Smith", "I love rust and graph databases"),
+        ("bob jones", "graph database in rust. rust rust"),
+        ("ALICIA", "databases of graphs"),
+    ] {
+        graph
+            .add_vertex("person")
+            .property("name", name)
+            .property("bio", bio)
+            .insert()
+            .unwrap();
+    }
+
+    database
+}
+
+#[test]
+fn string_predicates_respect_case() {
+    let database = database_with_bios();
+
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyStartsWith(name, Ali)"),
+        [0]
+    );
+    assert_eq!(
+        sorted_ids(
+            &database,
+            "g.V().hasPropertyStartsWithIgnoreCase(name, ali)"
+        ),
+        [0, 2]
+    );
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyEndsWith(name, jones)"),
+        [1]
+    );
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyValueIgnoreCase(name, alicia)"),
+        [2]
+    );
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyLikeIgnoreCase(name, SMITH)"),
+        [0]
+    );
+}
+
+#[test]
+fn regex_filters_and_rejects_bad_patterns() {
+    let database = database_with_bios();
+
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyRegex(name, (?i)^ali)"),
+        [0, 2]
+    );
+    assert_eq!(
+        sorted_ids(&database, "g.V().hasPropertyRegex(bio, rust\\.\\s+rust)"),
+        [1]
+    );
+    assert!(matches!(
+        database.query("g.V().hasPropertyRegex(name, a[)"),
+        Err(GraphDbError::Parse { .. })
+    ));
+}
+
+#[test]
+fn search_ranks_matches_and_follows_updates() {
+    let database = database_with_bios();
+    assert!(matches!(
+        database.query("g.search(bio, rust)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+
+    database.query("g.createTextIndex(bio)").unwrap();
+
+    // More occurrences in a shorter document rank higher
+    assert_eq!(ids(&database, "g.search(bio, rust)"), [1, 0]);
+    assert_eq!(ids(&database, "g.search(bio, \"graph database\")"), [1]);
+
+    database
+        .query("g.editV(1).property(bio, nothing here, string)")
+        .unwrap();
+    database
+        .query("g.addV(person).property(bio, Rust!, string)")
+        .unwrap();
+    database.query("g.deleteV(0)").unwrap();
+    assert_eq!(ids(&database, "g.search(bio, rust)"), [3]);
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use rust_graph_db::{Change, DataResult, GraphDbError, PropertyValue};
+
+use common::{database_with_graph, ids, property};
+
+#[test]
+fn before_triggers_set_properties_on_the_mutated_vertex() {
+    let database = database_with_graph("g");
+    database
+        .query("g.createTrigger(stamp, before, update, person).now(updated_at)")
+        .unwrap();
+    database
+        .query("g.createTrigger(defaults, before, insert, person).property(active, true, boolean)")
+        .unwrap();
+
+    database.query("g.addV(person)").unwrap();
+    database.query("g.addV(robot)").unwrap();
+    assert!(property(&database, 0, "updated_at").is_none());
+    assert_eq!(
+        property(&database, 0, "active"),
+        Some(PropertyValue::Boolean(true))
+    );
+    assert!(property(&database, 1, "active").is_none());
+
+    let edited = database
+        .query("g.editV(0).property(name, alice, string)")
+        .unwrap();
+    match edited {
+        DataResult::Vertex(vertex) => {
+            assert!(matches!(
+                vertex.get_property_value("updated_at"),
+                Some(PropertyValue::DateTime(ms)) if *ms > 0
+            ));
+            assert!(vertex.has_property("name"));
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn after_edge_triggers_maintain_counts_on_either_end() {
+    let database = database_with_graph("g");
+    database
+        .query("g.createTrigger(followed, after, insertEdge, follows).target(to).increment(follower_count, 1)")
+        .unwrap();
+    database
+        .query("g.createTrigger(unfollowed, after, deleteEdge, follows).target(to).increment(follower_count, -1)")
+        .unwrap();
+
+    for _ in 0..3 {
+        database.query("g.addV(person)").unwrap();
+    }
+    database.query("g.addE(follows, 0, 2)").unwrap();
+    database.query("g.addE(follows, 1, 2)").unwrap();
+    database.query("g.addE(likes, 1, 2)").unwrap();
+    assert_eq!(
+        property(&database, 2, "follower_count"),
+        Some(PropertyValue::Int64(2))
+    );
+
+    database.query("g.deleteV(0)").unwrap();
+    assert_eq!(
+        property(&database, 2, "follower_count"),
+        Some(PropertyValue::Int64(1))
+    );
+
+    // The followed vertex itself going needs no count
+    database.query("g.deleteV(2)").unwrap();
+}
+
+#[test]
+fn edge_edits_and_deletes_run_edge_triggers() {
+    let database = database_with_graph("g");
+    for query in [
+        "g.createTrigger(followed, after, insertEdge, follows).target(to).increment(follower_count, 1)",
+        "g.createTrigger(unfollowed, after, deleteEdge, follows).target(to).increment(follower_count, -1)",
+        "g.createTrigger(stamp, before, updateEdge, follows).now(updated_at)",
+        "g.createTrigger(edited, after, updateEdge, follows).target(from).increment(edits, 1)",
+        "g.createTrigger(guard, before, deleteEdge, follows).require(removable)",
+        "g.addV(person)",
+        "g.addV(person)",
+        "g.addV(person)",
+        "g.addE(follows, 0, 2)",
+        "g.addE(follows, 1, 2)",
+    ] {
+        database.query(query).unwrap();
+    }
+
+    match database.query("g.editE(0).property(removable, true, boolean)") {
+        Ok(DataResult::Edge(edge)) => {
+            assert!(edge.has_property("updated_at"));
+            assert!(edge.has_property("removable"));
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        property(&database, 0, "edits"),
+        Some(PropertyValue::Int64(1))
+    );
+
+    assert!(matches!(
+        database.query("g.deleteE(1)"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    database.query("g.deleteE(0)").unwrap();
+    assert_eq!(ids(&database, "g.V(2).in(follows)"), [1]);
+    assert_eq!(
+        property(&database, 2, "follower_count"),
+        Some(PropertyValue::Int64(1))
+    );
+    assert!(matches!(
+        database.query("g.deleteE(0)"),
+        Err(GraphDbError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn before_triggers_reject_mutations() {
+    let database = database_with_graph("g");
+    database
+        .query("g.createTrigger(named, before, insert, person).require(name)")
+        .unwrap();
+    database
+        .query("g.createTrigger(keep, before, delete, admin).reject(admins can't be deleted, ask an owner)")
+        .unwrap();
+
+    assert!(matches!(
+        database.query("g.addV(person)"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    database
+        .query("g.addV(person).property(name, alice, string)")
+        .unwrap();
+    database.query("g.addV(admin)").unwrap();
+
+    match database.query("g.deleteV(1)") {
+        Err(GraphDbError::ConstraintViolation(message)) => {
+            assert!(message.ends_with("admins can't be deleted, ask an owner"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    // Rejected mutations change nothing
+    assert!(database.graph("g").unwrap().vertex(1).is_ok());
+
+    database.query("g.dropTrigger(keep)").unwrap();
+    database.query("g.deleteV(1)").unwrap();
+}
+
+#[test]
+fn triggers_that_cannot_apply_are_refused() {
+    let database = database_with_graph("g");
+
+    for query in [
+        "g.createTrigger(t, after, insert, person).reject(no)",
+        "g.createTrigger(t, before, delete, person).now(deleted_at)",
+        "g.createTrigger(t, after, insertEdge, follows).increment(count, 1)",
+        "g.createTrigger(t, before, insert, person).target(to).now(created_at)",
+        "g.createTrigger(t, before, insert, person)",
+    ] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::InvalidArgument(_))),
+            "{}",
+            query
+        );
+    }
+
+    assert!(matches!(
+        database.query("g.createTrigger(t, during, insert, person).now(created_at)"),
+        Err(GraphDbError::Parse { .. })
+    ));
+
+    database
+        .query("g.createTrigger(t, before, insert, person).now(created_at)")
+        .unwrap();
+    assert!(matches!(
+        database.query("g.createTrigger(t, before, update, person).now(updated_at)"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    assert!(matches!(
+        database.query("g.triggers()"),
+        Ok(DataResult::StringVector(triggers)) if triggers == ["t: before insert person .now(created_at)"]
+    ));
+}
+
+#[test]
+fn after_triggers_change_the_vertex_along_with_the_mutation() {
+    let database = database_with_graph("g");
+    database
+        .query("g.createTrigger(created, after, insert, q).increment(n, 1)")
+        .unwrap();
+    database
+        .query("g.createTrigger(edited, after, update, q).increment(n, 10)")
+        .unwrap();
+    let changes = database.changes().subscribe(None).unwrap();
+
+    database.query("g.addV(q)").unwrap();
+    database
+        .query("g.editV(0).property(name, a, string)")
+        .unwrap();
+    assert_eq!(property(&database, 0, "n"), Some(PropertyValue::Int64(11)));
+
+    // One change each, already holding what the triggers set
+    let changes: Vec<Change> = changes.try_iter().map(|event| event.change).collect();
+    match changes.as_slice() {
+        [Change::VertexAdded { after: added, .. }, Change::VertexUpdated { after: updated, .. }] => {
+            assert_eq!(
+                added.get_property_value("n"),
+                Some(&PropertyValue::Int64(1))
+            );
+            assert_eq!(
+                updated.get_property_value("n"),
+                Some(&PropertyValue::Int64(11))
+            );
+            assert!(updated.has_property("name"));
+        }
+        other => panic!("Unexpected changes: {:?}", other),
+    }
+}
+
+#[test]
+fn failed_after_triggers_leave_the_graph_unchanged() {
+    let database = database_with_graph("g");
+    database
+        .query("g.createTrigger(counted, after, insert, q).increment(n, 1)")
+        .unwrap();
+    database
+        .query("g.createTrigger(edited, after, update, p).increment(n, 1)")
+        .unwrap();
+    database
+        .query("g.createTrigger(linked, after, insertEdge, e).target(to).increment(n, 1)")
+        .unwrap();
+
+    let mismatch = |query: &str| {
+        matches!(
+            database.query(query),
+            Err(GraphDbError::TypeMismatch { .. })
+        )
+    };
+    assert!(mismatch("g.addV(q).property(n, hello, string)"));
+    assert!(ids(&database, "g.V()").is_empty());
+
+    database.query("g.addV(p).property(n, 1, int32)").unwrap();
+    assert!(mismatch("g.editV(0).property(n, hello, string)"));
+    assert_eq!(property(&database, 0, "n"), Some(PropertyValue::Int32(1)));
+
+    database
+        .query("g.addV(p).property(n, hello, string)")
+        .unwrap();
+    assert!(mismatch("g.addE(e, 0, 1)"));
+    assert!(ids(&database, "g.V(0).out()").is_empty());
+    assert_eq!(
+        property(&database, 1, "n"),
+        Some(PropertyValue::String("hello".to_string()))
+    );
+}
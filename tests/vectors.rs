@@ -0,0 +1,145 @@
+mod common;
+
+use rust_graph_db::{Database, GraphDbError};
+
+use common::ids;
+
+const DIMENSION: usize = 16;
+
+fn vector_literal(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// Deterministic vectors, spread over the unit cube
+fn vectors(count: usize, seed: u32) -> Vec<Vec<f32>> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 8) as f32 / (1 << 24) as f32
+    };
+
+    (0..count)
+        .map(|_| (0..DIMENSION).map(|_| next()).collect())
+        .collect()
+}
+
+#[test]
+fn nearest_orders_by_metric_and_seeds_traversal() {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    let a = graph
+        .add_vertex("item")
+        .property("embedding", vec![1.0f32, 0.0])
+        .insert()
+        .unwrap();
+    let b = graph
+        .add_vertex("item")
+        .property("embedding", vec![0.0f32, 1.0])
+        .insert()
+        .unwrap();
+    let c = graph
+        .add_vertex("item")
+        .property("embedding", vec![3.0f32, 0.5])
+        .insert()
+        .unwrap();
+    let tag = graph.add_vertex("tag").insert().unwrap();
+    graph.add_edge("tagged", c, tag).insert().unwrap();
+
+    // c points almost the same way as a, but is further away
+    assert_eq!(
+        ids(&database, "g.V().nearest(embedding, [1, 0], 3, cosine)"),
+        [a, c, b]
+    );
+    assert_eq!(
+        ids(&database, "g.V().nearest(embedding, [1, 0], 3, l2)"),
+        [a, b, c]
+    );
+    assert_eq!(
+        ids(&database, "g.V().nearest(embedding, [1, 0], 1, dot)"),
+        [c]
+    );
+    assert_eq!(
+        ids(
+            &database,
+            "g.V().nearest(embedding, [1, 0], 1, dot).out(tagged)"
+        ),
+        [tag]
+    );
+}
+
+#[test]
+fn vector_lengths_are_fixed_per_property() {
+    let database = Database::new();
+    database.create_graph("g").unwrap();
+    database
+        .query("g.addV(item).property(embedding, [0.1, 0.2], vector)")
+        .unwrap();
+
+    assert!(matches!(
+        database.query("g.addV(item).property(embedding, [0.1, 0.2, 0.3], vector)"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    assert!(matches!(
+        database.query("g.V().nearest(embedding, [0.1], 1, cosine)"),
+        Err(GraphDbError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        database.query("g.V().nearest(embedding, [0.1, 0.2], 1, manhattan)"),
+        Err(GraphDbError::Parse { .. })
+    ));
+}
+
+#[test]
+fn index_finds_nearly_all_exact_neighbours() {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    let items = vectors(1000, 1);
+    for vector in &items {
+        graph
+            .add_vertex("item")
+            .property("embedding", vector.clone())
+            .insert()
+            .unwrap();
+    }
+
+    // Deletes and updates after the index is built leave stale links for searches to skip
+    database
+        .query("g.createVectorIndex(embedding, l2)")
+        .unwrap();
+    for id in 0..500 {
+        database.query(&format!("g.deleteV({})", id * 2)).unwrap();
+    }
+    for (id, vector) in items.iter().enumerate().take(250) {
+        database
+            .query(&format!(
+                "g.editV({}).property(embedding, {}, vector)",
+                id * 2 + 1,
+                vector_literal(vector)
+            ))
+            .unwrap();
+    }
+
+    let k = 10;
+    let mut found = 0;
+    for query in vectors(20, 2) {
+        let query = vector_literal(&query);
+        let approximate = ids(
+            &database,
+            &format!("g.V().nearest(embedding, {}, {}, l2)", query, k),
+        );
+        // Not the first step, so every vertex is scanned
+        let exact = ids(
+            &database,
+            &format!(
+                "g.V().hasLabel(item).nearest(embedding, {}, {}, l2)",
+                query, k
+            ),
+        );
+
+        found += approximate.iter().filter(|id| exact.contains(id)).count();
+    }
+
+    let recall = found as f64 / (20 * k) as f64;
+    assert!(recall >= 0.9, "recall was {}", recall);
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use rust_graph_db::{Binding, DataResult, Database, GraphDbError, PropertyValue};
+
+use common::{ids, table};
+
+/// Changes made in the same millisecond replace each other, so tests wait between them
+fn pause() {
+    thread::sleep(Duration::from_millis(5));
+}
+
+/// Valid from, valid to and change for each version of a vertex
+fn versions(database: &Database, id: usize) -> Vec<(i64, Option<i64>, String)> {
+    let rows = match database.query(&format!("g.history({})", id)) {
+        Ok(DataResult::Table(_, rows)) => rows,
+        other => panic!("Unexpected result: {:?}", other),
+    };
+
+    rows.into_iter()
+        .map(|row| match &row[..] {
+            [Binding::Value(PropertyValue::DateTime(from)), to, Binding::Value(PropertyValue::String(change)), _] => {
+                let to = match to {
+                    Binding::Value(PropertyValue::DateTime(to)) => Some(*to),
+                    _ => None,
+                };
+                (*from, to, change.clone())
+            }
+            other => panic!("Unexpected row: {:?}", other),
+        })
+        .collect()
+}
+
+fn age_as_of(database: &Database, timestamp: i64) -> Option<PropertyValue> {
+    match database.query(&format!("g.asOf({}).V(0).values(age)", timestamp)) {
+        Ok(DataResult::VertexValueVector(values)) => values.into_iter().next().flatten(),
+        Ok(DataResult::VertexVector(vertices)) if vertices.is_empty() => None,
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn overwritten_and_removed_vertices_can_still_be_seen() {
+    let database = Database::new();
+    database.create_graph("g").unwrap();
+    database.query("g.enableVersioning()").unwrap();
+    let events = database.changes().subscribe(None).unwrap();
+
+    database
+        .query("g.addV(person).property(age, 30, int32)")
+        .unwrap();
+    pause();
+    database
+        .query("g.editV(0).property(age, 31, int32)")
+        .unwrap();
+    pause();
+    database.query("g.deleteV(0)").unwrap();
+
+    let versions = versions(&database, 0);
+    let changes: Vec<&str> = versions
+        .iter()
+        .map(|(_, _, change)| change.as_str())
+        .collect();
+    assert_eq!(changes, ["added", "updated", "removed"]);
+    for pair in versions.windows(2) {
+        assert_eq!(pair[0].1, Some(pair[1].0));
+    }
+    assert_eq!(versions[2].1, None);
+
+    // Each version is valid from when its change was published
+    let published: Vec<i64> = events.try_iter().map(|event| event.timestamp).collect();
+    let from: Vec<i64> = versions.iter().map(|(from, _, _)| *from).collect();
+    assert_eq!(published, from);
+
+    let (added, updated, removed) = (versions[0].0, versions[1].0, versions[2].0);
+    assert_eq!(age_as_of(&database, added - 1), None);
+    assert_eq!(age_as_of(&database, added), Some(PropertyValue::Int32(30)));
+    assert_eq!(
+        age_as_of(&database, updated - 1),
+        Some(PropertyValue::Int32(30))
+    );
+    assert_eq!(
+        age_as_of(&database, updated),
+        Some(PropertyValue::Int32(31))
+    );
+    assert_eq!(age_as_of(&database, removed), None);
+    assert!(ids(&database, "g.V()").is_empty());
+}
+
+#[test]
+fn traversals_follow_edges_as_they_were() {
+    let database = Database::new();
+    let graph = database.create_graph("g").unwrap();
+    let alice = graph.add_vertex("person").insert().unwrap();
+    let bob = graph.add_vertex("person").insert().unwrap();
+    graph
+        .add_edge("knows", alice, bob)
+        .property("since", 2020i32)
+        .insert()
+        .unwrap();
+
+    // Everything there when versioning is enabled is its first version
+    database.query("g.enableVersioning()").unwrap();
+    let enabled = versions(&database, bob)[0].0;
+    pause();
+    graph.remove_vertex(bob).unwrap();
+    pause();
+    let carol = graph.add_vertex("person").insert().unwrap();
+    graph.add_edge("knows", alice, carol).insert().unwrap();
+    let now = versions(&database, carol)[0].0;
+
+    let query = |timestamp: i64| format!("g.asOf({}).V(0).out(knows)", timestamp);
+    assert_eq!(ids(&database, &query(enabled)), [bob]);
+    assert_eq!(ids(&database, &query(now)), [carol]);
+    assert_eq!(
+        ids(
+            &database,
+            &format!(
+                "g.asOf({}).V().outE(knows).hasPropertyValue(since, 2020).inV()",
+                enabled
+            )
+        ),
+        [bob]
+    );
+    let matched = |timestamp: i64| {
+        let query = format!("g.asOf({}).MATCH (a)-[:knows]->(b) RETURN b", timestamp);
+        table(&database, &query)
+            .1
+            .into_iter()
+            .map(|row| match &row[..] {
+                [Binding::Vertex(id, _)] => *id,
+                other => panic!("Unexpected row: {:?}", other),
+            })
+            .collect::<Vec<usize>>()
+    };
+    assert_eq!(matched(enabled), [bob]);
+    assert_eq!(matched(now), [carol]);
+
+    // Clearing the graph removes everything, but not its history
+    pause();
+    database.query("clearGraph(g)").unwrap();
+    assert!(ids(&database, "g.V()").is_empty());
+    assert_eq!(ids(&database, &query(now)), [carol]);
+    assert_eq!(versions(&database, alice).last().unwrap().2, "removed");
+}
+
+#[test]
+fn history_needs_versioning() {
+    let database = Database::new();
+    database.create_graph("g").unwrap();
+    database.query("g.addV(person)").unwrap();
+
+    for query in ["g.asOf(0).V()", "g.history(0)", "g.disableVersioning()"] {
+        assert!(
+            matches!(database.query(query), Err(GraphDbError::InvalidArgument(_))),
+            "{}",
+            query
+        );
+    }
+
+    database.query("g.enableVersioning()").unwrap();
+    assert!(matches!(
+        database.query("g.enableVersioning()"),
+        Err(GraphDbError::ConstraintViolation(_))
+    ));
+    assert!(matches!(
+        database.query("g.history(7)"),
+        Err(GraphDbError::NotFound { .. })
+    ));
+    database.query("g.disableVersioning()").unwrap();
+    assert!(database.query("g.history(0)").is_err());
+
+    for (query, position) in [
+        ("g.asOf(yesterday).V()", 7),
+        ("g.asOf(0)", 10),
+        ("g.asOf(0).addV(person)", 10),
+        ("g.asOf(0).V().hasLable(person)", 14),
+    ] {
+        match database.query(query) {
+            Err(GraphDbError::Parse {
+                position: found, ..
+            }) => {
+                assert_eq!(found, position, "{}", query)
+            }
+            other => panic!("Unexpected result for {}: {:?}", query, other),
+        }
+    }
+}